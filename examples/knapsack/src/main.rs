@@ -0,0 +1,218 @@
+// This example implements the 0/1 knapsack problem:
+// https://en.wikipedia.org/wiki/Knapsack_problem
+// using an evolutionary algorithm.
+//
+// Each item is either packed or not (a bit string). The total weight must not exceed the
+// capacity of the knapsack, this constraint is handled with two lexicographic objectives:
+// first minimize the weight above the capacity (the constraint violation), then maximize
+// the value of the packed items. Overweight offspring are repaired by unpacking items
+// until they fit (see enable_repair in main).
+
+extern crate rand;
+
+// Internal crates
+extern crate darwin_rs;
+
+use std::sync::Arc;
+
+use rand::Rng;
+
+// Internal modules
+use darwin_rs::individual::{Individual, FitnessGoal};
+use darwin_rs::operators::{BitString, BitFlip, UniformCrossover};
+use darwin_rs::simulation_builder;
+use darwin_rs::population_builder;
+
+// The penalty per unit of overweight for the scalar fitness
+const PENALTY: f64 = 100.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Item {
+    weight: f64,
+    value: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Knapsack {
+    items: Arc<Vec<Item>>,
+    capacity: f64,
+    packed: Vec<bool>,
+}
+
+impl Knapsack {
+    fn random(items: Arc<Vec<Item>>, capacity: f64, mut rng: &mut dyn Rng) -> Knapsack {
+        // Start with few items, so that most knapsacks are valid
+        let packed = items.iter().map(|_| Rng::gen_weighted_bool(&mut rng, 4)).collect();
+
+        Knapsack {
+            items: items,
+            capacity: capacity,
+            packed: packed,
+        }
+    }
+
+    fn packed_items(&self) -> Vec<&Item> {
+        self.items.iter().zip(&self.packed).filter(|&(_, packed)| *packed).map(|(item, _)| item).collect()
+    }
+
+    fn weight(&self) -> f64 {
+        self.packed_items().iter().map(|item| item.weight).sum()
+    }
+
+    fn value(&self) -> f64 {
+        self.packed_items().iter().map(|item| item.value).sum()
+    }
+
+    // The constraint violation: how much the knapsack is too heavy
+    fn overweight(&self) -> f64 {
+        (self.weight() - self.capacity).max(0.0)
+    }
+
+    // The packed item with the lowest value per weight
+    fn worst_packed(&self) -> Option<usize> {
+        (0..self.items.len())
+            .filter(|index| self.packed[*index])
+            .min_by(|a, b| {
+                let density = |index: usize| self.items[index].value / self.items[index].weight;
+                density(*a).partial_cmp(&density(*b)).unwrap()
+            })
+    }
+}
+
+// The generic bit string operators work on the packed flags
+impl BitString for Knapsack {
+    fn bits(&self) -> &[bool] {
+        &self.packed
+    }
+
+    fn bits_mut(&mut self) -> &mut [bool] {
+        &mut self.packed
+    }
+}
+
+// Implement trait functions mutate and calculate_fitness:
+impl Individual for Knapsack {
+    // Only a placeholder, the individuals are created by the initializer (see main)
+    fn new<S>(_data_source: S) -> Knapsack {
+        Knapsack {
+            items: Arc::new(Vec::new()),
+            capacity: 0.0,
+            packed: Vec::new(),
+        }
+    }
+
+    // Not used, since the population uses the BitFlip mutator
+    fn mutate(&mut self) {
+        let index = rand::thread_rng().gen_range(0, self.packed.len());
+        self.packed[index] = !self.packed[index];
+    }
+
+    // Penalty function: the value is maximized, so it is negated, every unit of overweight
+    // costs more than any item is worth
+    fn calculate_fitness(&self) -> f64 {
+        PENALTY * self.overweight() - self.value()
+    }
+
+    // Constraint first, then the value (see objective_goals in main)
+    fn calculate_fitness_multi(&self) -> Vec<f64> {
+        vec![self.overweight(), self.value()]
+    }
+
+    fn constraint_violation(&self) -> f64 {
+        self.overweight()
+    }
+
+    // Greedy repair: unpack the least valuable items (per weight) until the knapsack fits
+    fn repair(&mut self) -> bool {
+        let mut changed = false;
+
+        while self.overweight() > 0.0 {
+            match self.worst_packed() {
+                Some(index) => self.packed[index] = false,
+                None => break,
+            }
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+fn main() {
+    println!("Darwin test: knapsack problem");
+
+    let items = Arc::new(vec![
+        Item { weight: 23.0, value: 92.0 },
+        Item { weight: 31.0, value: 57.0 },
+        Item { weight: 29.0, value: 49.0 },
+        Item { weight: 44.0, value: 68.0 },
+        Item { weight: 53.0, value: 60.0 },
+        Item { weight: 38.0, value: 43.0 },
+        Item { weight: 63.0, value: 67.0 },
+        Item { weight: 85.0, value: 84.0 },
+        Item { weight: 89.0, value: 87.0 },
+        Item { weight: 82.0, value: 72.0 },
+        Item { weight: 12.0, value: 31.0 },
+        Item { weight: 47.0, value: 55.0 },
+        Item { weight: 71.0, value: 80.0 },
+        Item { weight: 17.0, value: 25.0 },
+        Item { weight: 58.0, value: 61.0 },
+        Item { weight: 36.0, value: 52.0 },
+        Item { weight: 94.0, value: 90.0 },
+        Item { weight: 27.0, value: 40.0 },
+        Item { weight: 65.0, value: 70.0 },
+        Item { weight: 41.0, value: 48.0 },
+    ]);
+    let capacity = 400.0;
+
+    let initializer_items = items.clone();
+
+    let population = population_builder::PopulationBuilder::<(), Knapsack>::new()
+        .set_id(1)
+        .set_data_source(())
+        .individuals(100)
+        .increasing_exp_mutation_rate(1.03)
+        .reset_limit_end(0) // disable the resetting of all individuals
+        .initializer(Box::new(move |rng| Knapsack::random(initializer_items.clone(), capacity, rng)))
+        .mutator(Box::new(BitFlip))
+        .crossover(Box::new(UniformCrossover), 0.3)
+        .enable_repair(true) // unpack items of overweight offspring, see Knapsack::repair
+        .finalize().unwrap();
+
+    let knapsack = simulation_builder::SimulationBuilder::<(), Knapsack>::new()
+        .iterations(1000)
+        .threads(2)
+        .lexicographic_objectives(2)
+        .objective_goals(vec![FitnessGoal::Minimize, FitnessGoal::Maximize])
+        .add_population(population)
+        .finalize();
+
+    match knapsack {
+        Err(e) => println!("Error: {}", e),
+        Ok(mut knapsack_simulation) => {
+            if let Err(e) = knapsack_simulation.run() {
+                println!("Error: {}", e);
+                return;
+            }
+
+            // An overweight of zero means that the solution is valid
+            if let Some(objectives) = knapsack_simulation.best_objectives() {
+                println!("overweight: {}, value: {}", objectives[0], objectives[1]);
+            }
+
+            let fittest = &knapsack_simulation.simulation_result.fittest[0].individual;
+
+            for (index, item) in items.iter().enumerate() {
+                if fittest.packed[index] {
+                    println!("item {}: weight: {}, value: {}", index, item.weight, item.value);
+                }
+            }
+
+            println!("total weight: {}, capacity: {}", fittest.weight(), capacity);
+            println!("repaired offspring: {}", knapsack_simulation.simulation_result.total_repairs);
+            println!("total run time: {} ms", knapsack_simulation.simulation_result.total_time_in_ms);
+            println!("number of iterations: {}",
+                     knapsack_simulation.simulation_result.iteration_counter);
+        }
+    }
+}
@@ -136,7 +136,7 @@ fn main() {
         Ok(mut tsp_simulation) => {
             // tsp_simulation.run();
             //
-            // println!("total run time: {} ms", tsp_simulation.total_time_in_ms);
+            // println!("total run time: {} ms", tsp_simulation.simulation_result.total_time_in_ms);
             // println!("improvement factor: {}", tsp_simulation.improvement_factor);
             // println!("number of iterations: {}", tsp_simulation.iteration_counter);
             //
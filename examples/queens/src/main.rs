@@ -188,7 +188,7 @@ fn main() {
                 println!("\n");
             }
 
-            println!("total run time: {} ms", queens_simulation.total_time_in_ms);
+            println!("total run time: {} ms", queens_simulation.simulation_result.total_time_in_ms);
             println!("improvement factor: {}",
                      queens_simulation.simulation_result.improvement_factor);
             println!("number of iterations: {}",
@@ -191,7 +191,7 @@ fn main() {
                 println!("\n");
             }
 
-            println!("total run time: {} ms", sudoku_simulation.total_time_in_ms);
+            println!("total run time: {} ms", sudoku_simulation.simulation_result.total_time_in_ms);
             println!("improvement factor: {}",
                 sudoku_simulation.simulation_result.improvement_factor);
             println!("number of iterations: {}",
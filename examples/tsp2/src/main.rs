@@ -156,7 +156,7 @@ fn main() {
                 println!("{} {}", x, y);
             }
 
-            println!("total run time: {} ms", tsp_simulation.total_time_in_ms);
+            println!("total run time: {} ms", tsp_simulation.simulation_result.total_time_in_ms);
             println!("improvement factor: {}",
                 tsp_simulation.simulation_result.improvement_factor);
             println!("number of iterations: {}",
@@ -0,0 +1,167 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! A minimal executor for `Individual::calculate_fitness_async`, only available with the
+//! `async` feature. It runs on the calling thread and overlaps the waiting time of the fitness
+//! calculations (for example requests to a remote simulator), it does not add any threads.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// Wakes the thread that runs the futures, see `join_bounded`.
+struct ThreadWaker {
+    /// The thread that polls the futures.
+    thread: Thread,
+}
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.thread.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.thread.unpark();
+    }
+}
+
+/// Runs all the given futures on the current thread until they are finished, with at most
+/// `max_concurrent` of them started at the same time (at least one). Returns their outputs in
+/// the order of the futures. Once `cancel` is set no more futures are started, these ones
+/// return None. The futures that are already running are still finished. A panic in a future
+/// is caught and returned as its output (`Err`), the other futures keep running.
+pub fn join_bounded<F: Future>(futures: Vec<F>, max_concurrent: usize, cancel: &AtomicBool)
+        -> Vec<Option<thread::Result<F::Output>>> {
+    let max_concurrent = max_concurrent.max(1);
+    let waker = Waker::from(Arc::new(ThreadWaker { thread: thread::current() }));
+    let mut context = Context::from_waker(&waker);
+
+    let mut outputs: Vec<Option<thread::Result<F::Output>>> = futures.iter().map(|_| None).collect();
+    let mut waiting = futures.into_iter().enumerate();
+    let mut running: Vec<(usize, Pin<Box<F>>)> = Vec::new();
+    let mut cancelled = false;
+
+    loop {
+        cancelled |= cancel.load(Ordering::Relaxed);
+
+        while !cancelled && running.len() < max_concurrent {
+            match waiting.next() {
+                Some((index, future)) => running.push((index, Box::pin(future))),
+                None => break,
+            }
+        }
+
+        if running.is_empty() {
+            return outputs;
+        }
+
+        let before = running.len();
+
+        running.retain_mut(|&mut (index, ref mut future)| {
+            match panic::catch_unwind(AssertUnwindSafe(|| future.as_mut().poll(&mut context))) {
+                Ok(Poll::Ready(output)) => {
+                    outputs[index] = Some(Ok(output));
+                    false
+                }
+                Ok(Poll::Pending) => true,
+                Err(payload) => {
+                    outputs[index] = Some(Err(payload));
+                    false
+                }
+            }
+        });
+
+        // Nothing is finished, sleep until one of the futures is woken up. A wake up before
+        // this call is not lost, `park` returns immediately then.
+        if running.len() == before {
+            thread::park();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::{self, Future};
+    use std::pin::Pin;
+    use std::sync::atomic::AtomicBool;
+    use std::task::{Context, Poll};
+    use super::join_bounded;
+
+    /// Is pending a given number of times before it returns its value.
+    struct Delayed {
+        value: u32,
+        polls: u32,
+    }
+
+    impl Future for Delayed {
+        type Output = u32;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<u32> {
+            if self.polls == 0 {
+                Poll::Ready(self.value)
+            } else {
+                self.polls -= 1;
+                context.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Returns the outputs of `join_bounded`, panics are None as well.
+    fn join_outputs<F: Future>(futures: Vec<F>, max_concurrent: usize, cancel: bool) -> Vec<Option<F::Output>> {
+        join_bounded(futures, max_concurrent, &AtomicBool::new(cancel)).into_iter()
+            .map(|output| output.and_then(|result| result.ok()))
+            .collect()
+    }
+
+    /// Panics when it is polled.
+    struct Panicking;
+
+    impl Future for Panicking {
+        type Output = u32;
+
+        fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<u32> {
+            panic!("Fitness calculation failed");
+        }
+    }
+
+    #[test]
+    fn join_bounded_in_order() {
+        let futures = (0..10).map(|value| Delayed { value: value, polls: 10 - value }).collect();
+
+        assert_eq!(join_outputs(futures, 3, false), (0..10).map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn join_bounded_cancelled() {
+        assert_eq!(join_outputs(vec![future::ready(1), future::ready(2)], 1, true), vec![None, None]);
+    }
+
+    #[test]
+    fn join_bounded_panicked() {
+        // The second future panics, the others still finish
+        let futures: Vec<Pin<Box<dyn Future<Output = u32>>>> = vec![
+            Box::pin(Delayed { value: 0, polls: 2 }),
+            Box::pin(Panicking),
+            Box::pin(Delayed { value: 2, polls: 2 }),
+            Box::pin(Delayed { value: 3, polls: 2 }),
+        ];
+        let outputs = join_bounded(futures, 2, &AtomicBool::new(false));
+
+        assert!(outputs[1].as_ref().unwrap().is_err());
+        assert_eq!(outputs.into_iter().map(|output| output.unwrap().ok()).collect::<Vec<_>>(),
+            vec![Some(0), None, Some(2), Some(3)]);
+    }
+}
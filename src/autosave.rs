@@ -0,0 +1,88 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Only available with the `serde` feature.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json;
+
+use individual::{Individual, IndividualWrapper};
+
+/// The `Autosave` type. Writes the fittest individual of the simulation to a file (as JSON),
+/// so that a crash in a long simulation does not lose the best solution found so far.
+/// Use `SimulationBuilder::autosave_best` to enable it.
+pub struct Autosave<T> {
+    /// The file the fittest individual is written to.
+    pub path: PathBuf,
+    /// If true the fittest individual is written every time it improves, otherwise only at
+    /// the end of the simulation.
+    pub on_improvement: bool,
+    /// The fitness of the last saved individual.
+    pub saved_fitness: f64,
+    /// Serializes the individual, created where `T: Serialize` is known.
+    write: WriteFn<T>,
+}
+
+/// Writes the individual to the file.
+type WriteFn<T> = Box<dyn Fn(&T, &mut File) -> io::Result<()> + Send + Sync>;
+
+impl<T: Individual> Autosave<T> {
+    /// Creates a new autosave for the given path.
+    pub fn new<P: AsRef<Path>>(path: P, on_improvement: bool) -> Autosave<T> where T: Serialize {
+        Autosave {
+            path: path.as_ref().to_path_buf(),
+            on_improvement,
+            saved_fitness: f64::MAX,
+            write: Box::new(|individual, file| {
+                serde_json::to_writer_pretty(file, individual).map_err(io::Error::from)
+            }),
+        }
+    }
+
+    /// Called after each iteration: saves the fittest individual if it has improved since the
+    /// last save (and `on_improvement` is set).
+    pub fn iteration_done(&mut self, fittest: &IndividualWrapper<T>) {
+        if self.on_improvement && fittest.fitness < self.saved_fitness {
+            self.save(fittest);
+        }
+    }
+
+    /// Called at the end of the simulation: saves the fittest individual if it not already
+    /// has been saved.
+    pub fn simulation_done(&mut self, fittest: &IndividualWrapper<T>) {
+        if fittest.fitness < self.saved_fitness {
+            self.save(fittest);
+        }
+    }
+
+    /// Writes the individual into a temporary file first, syncs it to the disk and then
+    /// renames it, so that the file is never left half written, not even after a crash.
+    fn save(&mut self, fittest: &IndividualWrapper<T>) {
+        let tmp_path = self.path.with_extension("tmp");
+
+        let result = File::create(&tmp_path)
+            .and_then(|mut file| {
+                (self.write)(&fittest.individual, &mut file)?;
+                file.flush()?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &self.path));
+
+        match result {
+            Ok(_) => self.saved_fitness = fittest.fitness,
+            Err(e) => log_info!("Could not save fittest individual to {}: {}", self.path.display(), e)
+        }
+    }
+}
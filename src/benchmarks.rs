@@ -0,0 +1,519 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Standard test functions with a known global optimum, ready to use as individuals.
+//! They ignore the data source, so any data source (for example `()`) can be used.
+
+use std::f64::consts::PI;
+
+use rand::{self, Rng};
+
+use individual::{Individual, structural_hash};
+use operators::BitString;
+
+/// The number of dimensions of the real vector functions and the number of bits for `OneMax`.
+pub const DIMENSION: usize = 10;
+
+/// Creates a random vector with all values in the range [low, high).
+fn random_vector(low: f64, high: f64) -> Vec<f64> {
+    let mut rng = rand::thread_rng();
+
+    (0..DIMENSION).map(|_| rng.gen_range(low, high)).collect()
+}
+
+/// Changes one random value of the vector. The step size is chosen randomly between 10% and
+/// 0.1% of the range, so that the vector can move quickly and be fine tuned as well.
+fn mutate_vector<R: Rng>(x: &mut [f64], low: f64, high: f64, rng: &mut R) {
+    let index = rng.gen_range(0, x.len());
+    let step = (high - low) * 10.0_f64.powi(-rng.gen_range(1, 4));
+
+    x[index] = (x[index] + rng.gen_range(-step, step)).max(low).min(high);
+}
+
+/// The sphere function: sum(x_i^2), x_i in [-5.12, 5.12].
+/// Global optimum: 0.0 at x_i = 0.0
+#[derive(Debug,Clone)]
+pub struct Sphere {
+    /// The position, one value per dimension.
+    pub x: Vec<f64>,
+}
+
+impl Sphere {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        0.0
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<f64> {
+        vec![0.0; DIMENSION]
+    }
+}
+
+impl Individual for Sphere {
+    fn new<S>(_data_source: S) -> Sphere {
+        Sphere { x: random_vector(-5.12, 5.12) }
+    }
+
+    fn mutate(&mut self) {
+        mutate_vector(&mut self.x, -5.12, 5.12, &mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        mutate_vector(&mut self.x, -5.12, 5.12, &mut rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        self.x.iter().map(|x| x * x).sum()
+    }
+}
+
+/// The Rastrigin function: 10 n + sum(x_i^2 - 10 cos(2 pi x_i)), x_i in [-5.12, 5.12].
+/// It has many local minima. Global optimum: 0.0 at x_i = 0.0
+#[derive(Debug,Clone)]
+pub struct Rastrigin {
+    /// The position, one value per dimension.
+    pub x: Vec<f64>,
+}
+
+impl Rastrigin {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        0.0
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<f64> {
+        vec![0.0; DIMENSION]
+    }
+}
+
+impl Individual for Rastrigin {
+    fn new<S>(_data_source: S) -> Rastrigin {
+        Rastrigin { x: random_vector(-5.12, 5.12) }
+    }
+
+    fn mutate(&mut self) {
+        mutate_vector(&mut self.x, -5.12, 5.12, &mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        mutate_vector(&mut self.x, -5.12, 5.12, &mut rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        10.0 * (self.x.len() as f64) +
+            self.x.iter().map(|x| x * x - 10.0 * (2.0 * PI * x).cos()).sum::<f64>()
+    }
+}
+
+/// The Ackley function, x_i in [-32.768, 32.768].
+/// It is nearly flat far away from the optimum. Global optimum: 0.0 at x_i = 0.0
+#[derive(Debug,Clone)]
+pub struct Ackley {
+    /// The position, one value per dimension.
+    pub x: Vec<f64>,
+}
+
+impl Ackley {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        0.0
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<f64> {
+        vec![0.0; DIMENSION]
+    }
+}
+
+impl Individual for Ackley {
+    fn new<S>(_data_source: S) -> Ackley {
+        Ackley { x: random_vector(-32.768, 32.768) }
+    }
+
+    fn mutate(&mut self) {
+        mutate_vector(&mut self.x, -32.768, 32.768, &mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        mutate_vector(&mut self.x, -32.768, 32.768, &mut rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        let n = self.x.len() as f64;
+        let sum_squares = self.x.iter().map(|x| x * x).sum::<f64>();
+        let sum_cos = self.x.iter().map(|x| (2.0 * PI * x).cos()).sum::<f64>();
+
+        // Rounding errors may give a tiny negative value at the optimum
+        (-20.0 * (-0.2 * (sum_squares / n).sqrt()).exp() - (sum_cos / n).exp() + 20.0 + 1.0_f64.exp())
+            .max(0.0)
+    }
+}
+
+/// The Rosenbrock function: sum(100 (x_i+1 - x_i^2)^2 + (1 - x_i)^2), x_i in [-5.0, 10.0].
+/// The optimum lies in a long, narrow valley. Global optimum: 0.0 at x_i = 1.0
+#[derive(Debug,Clone)]
+pub struct Rosenbrock {
+    /// The position, one value per dimension.
+    pub x: Vec<f64>,
+}
+
+impl Rosenbrock {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        0.0
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<f64> {
+        vec![1.0; DIMENSION]
+    }
+}
+
+impl Individual for Rosenbrock {
+    fn new<S>(_data_source: S) -> Rosenbrock {
+        Rosenbrock { x: random_vector(-5.0, 10.0) }
+    }
+
+    fn mutate(&mut self) {
+        mutate_vector(&mut self.x, -5.0, 10.0, &mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        mutate_vector(&mut self.x, -5.0, 10.0, &mut rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        self.x.windows(2)
+            .map(|x| 100.0 * (x[1] - x[0] * x[0]).powi(2) + (1.0 - x[0]).powi(2))
+            .sum()
+    }
+}
+
+/// OneMax: maximize the number of ones in a bitstring. Since the fitness is minimized, the
+/// fitness is the number of zeros. Global optimum: 0.0, all bits set.
+#[derive(Debug,Clone)]
+pub struct OneMax {
+    /// The bitstring.
+    pub bits: Vec<bool>,
+}
+
+impl OneMax {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        0.0
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<bool> {
+        vec![true; DIMENSION]
+    }
+}
+
+impl BitString for OneMax {
+    fn bits(&self) -> &[bool] {
+        &self.bits
+    }
+
+    fn bits_mut(&mut self) -> &mut [bool] {
+        &mut self.bits
+    }
+}
+
+impl Individual for OneMax {
+    fn new<S>(_data_source: S) -> OneMax {
+        let mut rng = rand::thread_rng();
+
+        OneMax { bits: (0..DIMENSION).map(|_| rng.gen()).collect() }
+    }
+
+    fn mutate(&mut self) {
+        self.mutate_with_rng(&mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        let index = Rng::gen_range(&mut rng, 0, self.bits.len());
+        self.bits[index] = !self.bits[index];
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        self.bits.iter().filter(|bit| !**bit).count() as f64
+    }
+
+    fn signature(&self) -> Option<u64> {
+        Some(structural_hash(&self.bits))
+    }
+}
+
+/// The number of cities of `Tsp`.
+pub const CITIES: usize = 50;
+
+/// A travelling salesman problem with the cities evenly spaced on the unit circle, the
+/// fitness is the length of the round trip. It is meant for benchmarks of the framework
+/// itself: the tour is a `Vec` that is recycled with `Individual::reuse_from`.
+/// Global optimum: 2 n sin(pi / n), visiting the cities in order around the circle.
+#[derive(Debug,Clone)]
+pub struct Tsp {
+    /// The order in which the cities are visited.
+    pub path: Vec<usize>,
+}
+
+impl Tsp {
+    /// The fitness of the global optimum.
+    pub fn global_optimum() -> f64 {
+        let n = CITIES as f64;
+
+        2.0 * n * (PI / n).sin()
+    }
+
+    /// The position of the global optimum.
+    pub fn optimum_position() -> Vec<usize> {
+        (0..CITIES).collect()
+    }
+
+    /// The position of the given city on the unit circle.
+    fn city(index: usize) -> (f64, f64) {
+        let angle = 2.0 * PI * (index as f64) / (CITIES as f64);
+
+        (angle.cos(), angle.sin())
+    }
+}
+
+impl Individual for Tsp {
+    fn new<S>(_data_source: S) -> Tsp {
+        let mut path = Tsp::optimum_position();
+        rand::thread_rng().shuffle(&mut path);
+
+        Tsp { path }
+    }
+
+    fn mutate(&mut self) {
+        self.mutate_with_rng(&mut rand::thread_rng());
+    }
+
+    /// Reverses a random part of the tour (a 2-opt move).
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        let first = Rng::gen_range(&mut rng, 0, self.path.len());
+        let second = Rng::gen_range(&mut rng, 0, self.path.len());
+
+        self.path[first.min(second)..first.max(second) + 1].reverse();
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        let mut length = 0.0;
+
+        for (index, city) in self.path.iter().enumerate() {
+            let (x1, y1) = Tsp::city(*city);
+            let (x2, y2) = Tsp::city(self.path[(index + 1) % self.path.len()]);
+            length += ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+        }
+
+        length
+    }
+
+    fn reuse_from(&mut self, source: &Tsp) {
+        self.path.clone_from(&source.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+    use std::time::Instant;
+
+    use individual::Individual;
+    use operators::{BitFlip, UniformCrossover};
+    use population_builder::PopulationBuilder;
+    use simulation_builder::SimulationBuilder;
+    use super::{Sphere, Rastrigin, Ackley, Rosenbrock, OneMax, Tsp, DIMENSION};
+
+    /// Runs a small seeded simulation that starts with mutated copies of `start` and returns
+    /// the best fitness. It gives the same result every time.
+    fn best_fitness<T: Individual + Send + Sync + Clone>(start: T, iterations: u32) -> f64 {
+        let population = PopulationBuilder::<(), T>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutation_seed(1)
+            .seed_individuals(vec![start], 5)
+            .reset_limit_end(0)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), T>::new()
+            .iterations(iterations)
+            .threads(2)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+        simulation.simulation_result.fittest[0].fitness
+    }
+
+    #[test]
+    fn optimum_positions() {
+        assert_eq!(Sphere { x: Sphere::optimum_position() }.calculate_fitness(), Sphere::global_optimum());
+        assert_eq!(Rastrigin { x: Rastrigin::optimum_position() }.calculate_fitness(), Rastrigin::global_optimum());
+        assert!(Ackley { x: Ackley::optimum_position() }.calculate_fitness() < 1.0e-12);
+        assert_eq!(Rosenbrock { x: Rosenbrock::optimum_position() }.calculate_fitness(), Rosenbrock::global_optimum());
+        assert_eq!(OneMax { bits: OneMax::optimum_position() }.calculate_fitness(), OneMax::global_optimum());
+        assert!((Tsp { path: Tsp::optimum_position() }.calculate_fitness() - Tsp::global_optimum()).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn sphere_converges() {
+        assert!(best_fitness(Sphere { x: vec![2.0; DIMENSION] }, 1000) < 0.1);
+    }
+
+    #[test]
+    fn one_max_converges() {
+        assert_eq!(best_fitness(OneMax { bits: vec![false; DIMENSION] }, 200), OneMax::global_optimum());
+    }
+
+    #[test]
+    fn one_max_bit_string_operators() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutation_seed(1)
+            .seed_individuals(vec![OneMax { bits: vec![false; DIMENSION] }], 5)
+            .reset_limit_end(0)
+            .mutator(Box::new(BitFlip))
+            .crossover(Box::new(UniformCrossover), 0.3)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(200)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+    }
+
+    /// Returns the run time in milliseconds of a TSP simulation with two populations of very
+    /// different sizes (10 and 1000 individuals).
+    fn uneven_populations_time(threads: usize, iterations: u32) -> f64 {
+        let mut builder = SimulationBuilder::<(), Tsp>::new()
+            .iterations(iterations)
+            .threads(threads);
+
+        for &(id, individuals) in &[(1, 10), (2, 1000)] {
+            builder = builder.add_population(PopulationBuilder::<(), Tsp>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(individuals)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        let start = Instant::now();
+        simulation.run().unwrap();
+        let elapsed = start.elapsed();
+
+        elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1000_000.0
+    }
+
+    /// Benchmark: the individuals of all populations share one work stealing pool, so a tiny
+    /// and a huge population together still use all cores. With one thread per population
+    /// the speedup would be at most about 1.0, since the huge population does nearly all the
+    /// work. Run it with `cargo test --release uneven_populations -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn uneven_populations() {
+        let cores = thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1);
+        let single = uneven_populations_time(1, 20);
+        let shared = uneven_populations_time(cores, 20);
+
+        println!("populations of 10 and 1000 individuals, 1 thread: {} ms, {} threads: {} ms, speedup: {}",
+            single, cores, shared, single / shared);
+    }
+
+    /// Returns the run time in ms of a large TSP simulation with one population per core,
+    /// each one pinned to its core or not.
+    #[cfg(feature = "core_affinity")]
+    fn pinned_populations_time(pinned: bool, iterations: u32) -> f64 {
+        let cores = thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1);
+        let mut builder = SimulationBuilder::<(), Tsp>::new()
+            .iterations(iterations)
+            .threads(cores);
+
+        for core in 0..cores {
+            builder = builder.add_population(PopulationBuilder::<(), Tsp>::new()
+                .set_data_source(())
+                .set_id(core as u32)
+                .individuals(200)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .cpu_affinity(if pinned { Some(core) } else { None })
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        let start = Instant::now();
+        simulation.run().unwrap();
+        let elapsed = start.elapsed();
+
+        elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1000_000.0
+    }
+
+    /// Benchmark: the run time with each population pinned to its own core against the work
+    /// stealing pool, which may move the individuals between cores every iteration.
+    /// Run it with `cargo test --release --features core_affinity pinned_populations -- --ignored --nocapture`.
+    #[cfg(feature = "core_affinity")]
+    #[test]
+    #[ignore]
+    fn pinned_populations() {
+        let shared = pinned_populations_time(false, 50);
+        let pinned = pinned_populations_time(true, 50);
+
+        println!("TSP with one population per core, shared pool: {} ms, pinned: {} ms, speedup: {}",
+            shared, pinned, shared / pinned);
+    }
+
+    /// Returns the number of fitness evaluations of a TSP simulation with a high survival
+    /// rate, where every other individual is not mutated.
+    fn elitist_evaluations(dirty_tracking: bool) -> u64 {
+        let population = PopulationBuilder::<(), Tsp>::new()
+            .set_data_source(())
+            .individuals(50)
+            .mutation_rate((0..50).map(|index| (index % 2) as u32).collect())
+            .survival_rate(0.9)
+            .dirty_tracking(dirty_tracking)
+            .reset_limit_end(0)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Tsp>::new()
+            .iterations(200)
+            .threads(1)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+        simulation.simulation_result.fitness_evaluations
+    }
+
+    /// Benchmark: the fitness evaluations saved by dirty tracking when most individuals
+    /// survive unchanged. Run it with `cargo test --release tsp_dirty_tracking -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn tsp_dirty_tracking() {
+        let all = elitist_evaluations(false);
+        let dirty = elitist_evaluations(true);
+
+        println!("TSP fitness evaluations, all: {}, dirty only: {} ({:.0}% fewer)",
+            all, dirty, 100.0 * (1.0 - dirty as f64 / all as f64));
+        assert!(dirty < all);
+    }
+}
@@ -0,0 +1,448 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//!
+
+use std::fmt;
+use std::thread;
+
+use jobsteal::{make_pool, IntoSplitIterator, SplitIterator};
+
+use individual::{Individual, IndividualWrapper};
+use simulation::Simulation;
+use statistics::{mean, standard_deviation};
+
+/// The `RunSummary` type. The outcome of one single run of an experiment.
+#[derive(Debug,Clone)]
+pub struct RunSummary {
+    /// The best fitness reached in this run.
+    pub best_fitness: f64,
+    /// The number of iterations of this run.
+    pub iteration_counter: u32,
+    /// The number of fitness evaluations of this run.
+    pub fitness_evaluations: u64,
+    /// The run time in milliseconds.
+    pub total_time_in_ms: f64,
+}
+
+impl RunSummary {
+    /// Creates the summary of a finished simulation.
+    pub fn new<S: Send + Sync, T: Individual + Send + Sync>(simulation: &Simulation<S, T>) -> RunSummary {
+        RunSummary {
+            best_fitness: simulation.simulation_result.fittest[0].fitness,
+            iteration_counter: simulation.simulation_result.iteration_counter,
+            fitness_evaluations: simulation.simulation_result.fitness_evaluations,
+            total_time_in_ms: simulation.simulation_result.total_time_in_ms,
+        }
+    }
+}
+
+/// The `ExperimentResult` type. Aggregates the results of running the same simulation
+/// configuration several times, see `run_experiment`.
+#[derive(Debug,Clone)]
+pub struct ExperimentResult {
+    /// The results of all the successfully finished runs.
+    pub runs: Vec<RunSummary>,
+    /// The number of runs that returned an error (for example a panic in a worker thread).
+    pub failed_runs: u32,
+    /// The mean of the best fitness of all runs.
+    pub mean_fitness: f64,
+    /// The standard deviation of the best fitness of all runs.
+    pub std_fitness: f64,
+    /// The lowest (best) fitness of all runs.
+    pub min_fitness: f64,
+    /// The highest (worst) fitness of all runs.
+    pub max_fitness: f64,
+}
+
+impl ExperimentResult {
+    /// Creates the aggregated result from the single runs.
+    pub fn new(runs: Vec<RunSummary>, failed_runs: u32) -> ExperimentResult {
+        let fitness: Vec<f64> = runs.iter().map(|run| run.best_fitness).collect();
+
+        ExperimentResult {
+            mean_fitness: mean(&fitness),
+            std_fitness: standard_deviation(&fitness),
+            min_fitness: fitness.iter().cloned().fold(f64::MAX, f64::min),
+            max_fitness: fitness.iter().cloned().fold(f64::MIN, f64::max),
+            runs,
+            failed_runs,
+        }
+    }
+
+    /// The fraction (0.0 - 1.0) of the runs that reached the given target fitness
+    /// (best fitness <= target_fitness). Failed runs count as unsuccessful.
+    pub fn success_rate(&self, target_fitness: f64) -> f64 {
+        let total = self.runs.len() + self.failed_runs as usize;
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        let successful = self.runs.iter().filter(|run| run.best_fitness <= target_fitness).count();
+
+        successful as f64 / total as f64
+    }
+
+    /// Exports all the runs as CSV, one line per run with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("run,best_fitness,iterations,fitness_evaluations,time_in_ms\n");
+
+        for (index, run) in self.runs.iter().enumerate() {
+            csv.push_str(&format!("{},{},{},{},{}\n", index + 1, run.best_fitness,
+                run.iteration_counter, run.fitness_evaluations, run.total_time_in_ms));
+        }
+
+        csv
+    }
+}
+
+impl fmt::Display for ExperimentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "runs: {}, failed: {}, fitness mean: {}, std: {}, min: {}, max: {}",
+            self.runs.len(), self.failed_runs, self.mean_fitness, self.std_fitness,
+            self.min_fitness, self.max_fitness)
+    }
+}
+
+/// Runs the same simulation configuration `repeats` times and aggregates the results.
+/// `builder_fn` is called for every run with the index of the run (0 to `repeats` - 1) and
+/// must return a freshly configured simulation (with new populations, so each run starts
+/// from new random individuals). With seeded populations (see
+/// `PopulationBuilder::mutation_seed`) derive the seeds from the index, otherwise all runs
+/// are the same.
+pub fn run_experiment<S, T, F>(mut builder_fn: F, repeats: u32) -> ExperimentResult
+    where S: Send + Sync, T: Individual + Send + Sync + Clone, F: FnMut(u32) -> Simulation<S, T> {
+    let mut runs = Vec::with_capacity(repeats as usize);
+    let mut failed_runs = 0;
+
+    for run in 0..repeats {
+        let mut simulation = builder_fn(run);
+
+        match simulation.run() {
+            Ok(_) => runs.push(RunSummary::new(&simulation)),
+            Err(e) => {
+                log_info!("experiment run {} failed: {}", run + 1, e);
+                failed_runs += 1;
+            }
+        }
+    }
+
+    ExperimentResult::new(runs, failed_runs)
+}
+
+/// The `RestartResult` type. The outcome of `run_with_restarts`.
+#[derive(Debug,Clone)]
+pub struct RestartResult<T: Individual> {
+    /// The results of all the successfully finished restarts.
+    pub runs: Vec<RunSummary>,
+    /// The number of restarts that returned an error.
+    pub failed_runs: u32,
+    /// The fittest individual of all restarts, None if all restarts failed.
+    pub best: Option<IndividualWrapper<T>>,
+}
+
+/// Splits the iteration budget `total_iterations` evenly across `restarts` independent runs
+/// and returns the fittest individual of all of them. Many short runs often do better than
+/// one long run that converges prematurely. `builder_fn` is called for every restart with
+/// the number of iterations of that restart and must return a freshly configured simulation
+/// that uses it (for example with `SimulationBuilder::iterations`).
+pub fn run_with_restarts<S, T, F>(mut builder_fn: F, total_iterations: u32, restarts: u32) -> RestartResult<T>
+    where S: Send + Sync, T: Individual + Send + Sync + Clone, F: FnMut(u32) -> Simulation<S, T> {
+    let restarts = restarts.max(1);
+    let iterations = (total_iterations / restarts).max(1);
+    let mut result = RestartResult { runs: Vec::new(), failed_runs: 0, best: None };
+
+    for restart in 0..restarts {
+        let mut simulation = builder_fn(iterations);
+
+        match simulation.run() {
+            Ok(_) => {
+                result.runs.push(RunSummary::new(&simulation));
+
+                let fittest = &simulation.simulation_result.fittest[0];
+
+                if result.best.as_ref().map_or(true, |best| fittest < best) {
+                    result.best = Some(fittest.clone());
+                }
+            }
+            Err(e) => {
+                log_info!("restart {} failed: {}", restart + 1, e);
+                result.failed_runs += 1;
+            }
+        }
+    }
+
+    result
+}
+
+/// The `ParamSet` type. One combination of parameter values of a `ParamGrid`.
+#[derive(Debug,Clone,PartialEq)]
+pub struct ParamSet {
+    /// The parameter names and their values, in the order they were added to the grid.
+    pub values: Vec<(String, f64)>,
+}
+
+impl ParamSet {
+    /// Returns the value of the parameter with the given name.
+    /// Panics if the grid has no parameter with that name.
+    pub fn get(&self, name: &str) -> f64 {
+        self.values.iter().find(|value| value.0 == name)
+            .unwrap_or_else(|| panic!("Unknown parameter: {}", name)).1
+    }
+}
+
+impl fmt::Display for ParamSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<String> = self.values.iter().map(|value| format!("{}: {}", value.0, value.1)).collect();
+        write!(f, "{}", values.join(", "))
+    }
+}
+
+/// The `ParamGrid` type. The values of several parameters (for example mutation rates and
+/// population sizes), `grid_search` runs every combination of them.
+#[derive(Debug,Clone)]
+pub struct ParamGrid {
+    /// The parameter names and all the values to try.
+    pub params: Vec<(String, Vec<f64>)>,
+    /// If true, the configurations run in parallel (one thread per available core).
+    pub parallel: bool,
+}
+
+impl ParamGrid {
+    /// Creates an empty grid, add the parameters with `add`.
+    pub fn new() -> ParamGrid {
+        ParamGrid {
+            params: Vec::new(),
+            parallel: false,
+        }
+    }
+
+    /// Adds a parameter with all its values to try.
+    pub fn add(mut self, name: &str, values: Vec<f64>) -> ParamGrid {
+        self.params.push((name.to_string(), values));
+        self
+    }
+
+    /// Runs the configurations in parallel. Each simulation still uses its own threads, so
+    /// use less threads per simulation (`SimulationBuilder::threads`) to avoid overloading
+    /// the machine. Default value is false.
+    pub fn parallel(mut self, parallel: bool) -> ParamGrid {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Returns every combination of the parameter values, the last parameter changes fastest.
+    pub fn combinations(&self) -> Vec<ParamSet> {
+        let mut combinations = vec![ParamSet { values: Vec::new() }];
+
+        for (name, values) in &self.params {
+            combinations = combinations.iter()
+                .flat_map(|combination| values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.values.push((name.clone(), *value));
+                    combination
+                }))
+                .collect();
+        }
+
+        combinations
+    }
+}
+
+impl Default for ParamGrid {
+    fn default() -> ParamGrid {
+        ParamGrid::new()
+    }
+}
+
+/// The `GridEntry` type. The aggregated results of one configuration of a grid search.
+#[derive(Debug,Clone)]
+pub struct GridEntry {
+    /// The parameter values of this configuration.
+    pub params: ParamSet,
+    /// The results of all the runs of this configuration.
+    pub result: ExperimentResult,
+}
+
+/// The `GridResult` type. The outcome of `grid_search`.
+#[derive(Debug,Clone)]
+pub struct GridResult {
+    /// All the configurations, sorted by their mean best fitness: the best one first.
+    pub entries: Vec<GridEntry>,
+}
+
+impl GridResult {
+    /// The best configuration (lowest mean best fitness), None if the grid was empty.
+    pub fn best(&self) -> Option<&GridEntry> {
+        self.entries.first()
+    }
+}
+
+impl fmt::Display for GridResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.entries {
+            writeln!(f, "{} -> {}", entry.params, entry.result)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs every combination of the parameter grid `repeats_each` times (see `run_experiment`)
+/// and returns the aggregated results of all configurations, sorted by their mean best
+/// fitness. `builder_fn` is called for every run with the parameter values and the index of
+/// the run of this configuration (0 to `repeats_each` - 1, see `run_experiment`) and must
+/// return a freshly configured simulation that uses them.
+pub fn grid_search<S, T, F>(param_grid: &ParamGrid, builder_fn: F, repeats_each: u32) -> GridResult
+    where S: Send + Sync, T: Individual + Send + Sync + Clone, F: Fn(&ParamSet, u32) -> Simulation<S, T> + Sync {
+    let combinations = param_grid.combinations();
+    let mut results: Vec<Option<ExperimentResult>> = vec![None; combinations.len()];
+
+    if param_grid.parallel && combinations.len() > 1 {
+        let num_of_threads = thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1)
+            .min(combinations.len());
+        let mut pool = make_pool(num_of_threads).unwrap();
+
+        (&combinations).into_split_iter().zip((&mut results).into_split_iter()).for_each(
+            &pool.spawner(), |(params, result)| {
+                *result = Some(run_experiment(|run| builder_fn(params, run), repeats_each));
+            });
+    } else {
+        for (params, result) in combinations.iter().zip(results.iter_mut()) {
+            *result = Some(run_experiment(|run| builder_fn(params, run), repeats_each));
+        }
+    }
+
+    let mut entries: Vec<GridEntry> = combinations.into_iter().zip(results)
+        .map(|(params, result)| GridEntry { params, result: result.expect("Configuration did not run") })
+        .collect();
+
+    // Configurations without any successful run have a NaN mean, they go last
+    entries.sort_by(|a, b| match (a.result.mean_fitness.is_nan(), b.result.mean_fitness.is_nan()) {
+        (false, false) => a.result.mean_fitness.partial_cmp(&b.result.mean_fitness).unwrap(),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    });
+
+    GridResult { entries }
+}
+
+#[cfg(test)]
+mod test {
+    use benchmarks::{Sphere, DIMENSION};
+    use individual::Individual;
+    use population_builder::PopulationBuilder;
+    use simulation_builder::SimulationBuilder;
+
+    use super::{grid_search, run_experiment, run_with_restarts, ParamGrid};
+
+    #[test]
+    fn run_experiment_aggregates() {
+        let build = |seed: u64| {
+            let population = PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(10)
+                .mutation_seed(seed)
+                .seed_individuals(vec![Sphere { x: vec![2.0; DIMENSION] }], 10)
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Sphere>::new()
+                .iterations(50)
+                .threads(1)
+                .add_population(population)
+                .finalize().unwrap()
+        };
+
+        let result = run_experiment(|run| build(run as u64 + 1), 3);
+
+        assert_eq!(result.runs.len(), 3);
+        assert_eq!(result.failed_runs, 0);
+        assert!(result.min_fitness <= result.mean_fitness && result.mean_fitness <= result.max_fitness);
+        assert!(result.max_fitness < Sphere { x: vec![2.0; DIMENSION] }.calculate_fitness());
+
+        // The runs are seeded by their index, so they all differ
+        assert!(result.runs[0].best_fitness != result.runs[1].best_fitness);
+        assert!(result.runs[1].best_fitness != result.runs[2].best_fitness);
+        assert!(result.std_fitness > 0.0);
+
+        // The same seed every time gives the same run every time
+        let repeated = run_experiment(|_| build(1), 3);
+        assert_eq!(repeated.min_fitness, repeated.max_fitness);
+        assert_eq!(repeated.std_fitness, 0.0);
+        assert_eq!(repeated.runs[0].best_fitness, result.runs[0].best_fitness);
+    }
+
+    #[test]
+    fn restarts_split_the_budget() {
+        let mut seed = 0;
+        let result = run_with_restarts(|iterations| {
+            seed += 1;
+
+            let population = PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(10)
+                .mutation_seed(seed)
+                .seed_individuals(vec![Sphere { x: vec![2.0; DIMENSION] }], 10)
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Sphere>::new()
+                .iterations(iterations)
+                .threads(1)
+                .add_population(population)
+                .finalize().unwrap()
+        }, 100, 4);
+
+        assert_eq!(result.runs.len(), 4);
+        assert_eq!(result.failed_runs, 0);
+        assert!(result.runs.iter().all(|run| run.iteration_counter == 25));
+
+        let best_run = result.runs.iter().map(|run| run.best_fitness).fold(f64::MAX, f64::min);
+        assert_eq!(result.best.unwrap().fitness, best_run);
+    }
+
+    #[test]
+    fn grid_search_sorted() {
+        let grid = ParamGrid::new()
+            .add("iterations", vec![10.0, 500.0])
+            .add("individuals", vec![5.0, 20.0])
+            .parallel(true);
+
+        assert_eq!(grid.combinations().len(), 4);
+        assert_eq!(grid.combinations()[1].values, vec![("iterations".to_string(), 10.0), ("individuals".to_string(), 20.0)]);
+
+        let result = grid_search(&grid, |params, run| {
+            let population = PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(params.get("individuals") as u32)
+                .increasing_mutation_rate()
+                .mutation_seed(run as u64 + 1)
+                .seed_individuals(vec![Sphere { x: vec![2.0; DIMENSION] }], 10)
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Sphere>::new()
+                .iterations(params.get("iterations") as u32)
+                .threads(1)
+                .add_population(population)
+                .finalize().unwrap()
+        }, 2);
+
+        assert_eq!(result.entries.len(), 4);
+        assert!(result.entries.windows(2).all(|pair| pair[0].result.mean_fitness <= pair[1].result.mean_fitness));
+        assert_eq!(result.best().unwrap().params.get("iterations"), 500.0);
+        assert!(result.entries.iter().all(|entry| entry.result.runs.len() == 2));
+
+        // The repeats of each configuration are seeded by their index, so they differ
+        assert!(result.entries.iter().all(|entry| entry.result.runs[0].best_fitness != entry.result.runs[1].best_fitness));
+    }
+}
@@ -0,0 +1,204 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Two simulations with different individual types that solve the same problem and exchange
+//! their fittest individuals with a user defined conversion.
+
+use individual::Individual;
+use simulation::{Simulation, RunError};
+
+/// Converts an individual of one representation into the other one.
+pub type Conversion<A, B> = Box<dyn Fn(&A) -> B>;
+
+/// The `HeterogeneousSimulation` type. Runs two simulations with different representations
+/// of the same problem (for example a permutation and an adjacency matrix for the TSP) side
+/// by side. Every `migration_interval` iterations the fittest individual of each simulation
+/// is converted and injected into every population of the other one (see
+/// `Simulation::inject`), so both islands profit from each other.
+/// `Individual` can't be used as a trait object (`Individual::new` is generic), so instead
+/// of a habitat of `Box<dyn Individual>` this holds exactly two simulations, each one with
+/// its own type. The fitness of both representations must be comparable.
+pub struct HeterogeneousSimulation<SA, A, SB, B>
+    where SA: Send + Sync, A: Individual + Send + Sync, SB: Send + Sync, B: Individual + Send + Sync {
+    /// The simulation with the first representation.
+    pub first: Simulation<SA, A>,
+    /// The simulation with the second representation.
+    pub second: Simulation<SB, B>,
+    /// Converts the fittest individual of the first simulation for the second one.
+    pub first_to_second: Conversion<A, B>,
+    /// Converts the fittest individual of the second simulation for the first one.
+    pub second_to_first: Conversion<B, A>,
+    /// Exchange the fittest individuals every `migration_interval` iterations, 0 disables
+    /// the exchange.
+    pub migration_interval: u32,
+    /// The number of exchanges done so far.
+    pub migrations: u32,
+    /// The number of iterations done so far.
+    pub iteration_counter: u32,
+}
+
+impl<SA, A, SB, B> HeterogeneousSimulation<SA, A, SB, B>
+    where SA: Send + Sync, A: Individual + Send + Sync + Clone, SB: Send + Sync, B: Individual + Send + Sync + Clone {
+    /// Creates a new heterogeneous simulation from two configured simulations and the
+    /// conversions between their individuals. The default migration interval is 10.
+    pub fn new(first: Simulation<SA, A>, second: Simulation<SB, B>, first_to_second: Conversion<A, B>,
+               second_to_first: Conversion<B, A>) -> HeterogeneousSimulation<SA, A, SB, B> {
+        HeterogeneousSimulation {
+            first,
+            second,
+            first_to_second,
+            second_to_first,
+            migration_interval: 10,
+            migrations: 0,
+            iteration_counter: 0,
+        }
+    }
+
+    /// Sets the number of iterations between two exchanges of the fittest individuals.
+    pub fn migration_interval(mut self, migration_interval: u32) -> HeterogeneousSimulation<SA, A, SB, B> {
+        self.migration_interval = migration_interval;
+        self
+    }
+
+    /// Runs both simulations one step at a time until both are finished (each one with its
+    /// own stop criterion) and exchanges the fittest individuals in between.
+    pub fn run(&mut self) -> Result<(), RunError> {
+        while !self.finished() {
+            if !self.first.finished() {
+                self.first.step()?;
+            }
+
+            if !self.second.finished() {
+                self.second.step()?;
+            }
+
+            self.iteration_counter += 1;
+
+            if self.migration_interval > 0 && self.iteration_counter % self.migration_interval == 0 {
+                self.migrate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if both simulations are finished.
+    pub fn finished(&self) -> bool {
+        self.first.finished() && self.second.finished()
+    }
+
+    /// Converts the fittest individual of each simulation and injects it into every
+    /// population of the other one.
+    pub fn migrate(&mut self) -> Result<(), RunError> {
+        let to_second = self.first.best_cloned().map(|best| (self.first_to_second)(&best));
+        let to_first = self.second.best_cloned().map(|best| (self.second_to_first)(&best));
+
+        if let Some(migrant) = to_second {
+            let ids: Vec<u32> = self.second.habitat.iter().map(|population| population.id).collect();
+
+            for id in ids {
+                self.second.inject(id, migrant.clone())?;
+            }
+        }
+
+        if let Some(migrant) = to_first {
+            let ids: Vec<u32> = self.first.habitat.iter().map(|population| population.id).collect();
+
+            for id in ids {
+                self.first.inject(id, migrant.clone())?;
+            }
+        }
+
+        self.migrations += 1;
+        Ok(())
+    }
+
+    /// Returns the fitness of the fittest individual of both simulations, or None if they
+    /// have not been started yet.
+    pub fn best_fitness(&self) -> Option<f64> {
+        match (self.first.best_fitness(), self.second.best_fitness()) {
+            (Some(first), Some(second)) => Some(first.min(second)),
+            (first, second) => first.or(second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{self, Rng};
+
+    use benchmarks::{Sphere, DIMENSION};
+    use individual::Individual;
+    use population_builder::PopulationBuilder;
+    use simulation_builder::SimulationBuilder;
+
+    use super::HeterogeneousSimulation;
+
+    /// The sphere function with the position stored halved (x = 2 y).
+    #[derive(Debug, Clone)]
+    struct HalvedSphere {
+        y: Vec<f64>,
+    }
+
+    impl Individual for HalvedSphere {
+        fn new<S>(_data_source: S) -> HalvedSphere {
+            let mut rng = rand::thread_rng();
+            HalvedSphere { y: (0..DIMENSION).map(|_| rng.gen_range(-2.56, 2.56)).collect() }
+        }
+
+        fn mutate(&mut self) {
+            let mut rng = rand::thread_rng();
+            let index = rng.gen_range(0, self.y.len());
+            self.y[index] = (self.y[index] + rng.gen_range(-0.1, 0.1)).max(-2.56).min(2.56);
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.y.iter().map(|y| 4.0 * y * y).sum()
+        }
+    }
+
+    #[test]
+    fn heterogeneous_migration() {
+        let first = SimulationBuilder::<(), Sphere>::new()
+            .iterations(50)
+            .add_population(PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(10)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .finalize().unwrap())
+            .finalize().unwrap();
+
+        let second = SimulationBuilder::<(), HalvedSphere>::new()
+            .iterations(50)
+            .add_population(PopulationBuilder::<(), HalvedSphere>::new()
+                .set_data_source(())
+                .individuals(10)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .finalize().unwrap())
+            .finalize().unwrap();
+
+        let mut simulation = HeterogeneousSimulation::new(first, second,
+            Box::new(|sphere: &Sphere| HalvedSphere { y: sphere.x.iter().map(|x| x / 2.0).collect() }),
+            Box::new(|halved: &HalvedSphere| Sphere { x: halved.y.iter().map(|y| y * 2.0).collect() }))
+            .migration_interval(10);
+
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.migrations, 5);
+        assert!(simulation.finished());
+
+        let first = simulation.first.best_fitness().unwrap();
+        let second = simulation.second.best_fitness().unwrap();
+        assert_eq!(simulation.best_fitness(), Some(first.min(second)));
+    }
+}
@@ -13,6 +13,13 @@
 
 // external modules
 use std::cmp::Ordering;
+#[cfg(feature = "async")]
+use std::future::{self, Future};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+
+use rand::Rng;
 
 /// A wrapper helper struct for the individuals.
 /// It does the book keeping of the fitness and the number of mutations this individual
@@ -27,12 +34,167 @@ pub struct IndividualWrapper<T: Individual> {
     pub num_of_mutations: u32,
     /// The id of the population that this individual belongs to. Just for statistics
     pub id: u32,
+    /// All the objectives of this individual, only used for lexicographic comparison (see
+    /// `Individual::calculate_fitness_multi`). Empty for scalar fitness.
+    /// Objectives that are maximized (see `FitnessGoal`) are stored negated, so that lower is
+    /// always better. `Simulation::best_objectives` returns the original values.
+    pub objectives: Vec<f64>,
+    /// True if the individual has changed since its fitness was calculated the last time.
+    /// Only used if dirty tracking is enabled, see `PopulationBuilder::dirty_tracking`.
+    pub dirty: bool,
+    /// How this individual was produced, only recorded if lineage tracking is enabled, see
+    /// `PopulationBuilder::track_lineage`.
+    pub lineage: Option<Arc<Lineage>>,
+    /// The cell of the grid this individual lives in, only used for spatial populations
+    /// (see `PopulationBuilder::spatial`).
+    pub cell: usize,
+}
+
+impl<T: Individual> IndividualWrapper<T> {
+    /// Adds a new record to the lineage of this individual, the current lineage becomes
+    /// its parent. The individual gets a new lineage id.
+    pub fn record_lineage(&mut self, origin: Origin, generation: u32) {
+        self.record_lineage_with_parent(origin, generation, None);
+    }
+
+    /// Like `record_lineage`, the lineage id of a second parent (for example the other parent
+    /// of a crossover) is added to the parent ids.
+    pub fn record_lineage_with_parent(&mut self, origin: Origin, generation: u32, second_parent: Option<u64>) {
+        let parent = self.lineage.take();
+        let mut parent_ids: Vec<u64> = parent.iter().map(|parent| parent.id).collect();
+        parent_ids.extend(second_parent);
+
+        self.lineage = Some(Arc::new(Lineage {
+            id: NEXT_LINEAGE_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            parent_ids,
+            generation,
+            origin,
+            population_id: self.id,
+            fitness: None,
+            parent,
+        }));
+    }
+
+    /// Adds a `Survival` record with the current fitness to the lineage of this individual,
+    /// see `PopulationBuilder::track_individual_history`. The individual keeps its lineage id.
+    pub fn record_fitness(&mut self, generation: u32) {
+        let parent = self.lineage.take();
+        let id = parent.as_ref().map_or_else(|| NEXT_LINEAGE_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            |parent| parent.id);
+
+        self.lineage = Some(Arc::new(Lineage {
+            id,
+            parent_ids: Vec::new(),
+            generation,
+            origin: Origin::Survival,
+            population_id: self.id,
+            fitness: Some(self.fitness),
+            parent,
+        }));
+    }
+
+    /// Returns the lineage id of this individual, None if lineage tracking is disabled.
+    pub fn lineage_id(&self) -> Option<u64> {
+        self.lineage.as_ref().map(|lineage| lineage.id)
+    }
+}
+
+/// The next free lineage id, see `Lineage::id`.
+static NEXT_LINEAGE_ID: AtomicU64 = AtomicU64::new(0);
+
+/// How an individual (or one step in its history) was produced.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Origin {
+    /// Created at the beginning of the simulation.
+    Initial,
+    /// Created when the population was reset (see reset limit), grown or when it replaced a
+    /// duplicate.
+    Reset,
+    /// Mutated (as often as its mutation rate says).
+    Mutation,
+    /// A mutated copy of a surviving parent, see `PopulationBuilder::survival_rate`.
+    Offspring,
+    /// A child of two parents, see `PopulationBuilder::crossover`. Only the lineage of the
+    /// first parent is kept, the second parent is in `Lineage::parent_ids`.
+    Crossover,
+    /// Hyper-mutated on a fitness plateau, see `SimulationBuilder::plateau_threshold`.
+    HyperMutation,
+    /// Migrated from another population.
+    Migration,
+    /// Injected by the user, see `Simulation::inject`.
+    Injection,
+    /// Survived the selection with the recorded fitness, see
+    /// `PopulationBuilder::track_individual_history`.
+    Survival,
+}
+
+/// One record of the provenance of an individual. The records are linked to their parents,
+/// so the whole history can be followed back to the initial individual (see `history`).
+/// Copies of an individual share their history.
+#[derive(Debug)]
+pub struct Lineage {
+    /// The lineage id of the individual produced by this step, unique within the process.
+    /// A `Survival` record keeps the id of the individual, all other records get a new one.
+    pub id: u64,
+    /// The lineage ids of the individuals this one was produced from: the previous record of
+    /// the history (if any) and the second parent of a crossover. Empty for `Survival` records
+    /// and for new individuals (`Initial`, `Reset` and `Injection`).
+    pub parent_ids: Vec<u64>,
+    /// The iteration (generation) in which this step happened.
+    pub generation: u32,
+    /// What happened to the individual.
+    pub origin: Origin,
+    /// The id of the population the individual belonged to.
+    pub population_id: u32,
+    /// The fitness of the individual, only set for `Survival` records.
+    pub fitness: Option<f64>,
+    /// The previous step of the history, None for the first one.
+    pub parent: Option<Arc<Lineage>>,
+}
+
+impl Lineage {
+    /// Returns the whole history, this record first and the oldest record last.
+    pub fn history(&self) -> Vec<&Lineage> {
+        let mut history = vec![self];
+        let mut current = self;
+
+        while let Some(ref parent) = current.parent {
+            history.push(parent);
+            current = parent;
+        }
+
+        history
+    }
+
+    /// Returns the fitness of the individual in each generation it survived (generation,
+    /// fitness), the oldest first. Empty unless `PopulationBuilder::track_individual_history`
+    /// is set.
+    pub fn fitness_trajectory(&self) -> Vec<(u32, f64)> {
+        self.history().iter().rev()
+            .filter_map(|record| record.fitness.map(|fitness| (record.generation, fitness)))
+            .collect()
+    }
+}
+
+/// The history can be very long, drop it iteratively instead of recursively to avoid a
+/// stack overflow.
+impl Drop for Lineage {
+    fn drop(&mut self) {
+        let mut parent = self.parent.take();
+
+        while let Some(node) = parent {
+            match Arc::try_unwrap(node) {
+                Ok(mut node) => parent = node.parent.take(),
+                Err(_) => break
+            }
+        }
+    }
 }
 
 /// Implement this for sorting
 impl<T: Individual> PartialEq for IndividualWrapper<T> {
     fn eq(&self, other: &IndividualWrapper<T>) -> bool {
-        self.fitness == other.fitness
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 
@@ -46,14 +208,73 @@ impl<T: Individual> Ord for IndividualWrapper<T> {
     }
 }
 
-/// Implement this for sorting
+/// Implement this for sorting. The individuals are ordered by ascending fitness, so a fitter
+/// individual (lower fitness) is less than a less fit one and comes first after sorting.
+/// If both individuals have objectives, they are compared lexicographically: the first
+/// objective decides, if it is equal the second one and so on.
 impl<T: Individual> PartialOrd for IndividualWrapper<T> {
     fn partial_cmp(&self, other: &IndividualWrapper<T>) -> Option<Ordering> {
-        self.fitness.partial_cmp(&other.fitness)
+        if self.objectives.is_empty() || other.objectives.is_empty() {
+            return self.fitness.partial_cmp(&other.fitness);
+        }
+
+        for (objective, other_objective) in self.objectives.iter().zip(other.objectives.iter()) {
+            match objective.partial_cmp(other_objective) {
+                Some(Ordering::Equal) => continue,
+                ordering => return ordering
+            }
+        }
+
+        Some(Ordering::Equal)
+    }
+}
+
+/// Should an objective be minimized or maximized? See `SimulationBuilder::objective_goals`.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum FitnessGoal {
+    /// Lower values are better (default).
+    Minimize,
+    /// Higher values are better.
+    Maximize,
+}
+
+impl FitnessGoal {
+    /// Converts the objective value into a value where lower is always better (and back,
+    /// since it is its own inverse).
+    pub fn apply(&self, value: f64) -> f64 {
+        match *self {
+            FitnessGoal::Minimize => value,
+            FitnessGoal::Maximize => -value,
+        }
+    }
+}
+
+/// Additional information about the state of the simulation, that is passed to
+/// `Individual::calculate_fitness_with_context`. More fields may be added, so outside of this
+/// crate it can only be created with `new`.
+#[derive(Debug,Clone)]
+#[non_exhaustive]
+pub struct FitnessContext {
+    /// The current iteration (generation) of the simulation.
+    pub generation: u32,
+    /// The factor for the penalty of constraint violations, if the fitness uses one. It starts
+    /// at 1.0 and is halved by `InfeasibilityRecovery::RelaxPenalty`.
+    pub penalty_scale: f64,
+}
+
+impl FitnessContext {
+    /// Creates the context for the given generation with a penalty scale of 1.0, for example
+    /// to call `Individual::calculate_fitness_with_context` in a test.
+    pub fn new(generation: u32) -> FitnessContext {
+        FitnessContext {
+            generation,
+            penalty_scale: 1.0,
+        }
     }
 }
 
 /// This trait has to be implemented for the user defined struct.
+/// If the individuals share large problem data, see `operators::SharedContextIndividual`.
 pub trait Individual {
     /// This method creates a new individual.
     fn new<S>(data_source: S) -> Self;
@@ -73,22 +294,164 @@ pub trait Individual {
     /// mutation function (one operation) and add more and more "smarter" mutation types to the
     /// mutate function.
     fn mutate(&mut self);
+    /// This method is the one actually called by the simulation to mutate the individual
+    /// (unless a `Mutator` is used). Override it and use the given random number generator
+    /// instead of `rand::thread_rng` to make the mutation reproducible, see
+    /// `PopulationBuilder::mutation_seed`.
+    /// The default implementation just calls `mutate`.
+    fn mutate_with_rng(&mut self, _rng: &mut dyn Rng) {
+        self.mutate();
+    }
     /// This method calculates the fitness for the individual. Usually this is an expensive
     /// operation and a bit more difficult to implement, compared to the mutation method above.
     /// The lower the fitness value, the better (healthier) the individual is and the closer
     /// the individual is to the perfect solution. This can also correspont to the number of
     /// errors like for example in the sudoku or queens problem case.
     fn calculate_fitness(&self) -> f64;
+    /// This method is the one actually called by the simulation. It calculates the fitness
+    /// with additional information about the simulation (for example the current generation).
+    /// Override it for dynamic problems where the fitness changes over time (moving targets).
+    /// In that case the simulation should also be told to re-evaluate the unmutated
+    /// individuals each iteration, see `PopulationBuilder::dynamic_fitness`.
+    /// The default implementation just calls `calculate_fitness`.
+    fn calculate_fitness_with_context(&self, _context: &FitnessContext) -> f64 {
+        self.calculate_fitness()
+    }
+    /// This method is the one actually called by the simulation for the scalar fitness. For
+    /// very expensive fitness calculations (for example a simulation that takes minutes) check
+    /// the cancel flag regularly and return None if it is set, the simulation is then stopped
+    /// with `RunError::Cancelled` (see `Simulation::cancel_flag`).
+    /// The default implementation ignores the flag and calls `calculate_fitness_with_context`.
+    fn calculate_fitness_cancellable(&self, context: &FitnessContext, _cancel: &AtomicBool) -> Option<f64> {
+        Some(self.calculate_fitness_with_context(context))
+    }
+    /// This method is called by the simulation instead of `calculate_fitness_cancellable` if
+    /// `SimulationBuilder::max_concurrent_evals` is set. Override it if the fitness calculation
+    /// mostly waits (for example for a remote simulator or a GPU queue): the fitness of many
+    /// individuals is then awaited at the same time, so their waiting times overlap.
+    /// Only available with the `async` feature.
+    /// The default implementation just returns the result of `calculate_fitness`.
+    #[cfg(feature = "async")]
+    fn calculate_fitness_async(&self) -> impl Future<Output = f64> {
+        future::ready(self.calculate_fitness())
+    }
+    /// This method calculates several objectives (fitness values) for the individual. They are
+    /// compared lexicographically: the first objective is the most important one, the second
+    /// one is only used if the first ones are equal and so on. For example minimize the
+    /// number of constraint violations first, then the cost.
+    /// This is only used if `SimulationBuilder::lexicographic_objectives` is set. The returned
+    /// vector must always have the same length.
+    /// The default implementation just returns the scalar fitness.
+    fn calculate_fitness_multi(&self) -> Vec<f64> {
+        vec![self.calculate_fitness()]
+    }
+    /// This method returns how much the individual violates the constraints of the problem
+    /// (for example the weight above the capacity of a knapsack), 0.0 means it is feasible.
+    /// It is only used to reject infeasible offspring, see
+    /// `PopulationBuilder::feasibility_retries`, and to detect populations without any feasible
+    /// individual, see `SimulationBuilder::infeasibility_recovery`.
+    /// The default implementation returns 0.0, so every individual is feasible.
+    fn constraint_violation(&self) -> f64 {
+        0.0
+    }
+    /// This method fixes the constraint violations of the individual (for example drops
+    /// items until a knapsack fits) and returns true if it changed the individual. It is
+    /// called for the offspring before the fitness calculation, but only if
+    /// `PopulationBuilder::enable_repair` is set.
+    /// The default implementation does nothing and returns false.
+    fn repair(&mut self) -> bool {
+        false
+    }
+    /// This method calculates the fitness on validation data (for example a test set for
+    /// symbolic regression) that is not used for the training fitness. It is called once per
+    /// iteration for the fittest individual of each population, the best one is kept in
+    /// `SimulationResult::best_validation` to detect overfitting.
+    /// The default implementation returns None, so no validation fitness is tracked.
+    fn validation_fitness(&self) -> Option<f64> {
+        None
+    }
+    /// This method overwrites the individual with the content of the given source individual.
+    /// The simulation uses it to recycle the individuals that died in the last iteration
+    /// instead of allocating new ones. Override it if your individual contains allocated
+    /// data (like a `Vec`) that can be copied in place, for example with `clone_from_slice`.
+    /// The default implementation just calls `clone_from`.
+    fn reuse_from(&mut self, source: &Self) where Self: Clone {
+        self.clone_from(source);
+    }
+    /// This method improves the individual with one step of a local search (for example one
+    /// 2-opt move for a TSP tour). It is only used for the final refinement of the fittest
+    /// individual, see `SimulationBuilder::final_refinement`.
+    /// The default implementation does nothing.
+    fn local_search(&mut self) {
+    }
+    /// This method calculates the fitness of a partial solution, only the first `up_to`
+    /// components (for example the first cities of a tour) are considered. It is not used by
+    /// the simulation itself, but by constructive helpers like `operators::beam_search` that
+    /// build solutions step by step.
+    /// The default implementation just returns the fitness of the whole individual.
+    fn partial_fitness(&self, _up_to: usize) -> f64 {
+        self.calculate_fitness()
+    }
+    /// This method calculates the distance between two individuals (for example the Hamming
+    /// distance of two bit strings), the more different they are the greater. It is only used
+    /// by `TieBreak::ByDiversity`.
+    /// The default implementation returns 0.0, so all individuals are equally unique.
+    fn distance(&self, _other: &Self) -> f64 {
+        0.0
+    }
+    /// This method returns a signature (for example a hash) of the individual: two
+    /// individuals with the same signature are considered to be identical. It is only used
+    /// to remove duplicates, see `PopulationBuilder::dedup`.
+    /// Comparing signatures is much cheaper than comparing whole individuals (for example
+    /// the path of a TSP). If the individual (or the part that matters, like the path)
+    /// implements `Hash`, `structural_hash` can be used to calculate it.
+    /// The default implementation returns None, so no individual is ever a duplicate.
+    fn signature(&self) -> Option<u64> {
+        None
+    }
+    /// This method returns a short human readable description of the individual (for
+    /// example the tour of a TSP), it is printed with the fittest individual, see
+    /// `SimulationBuilder::log_best_every`.
+    /// The default implementation returns None, so only the fitness is printed.
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The 64 bit FNV-1a hash function, see `structural_hash`. Unlike the `DefaultHasher` of the
+/// standard library its algorithm is fixed, so it gives the same hash in every build.
+struct FnvHasher(u64);
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Calculates a hash of the given value that can be used as the signature of an individual,
+/// see `Individual::signature`. Equal values always give the same hash, also across runs and
+/// builds. Only on the same platform though: the `Hash` implementations of the standard types
+/// depend on the byte order and on the size of `usize`.
+pub fn structural_hash<H: Hash + ?Sized>(value: &H) -> u64 {
+    let mut hasher = FnvHasher(0xcbf29ce484222325);
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
 #[cfg(test)]
 mod test {
-    use super::{IndividualWrapper, Individual};
+    use super::{IndividualWrapper, Individual, structural_hash};
 
     struct IndividualTest1;
 
     impl Individual for IndividualTest1 {
-        fn new() -> IndividualTest1 {
+        fn new<S>(_data_source: S) -> IndividualTest1 {
             IndividualTest1
         }
 
@@ -102,28 +465,49 @@ mod test {
 
     #[test]
     fn compare1() {
-        let individual1 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 1.2, num_of_mutations: 21};
-        let individual2 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 5.93, num_of_mutations: 7};
+        let individual1 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 1.2, num_of_mutations: 21, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
+        let individual2 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 5.93, num_of_mutations: 7, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
 
         assert!(individual2 > individual1);
     }
 
     #[test]
     fn compare2() {
-        let individual1 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 3.78, num_of_mutations: 21};
-        let individual2 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 7.12, num_of_mutations: 7};
+        let individual1 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 3.78, num_of_mutations: 21, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
+        let individual2 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 7.12, num_of_mutations: 7, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
 
-        assert!(individual2 < individual1);
+        // The fitter individual (lower fitness) is the lesser one, like in compare1
+        assert!(individual1 < individual2);
     }
 
     #[test]
     fn compare3() {
-        let individual1 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 21.996, num_of_mutations: 11};
-        let individual2 = IndividualWrapper{individual: IndividualTest1::new(), fitness: 21.996, num_of_mutations: 34};
+        let individual1 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 21.996, num_of_mutations: 11, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
+        let individual2 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 21.996, num_of_mutations: 34, id: 1, objectives: Vec::new(), dirty: false, lineage: None, cell: 0};
 
         assert!(individual1 == individual2);
     }
 
+    #[test]
+    fn compare_lexicographic() {
+        let individual1 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 1.0, num_of_mutations: 1, id: 1, objectives: vec![1.0, 9.0], dirty: false, lineage: None, cell: 0};
+        let individual2 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 1.0, num_of_mutations: 1, id: 1, objectives: vec![1.0, 3.0], dirty: false, lineage: None, cell: 0};
+        let individual3 = IndividualWrapper{individual: IndividualTest1::new(()), fitness: 0.5, num_of_mutations: 1, id: 1, objectives: vec![0.5, 20.0], dirty: false, lineage: None, cell: 0};
+
+        assert!(individual2 < individual1);
+        assert!(individual3 < individual2);
+    }
 
+    #[test]
+    fn structural_hash1() {
+        assert_eq!(structural_hash(&vec![0, 3, 1, 2]), structural_hash(&vec![0, 3, 1, 2]));
+        assert!(structural_hash(&vec![0, 3, 1, 2]) != structural_hash(&vec![0, 1, 3, 2]));
+    }
 
+    #[test]
+    fn structural_hash_fixed() {
+        // The FNV-1a hashes of "a" and "foo", the same in every build
+        assert_eq!(structural_hash(&b'a'), 0xaf63dc4c8601ec8c);
+        assert_eq!(structural_hash(&(b'f', b'o', b'o')), 0xdcb27518fed9d577);
+    }
 }
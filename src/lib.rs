@@ -18,9 +18,54 @@
 
 #[macro_use] extern crate quick_error;
 extern crate jobsteal;
+extern crate rand;
+#[cfg(feature = "indicatif")]
+extern crate indicatif;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "log")]
+#[macro_use] extern crate log;
+#[cfg(feature = "core_affinity")]
+extern crate core_affinity;
+
+// Progress messages go to the log crate with the `log` feature, otherwise to stdout.
+#[cfg(feature = "log")]
+macro_rules! log_info {
+    ($($arg:tt)*) => { info!($($arg)*) }
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_info {
+    ($($arg:tt)*) => { println!($($arg)*) }
+}
+#[cfg(feature = "log")]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { debug!($($arg)*) }
+}
+#[cfg(not(feature = "log"))]
+macro_rules! log_debug {
+    ($($arg:tt)*) => { println!($($arg)*) }
+}
 
 pub mod individual;
 pub mod simulation;
 pub mod simulation_builder;
 pub mod population;
 pub mod population_builder;
+pub mod operators;
+pub mod statistics;
+pub mod experiment;
+pub mod heterogeneous;
+pub mod benchmarks;
+#[cfg(feature = "serde")]
+pub mod autosave;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+#[cfg(feature = "async")]
+pub mod async_evaluation;
+
+pub use statistics::compare_runs;
+pub use experiment::{run_experiment, run_with_restarts, grid_search};
@@ -0,0 +1,847 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//!
+
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use rand::{self, Rng};
+
+use individual::Individual;
+use population::BoxedInitializer;
+
+pub mod permutation;
+
+/// Information that is passed to `Mutator::mutate`.
+#[derive(Debug,Clone)]
+pub struct MutationContext<'a> {
+    /// The current iteration (generation) of the simulation.
+    pub generation: u32,
+    /// The indices of the components (genes) of the individual that must not change, see
+    /// `PopulationBuilder::frozen_components`.
+    pub frozen_components: &'a [usize],
+    /// The relative fitness of the individual in its population before the mutation: 0.0 for
+    /// the fittest and 1.0 for the least fit individual. Use it to mutate good individuals
+    /// gently (local search) and bad ones strongly (exploration).
+    /// Always 1.0 unless `PopulationBuilder::fitness_scaled_mutation` is enabled.
+    pub scale: f64,
+}
+
+impl<'a> MutationContext<'a> {
+    /// Returns true if the component with the given index must not change.
+    pub fn is_frozen(&self, index: usize) -> bool {
+        self.frozen_components.contains(&index)
+    }
+
+    /// Returns a random index in [0, len) of a component that may change, or None if all
+    /// components are frozen. Use this instead of `rng.gen_range(0, len)` in a mutator to
+    /// honor the frozen components.
+    pub fn random_unfrozen_index(&self, len: usize, mut rng: &mut dyn Rng) -> Option<usize> {
+        let unfrozen: Vec<usize> = (0..len).filter(|index| !self.is_frozen(*index)).collect();
+
+        if unfrozen.is_empty() {
+            None
+        } else {
+            Some(unfrozen[Rng::gen_range(&mut rng, 0, unfrozen.len())])
+        }
+    }
+}
+
+/// A mutation strategy that is held by the framework instead of the individual. This allows
+/// to swap mutation operators without changing the `Individual` implementation.
+/// Register it with `PopulationBuilder::mutator`, otherwise `Individual::mutate` is used.
+pub trait Mutator<T>: Send + Sync {
+    /// Mutates the given individual once. The context contains the current iteration of the
+    /// simulation (so that the strategy can change over time) and the frozen components.
+    /// The framework can't know the layout of the individual, so the mutator itself must
+    /// leave the frozen components unchanged, for example by picking the positions to change
+    /// with `MutationContext::random_unfrozen_index`.
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext);
+    /// Like `mutate`, but returns the index of the operator that was applied (see
+    /// `operator_names`), so that the framework can count how well each operator does
+    /// (see `SimulationResult::operator_stats`).
+    /// The default implementation calls `mutate` and returns None.
+    fn mutate_with_operator(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext) -> Option<usize> {
+        self.mutate(individual, rng, context);
+        None
+    }
+    /// The names of the operators this mutator chooses from, see `RandomOperator`.
+    /// The default implementation returns an empty list.
+    fn operator_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Called by the framework after each iteration with the number of applications and
+    /// improvements of each operator in this iteration (indices of `mutate_with_operator`),
+    /// so that an adaptive mutator can change its strategy, see `AdaptivePursuit`.
+    /// The default implementation does nothing.
+    fn update_operators(&self, _applications: &[u64], _improvements: &[u64]) {
+    }
+}
+
+/// How often one operator of a mutator (see `Mutator::operator_names`) was applied and how
+/// often it made an individual fitter, see `SimulationResult::operator_stats`.
+#[derive(Debug,Clone,PartialEq)]
+pub struct OperatorStat {
+    /// The name of the operator.
+    pub name: String,
+    /// The number of times the operator was applied.
+    pub applications: u64,
+    /// The number of applications that made the individual fitter than its parent. If an
+    /// individual is mutated several times, each applied operator counts the improvement.
+    pub improvements: u64,
+}
+
+impl OperatorStat {
+    /// The fraction (0.0 - 1.0) of the applications that made the individual fitter.
+    pub fn improvement_rate(&self) -> f64 {
+        if self.applications == 0 {
+            0.0
+        } else {
+            self.improvements as f64 / self.applications as f64
+        }
+    }
+}
+
+/// A mutator that applies one of several named operators, chosen randomly by weight for
+/// each mutation. The framework counts how often each operator was applied and how often it
+/// improved an individual, see `SimulationResult::operator_stats`.
+pub struct RandomOperator<T> {
+    /// The operators with their names and weights.
+    pub operators: Vec<(String, f64, Box<dyn Mutator<T>>)>,
+}
+
+impl<T> RandomOperator<T> {
+    /// Creates a new mutator without any operators, add them with `add`.
+    pub fn new() -> RandomOperator<T> {
+        RandomOperator {
+            operators: Vec::new(),
+        }
+    }
+
+    /// Adds an operator with the given name and weight. An operator is chosen with the
+    /// probability `weight / sum of all weights`.
+    pub fn add(mut self, name: &str, weight: f64, operator: Box<dyn Mutator<T>>) -> RandomOperator<T> {
+        self.operators.push((name.to_string(), weight.max(0.0), operator));
+        self
+    }
+
+    /// Returns the index of a randomly chosen operator.
+    fn choose(&self, mut rng: &mut dyn Rng) -> usize {
+        let total: f64 = self.operators.iter().map(|operator| operator.1).sum();
+
+        if total <= 0.0 {
+            return Rng::gen_range(&mut rng, 0, self.operators.len());
+        }
+
+        let mut value = Rng::gen_range(&mut rng, 0.0, total);
+
+        for (index, operator) in self.operators.iter().enumerate() {
+            if value < operator.1 {
+                return index;
+            }
+            value -= operator.1;
+        }
+
+        self.operators.len() - 1
+    }
+}
+
+impl<T> Default for RandomOperator<T> {
+    fn default() -> RandomOperator<T> {
+        RandomOperator::new()
+    }
+}
+
+impl<T> Mutator<T> for RandomOperator<T> {
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext) {
+        self.mutate_with_operator(individual, rng, context);
+    }
+
+    fn mutate_with_operator(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext) -> Option<usize> {
+        if self.operators.is_empty() {
+            return None;
+        }
+
+        let index = self.choose(rng);
+        self.operators[index].2.mutate(individual, rng, context);
+        Some(index)
+    }
+
+    fn operator_names(&self) -> Vec<String> {
+        self.operators.iter().map(|operator| operator.0.clone()).collect()
+    }
+}
+
+/// A recombination (crossover) operator that creates children from two parents, register it
+/// with `PopulationBuilder::crossover`. The children are mutated afterwards like all the
+/// other offspring.
+pub trait Crossover<T>: Send + Sync {
+    /// Combines the two parents into one or two children. The second child (for example the
+    /// complementary child of a one point crossover) is only used with
+    /// `PopulationBuilder::offspring_per_crossover(2)`, return None if the operator creates
+    /// only one child.
+    fn crossover(&self, first: &T, second: &T, rng: &mut dyn Rng) -> (T, Option<T>);
+}
+
+/// The quality estimate and the probability of each operator of an `AdaptivePursuit`.
+#[derive(Debug,Clone)]
+struct PursuitState {
+    quality: Vec<f64>,
+    probabilities: Vec<f64>,
+}
+
+/// A mutator that chooses one of several operators randomly and adapts the probabilities
+/// with adaptive pursuit (Thierens 2005): after each iteration the quality estimate of each
+/// applied operator moves towards its improvement rate in that iteration, then the
+/// probability of the best operator moves towards `max_probability` and all other
+/// probabilities move towards `min_probability`. So good operators are favored while the
+/// others are still tried now and then, in case they become useful later on.
+/// Use it with `PopulationBuilder::adaptive_operators`.
+pub struct AdaptivePursuit<T> {
+    /// The operators to choose from with their names.
+    pub operators: Vec<(String, Box<dyn Mutator<T>>)>,
+    /// How fast the quality estimates follow the improvement rates (0.0 - 1.0).
+    pub adaptation_rate: f64,
+    /// How fast the probabilities move towards the best operator (0.0 - 1.0).
+    pub learning_rate: f64,
+    /// The lowest probability of an operator, must be less than 1 / number of operators.
+    pub min_probability: f64,
+    /// The current quality estimates and probabilities.
+    state: RwLock<PursuitState>,
+}
+
+impl<T> AdaptivePursuit<T> {
+    /// Creates a new adaptive mutator from the given operators and their names, all operators
+    /// start with the same probability. The adaptation rate and the learning rate are 0.8 and
+    /// the minimal probability is 0.1 / number of operators.
+    pub fn new(operators: Vec<(&str, Box<dyn Mutator<T>>)>) -> AdaptivePursuit<T> {
+        let count = operators.len().max(1) as f64;
+
+        AdaptivePursuit {
+            state: RwLock::new(PursuitState {
+                quality: vec![1.0; operators.len()],
+                probabilities: vec![1.0 / count; operators.len()],
+            }),
+            operators: operators.into_iter().map(|(name, operator)| (name.to_string(), operator)).collect(),
+            adaptation_rate: 0.8,
+            learning_rate: 0.8,
+            min_probability: 0.1 / count,
+        }
+    }
+
+    /// Returns the current probability of each operator.
+    pub fn probabilities(&self) -> Vec<f64> {
+        match self.state.read() {
+            Ok(state) => state.probabilities.clone(),
+            Err(e) => e.into_inner().probabilities.clone()
+        }
+    }
+}
+
+impl<T> Mutator<T> for AdaptivePursuit<T> {
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext) {
+        self.mutate_with_operator(individual, rng, context);
+    }
+
+    fn mutate_with_operator(&self, individual: &mut T, mut rng: &mut dyn Rng, context: &MutationContext) -> Option<usize> {
+        if self.operators.is_empty() {
+            return None;
+        }
+
+        let mut value = Rng::gen_range(&mut rng, 0.0, 1.0);
+        let mut index = self.operators.len() - 1;
+
+        {
+            let state = match self.state.read() {
+                Ok(state) => state,
+                Err(e) => e.into_inner()
+            };
+
+            for (operator, probability) in state.probabilities.iter().enumerate() {
+                if value < *probability {
+                    index = operator;
+                    break;
+                }
+                value -= *probability;
+            }
+        }
+
+        self.operators[index].1.mutate(individual, rng, context);
+        Some(index)
+    }
+
+    fn operator_names(&self) -> Vec<String> {
+        self.operators.iter().map(|operator| operator.0.clone()).collect()
+    }
+
+    fn update_operators(&self, applications: &[u64], improvements: &[u64]) {
+        let mut state = match self.state.write() {
+            Ok(state) => state,
+            Err(e) => e.into_inner()
+        };
+
+        for (index, applications) in applications.iter().enumerate() {
+            if *applications > 0 && index < state.quality.len() {
+                let reward = improvements[index] as f64 / *applications as f64;
+                state.quality[index] += self.adaptation_rate * (reward - state.quality[index]);
+            }
+        }
+
+        let best = match state.quality.iter().enumerate()
+            .max_by(|a, b| a.1.partial_cmp(b.1).expect("Quality is NaN")) {
+            Some((best, _)) => best,
+            None => return
+        };
+        let max_probability = 1.0 - (state.probabilities.len() - 1) as f64 * self.min_probability;
+
+        for (index, probability) in state.probabilities.iter_mut().enumerate() {
+            let target = if index == best { max_probability } else { self.min_probability };
+            *probability += self.learning_rate * (target - *probability);
+        }
+    }
+}
+
+/// Creates `count` copies of the given template individual. Each copy is mutated a random
+/// number of times (between 0 and `mutations`), so that a population seeded from a single
+/// (heuristic) solution does not start with identical individuals. The copies are mutated with
+/// `Individual::mutate_with_rng` and the given random number generator.
+pub fn shuffle_copies<T: Individual + Clone, R: Rng>(template: &T, count: usize, mutations: u32,
+                                                     rng: &mut R) -> Vec<T> {
+    let mut copies = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let mut copy = template.clone();
+
+        for _ in 0..rng.gen_range(0, mutations + 1) {
+            copy.mutate_with_rng(rng);
+        }
+
+        copies.push(copy);
+    }
+
+    copies
+}
+
+/// Builds solutions step by step with a beam search, for example to create good initial
+/// individuals for a construct-and-evolve (GRASP-like) hybrid. Starting with `start`, each of
+/// the `steps` steps extends every candidate in the beam with `extend` (called with the
+/// candidate and the number of components that are already set) and keeps the `beam_width`
+/// candidates with the best `Individual::partial_fitness`. Returns the final beam, fittest
+/// candidate first.
+pub fn beam_search<T, F>(start: T, beam_width: usize, steps: usize, extend: F) -> Vec<T>
+    where T: Individual, F: Fn(&T, usize) -> Vec<T> {
+    let mut beam = vec![start];
+
+    for step in 0..steps {
+        let mut candidates: Vec<(f64, T)> = beam.iter()
+            .flat_map(|candidate| extend(candidate, step))
+            .map(|candidate| (candidate.partial_fitness(step + 1), candidate))
+            .collect();
+
+        if candidates.is_empty() {
+            break;
+        }
+
+        candidates.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Fitness is NaN"));
+        candidates.truncate(beam_width.max(1));
+        beam = candidates.into_iter().map(|(_, candidate)| candidate).collect();
+    }
+
+    beam
+}
+
+/// The evolving part of a `SharedContextIndividual`: a small genome (for example the indices
+/// of a path) that is interpreted with the help of a shared, immutable context (for example
+/// the positions of the cities).
+pub trait Genome<C>: Clone {
+    /// Creates a new random genome for the given context.
+    fn random(context: &C, rng: &mut dyn Rng) -> Self;
+    /// Mutates the genome once.
+    fn mutate(&mut self, context: &C, rng: &mut dyn Rng);
+    /// Calculates the fitness of the genome, the lower the better.
+    fn fitness(&self, context: &C) -> f64;
+}
+
+/// An individual that consists of a large shared context `C` (the problem data, for example
+/// the positions of the cities of a TSP) and a small genome `G` (for example the path).
+/// Cloning the individual only clones the genome, the context is shared with an `Arc`.
+/// This is the recommended pattern for problems with large problem data, since the
+/// simulation clones individuals all the time. Create the individuals with
+/// `PopulationBuilder::initializer(SharedContextIndividual::initializer(context))`.
+pub struct SharedContextIndividual<C, G> {
+    /// The shared problem data.
+    pub context: Arc<C>,
+    /// The evolving part of the individual.
+    pub genome: G,
+}
+
+impl<C, G: Genome<C>> SharedContextIndividual<C, G> {
+    /// Creates a new individual with a random genome.
+    pub fn random(context: Arc<C>, rng: &mut dyn Rng) -> SharedContextIndividual<C, G> {
+        let genome = G::random(&context, rng);
+
+        SharedContextIndividual {
+            context,
+            genome,
+        }
+    }
+
+    /// Returns an initializer for `PopulationBuilder::initializer` that creates individuals
+    /// with random genomes, all of them share the given context.
+    pub fn initializer(context: Arc<C>) -> BoxedInitializer<SharedContextIndividual<C, G>>
+        where C: Send + Sync + 'static, G: 'static {
+        Box::new(move |rng| SharedContextIndividual::random(context.clone(), rng))
+    }
+}
+
+impl<C, G: Clone> Clone for SharedContextIndividual<C, G> {
+    fn clone(&self) -> SharedContextIndividual<C, G> {
+        SharedContextIndividual {
+            context: self.context.clone(),
+            genome: self.genome.clone(),
+        }
+    }
+}
+
+impl<C, G: fmt::Debug> fmt::Debug for SharedContextIndividual<C, G> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedContextIndividual {{ genome: {:?} }}", self.genome)
+    }
+}
+
+impl<C: Default, G: Genome<C> + Default> Individual for SharedContextIndividual<C, G> {
+    /// Only creates a placeholder with the default context and genome, use
+    /// `SharedContextIndividual::initializer`.
+    fn new<S>(_data_source: S) -> SharedContextIndividual<C, G> {
+        SharedContextIndividual {
+            context: Arc::new(C::default()),
+            genome: G::default(),
+        }
+    }
+
+    fn mutate(&mut self) {
+        self.genome.mutate(&self.context, &mut rand::thread_rng());
+    }
+
+    fn mutate_with_rng(&mut self, rng: &mut dyn Rng) {
+        self.genome.mutate(&self.context, rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        self.genome.fitness(&self.context)
+    }
+
+    fn reuse_from(&mut self, source: &SharedContextIndividual<C, G>) {
+        self.context = source.context.clone();
+        self.genome.clone_from(&source.genome);
+    }
+}
+
+/// The cost function of a permutation, the lower the better.
+pub type PermutationCost = Arc<dyn Fn(&[usize]) -> f64 + Send + Sync>;
+
+/// A generic permutation individual for ordering problems (like the TSP): `order` contains
+/// each number of 0..n exactly once. The first `fixed_prefix` positions never change, for
+/// example to keep the start city of a round trip fixed. The fitness is given by a cost
+/// closure. Since `Individual::new` can't create the cost closure, create the individuals
+/// with `PopulationBuilder::initializer(Permutation::initializer(size, fixed_prefix, cost))`.
+#[derive(Clone)]
+pub struct Permutation {
+    /// The order of the elements 0..n.
+    pub order: Vec<usize>,
+    /// The number of positions at the beginning of the order that never change.
+    pub fixed_prefix: usize,
+    /// Calculates the fitness of the order.
+    pub cost: PermutationCost,
+}
+
+impl Permutation {
+    /// Creates a random permutation of 0..size, the first `fixed_prefix` elements are in
+    /// their natural order (0, 1, 2, ...).
+    pub fn random(size: usize, fixed_prefix: usize, cost: PermutationCost, mut rng: &mut dyn Rng) -> Permutation {
+        let fixed_prefix = fixed_prefix.min(size);
+        let mut order: Vec<usize> = (0..size).collect();
+        Rng::shuffle(&mut rng, &mut order[fixed_prefix..]);
+
+        Permutation {
+            order,
+            fixed_prefix,
+            cost,
+        }
+    }
+
+    /// Returns an initializer for `PopulationBuilder::initializer` that creates random
+    /// permutations, see `random`.
+    pub fn initializer<F>(size: usize, fixed_prefix: usize, cost: F) -> BoxedInitializer<Permutation>
+        where F: Fn(&[usize]) -> f64 + Send + Sync + 'static {
+        let cost: PermutationCost = Arc::new(cost);
+
+        Box::new(move |rng| Permutation::random(size, fixed_prefix, cost.clone(), rng))
+    }
+
+    /// Swaps two random elements, see `permutation::swap_positions`.
+    pub fn swap_mutation(&mut self, rng: &mut dyn Rng) {
+        permutation::swap_positions(&mut self.order, rng, self.fixed_prefix);
+    }
+
+    /// Moves a random element to another random position.
+    pub fn insertion_mutation(&mut self, mut rng: &mut dyn Rng) {
+        if let Some((i, j)) = self.random_positions(&mut rng) {
+            let element = self.order.remove(i);
+            self.order.insert(j, element);
+        }
+    }
+
+    /// Reverses a random segment, for the TSP this is the well known 2-opt move, see
+    /// `permutation::reverse_segment`.
+    pub fn reversal_mutation(&mut self, rng: &mut dyn Rng) {
+        permutation::reverse_segment(&mut self.order, rng, self.fixed_prefix);
+    }
+
+    /// Order crossover (OX): the child gets a random segment of this permutation at the same
+    /// positions, the remaining positions are filled with the missing elements in the order
+    /// they appear in the other permutation. The fixed prefix and the cost function are
+    /// taken from this permutation, both permutations must have the same fixed prefix.
+    pub fn order_crossover(&self, other: &Permutation, mut rng: &mut dyn Rng) -> Permutation {
+        let mut child = self.clone();
+
+        if let Some((i, j)) = self.random_positions(&mut rng) {
+            let (start, end) = (i.min(j), i.max(j) + 1);
+            let segment = &self.order[start..end];
+            let mut remaining = other.order[self.fixed_prefix..].iter()
+                .filter(|element| !segment.contains(element));
+
+            for position in (self.fixed_prefix..start).chain(end..self.order.len()) {
+                child.order[position] = *remaining.next().expect("Permutations don't match");
+            }
+        }
+
+        child
+    }
+
+    /// Returns two different random positions after the fixed prefix, or None if there are
+    /// less than two positions that may change.
+    fn random_positions(&self, rng: &mut dyn Rng) -> Option<(usize, usize)> {
+        permutation::random_positions(self.order.len(), self.fixed_prefix, rng)
+    }
+}
+
+impl fmt::Debug for Permutation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Permutation {{ order: {:?}, fixed_prefix: {} }}", self.order, self.fixed_prefix)
+    }
+}
+
+impl Individual for Permutation {
+    /// Only creates an empty placeholder, use `Permutation::initializer`.
+    fn new<S>(_data_source: S) -> Permutation {
+        Permutation {
+            order: Vec::new(),
+            fixed_prefix: 0,
+            cost: Arc::new(|_| f64::MAX),
+        }
+    }
+
+    fn mutate(&mut self) {
+        self.mutate_with_rng(&mut rand::thread_rng());
+    }
+
+    /// Applies one of the swap, insertion or reversal mutations (chosen randomly).
+    fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+        match Rng::gen_range(&mut rng, 0, 3) {
+            0 => self.swap_mutation(rng),
+            1 => self.insertion_mutation(rng),
+            _ => self.reversal_mutation(rng),
+        }
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        (self.cost)(&self.order)
+    }
+
+    /// The cost of the first `up_to` elements only, the cost closure must accept shorter
+    /// orders for this.
+    fn partial_fitness(&self, up_to: usize) -> f64 {
+        (self.cost)(&self.order[..up_to.min(self.order.len())])
+    }
+
+    fn reuse_from(&mut self, source: &Permutation) {
+        self.order.clone_from(&source.order);
+        self.fixed_prefix = source.fixed_prefix;
+        self.cost = source.cost.clone();
+    }
+}
+
+/// The order crossover (see `Permutation::order_crossover`) as a crossover operator, the
+/// second child is created with the roles of the parents swapped.
+#[derive(Debug,Clone,Copy)]
+pub struct OrderCrossover;
+
+impl Crossover<Permutation> for OrderCrossover {
+    fn crossover(&self, first: &Permutation, second: &Permutation, rng: &mut dyn Rng) -> (Permutation, Option<Permutation>) {
+        (first.order_crossover(second, rng), Some(second.order_crossover(first, rng)))
+    }
+}
+
+/// An individual that is a string of bits (for example a selection of items for a knapsack),
+/// implement it to use the generic bit string operators `BitFlip` and `UniformCrossover`.
+pub trait BitString {
+    /// The bits of the individual.
+    fn bits(&self) -> &[bool];
+    /// The bits of the individual, the operators never change the number of bits.
+    fn bits_mut(&mut self) -> &mut [bool];
+}
+
+/// Flips one random bit that is not frozen, see `MutationContext::random_unfrozen_index`.
+#[derive(Debug,Clone,Copy)]
+pub struct BitFlip;
+
+impl<T: BitString> Mutator<T> for BitFlip {
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, context: &MutationContext) {
+        let len = individual.bits().len();
+
+        if let Some(index) = context.random_unfrozen_index(len, rng) {
+            let bits = individual.bits_mut();
+            bits[index] = !bits[index];
+        }
+    }
+}
+
+/// Uniform crossover: each bit of the first child is taken from a randomly chosen parent,
+/// the second child gets the bits of the other parent. Both parents must have the same
+/// number of bits.
+#[derive(Debug,Clone,Copy)]
+pub struct UniformCrossover;
+
+impl<T: BitString + Clone> Crossover<T> for UniformCrossover {
+    fn crossover(&self, first: &T, second: &T, mut rng: &mut dyn Rng) -> (T, Option<T>) {
+        let mut first_child = first.clone();
+        let mut second_child = second.clone();
+
+        for (a, b) in first_child.bits_mut().iter_mut().zip(second_child.bits_mut().iter_mut()) {
+            if Rng::gen(&mut rng) {
+                ::std::mem::swap(a, b);
+            }
+        }
+
+        (first_child, Some(second_child))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{Rng, XorShiftRng, SeedableRng};
+
+    use individual::Individual;
+    use std::sync::Arc;
+
+    use super::{beam_search, shuffle_copies, AdaptivePursuit, BitString, Crossover, Genome, Mutator,
+                MutationContext, Permutation, SharedContextIndividual, UniformCrossover};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Counter {
+        value: u32,
+    }
+
+    impl Individual for Counter {
+        fn new<S>(_data_source: S) -> Counter {
+            Counter { value: 0 }
+        }
+
+        fn mutate(&mut self) {
+            self.value += 1;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value as f64
+        }
+    }
+
+    struct Increment;
+
+    impl Mutator<Counter> for Increment {
+        fn mutate(&self, individual: &mut Counter, _rng: &mut dyn Rng, _context: &MutationContext) {
+            individual.value += 1;
+        }
+    }
+
+    #[test]
+    fn adaptive_pursuit() {
+        let pursuit = AdaptivePursuit::new(vec![("good", Box::new(Increment) as Box<dyn Mutator<Counter>>),
+            ("bad", Box::new(Increment))]);
+        assert_eq!(pursuit.probabilities(), vec![0.5, 0.5]);
+        assert_eq!(pursuit.operator_names(), vec!["good".to_string(), "bad".to_string()]);
+
+        // The second operator never improves an individual
+        for _ in 0..10 {
+            pursuit.update_operators(&[10, 10], &[5, 0]);
+        }
+
+        let probabilities = pursuit.probabilities();
+        assert!(probabilities[0] > 0.9);
+        assert!(probabilities[1] >= pursuit.min_probability - 1.0e-9);
+        assert!((probabilities[0] + probabilities[1] - 1.0).abs() < 1.0e-9);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Bits {
+        bits: Vec<bool>,
+    }
+
+    impl BitString for Bits {
+        fn bits(&self) -> &[bool] {
+            &self.bits
+        }
+
+        fn bits_mut(&mut self) -> &mut [bool] {
+            &mut self.bits
+        }
+    }
+
+    #[test]
+    fn uniform_crossover() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let (first, second) = UniformCrossover.crossover(&Bits { bits: vec![true; 16] },
+            &Bits { bits: vec![false; 16] }, &mut rng);
+        let second = second.unwrap();
+
+        assert!(first.bits.iter().any(|bit| *bit) && first.bits.iter().any(|bit| !*bit));
+        assert!(first.bits.iter().zip(&second.bits).all(|(a, b)| a != b));
+    }
+
+    /// Selects some of the shared values, the fitness is the sum of the selected values.
+    #[derive(Debug, Clone, Default)]
+    struct Selection {
+        indices: Vec<usize>,
+    }
+
+    impl Genome<Vec<f64>> for Selection {
+        fn random(context: &Vec<f64>, mut rng: &mut dyn Rng) -> Selection {
+            Selection { indices: vec![Rng::gen_range(&mut rng, 0, context.len())] }
+        }
+
+        fn mutate(&mut self, context: &Vec<f64>, mut rng: &mut dyn Rng) {
+            self.indices.push(Rng::gen_range(&mut rng, 0, context.len()));
+        }
+
+        fn fitness(&self, context: &Vec<f64>) -> f64 {
+            self.indices.iter().map(|index| context[*index]).sum()
+        }
+    }
+
+    #[test]
+    fn shared_context_individual() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let initializer = SharedContextIndividual::<Vec<f64>, Selection>::initializer(Arc::new(vec![1.0; 100]));
+        let a = initializer(&mut rng);
+        let mut b = a.clone();
+        b.mutate_with_rng(&mut rng);
+
+        assert!(Arc::ptr_eq(&a.context, &b.context));
+        assert_eq!(a.calculate_fitness(), 1.0);
+        assert_eq!(b.calculate_fitness(), 2.0);
+    }
+
+    #[test]
+    fn shuffle_copies_differ() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let copies = shuffle_copies(&Counter::new(()), 10, 100, &mut rng);
+
+        assert_eq!(copies.len(), 10);
+        assert!(copies.iter().any(|copy| *copy != copies[0]));
+        assert!(copies.iter().all(|copy| copy.value <= 100));
+    }
+
+    #[test]
+    fn random_unfrozen_index() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let frozen_components = vec![0, 2, 3];
+        let context = MutationContext { generation: 0, frozen_components: &frozen_components, scale: 1.0 };
+
+        for _ in 0..20 {
+            let index = context.random_unfrozen_index(5, &mut rng).unwrap();
+            assert!(index == 1 || index == 4);
+        }
+
+        assert_eq!(context.random_unfrozen_index(1, &mut rng), None);
+    }
+
+    #[test]
+    fn permutation() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let initializer = Permutation::initializer(8, 2, |order| order[2] as f64);
+        let mut a = initializer(&mut rng);
+        let b = initializer(&mut rng);
+
+        for _ in 0..20 {
+            a.mutate();
+            a.swap_mutation(&mut rng);
+            a.insertion_mutation(&mut rng);
+            a.reversal_mutation(&mut rng);
+        }
+
+        let child = a.order_crossover(&b, &mut rng);
+
+        for permutation in &[a, b, child] {
+            let mut sorted = permutation.order.clone();
+            sorted.sort();
+
+            assert_eq!(sorted, (0..8).collect::<Vec<usize>>());
+            assert_eq!(&permutation.order[..2], &[0, 1]);
+            assert_eq!(permutation.calculate_fitness(), permutation.order[2] as f64);
+        }
+    }
+
+    #[test]
+    fn permutation_seeded_mutation() {
+        let initializer = Permutation::initializer(20, 2, |order| order[2] as f64);
+        let start = initializer(&mut XorShiftRng::from_seed([1, 2, 3, 4]));
+
+        let mutate = |seed: [u32; 4]| {
+            let mut rng = XorShiftRng::from_seed(seed);
+            let mut permutation = start.clone();
+
+            for _ in 0..20 {
+                permutation.mutate_with_rng(&mut rng);
+            }
+
+            permutation.order
+        };
+
+        // The same seed gives the same mutations, another seed other ones
+        assert_eq!(mutate([5, 6, 7, 8]), mutate([5, 6, 7, 8]));
+        assert!(mutate([5, 6, 7, 8]) != mutate([8, 7, 6, 5]));
+        assert_eq!(&mutate([5, 6, 7, 8])[..2], &[0, 1]);
+    }
+
+    #[test]
+    fn beam_search_path() {
+        let start = Permutation {
+            order: Vec::new(),
+            fixed_prefix: 0,
+            cost: Arc::new(|order| order.windows(2).map(|pair| (pair[0] as f64 - pair[1] as f64).abs()).sum()),
+        };
+
+        let beam = beam_search(start, 3, 5, |candidate, _| {
+            (0..5).filter(|element| !candidate.order.contains(element))
+                .map(|element| {
+                    let mut child = candidate.clone();
+                    child.order.push(element);
+                    child
+                })
+                .collect()
+        });
+
+        assert_eq!(beam.len(), 3);
+        assert_eq!(beam[0].calculate_fitness(), 4.0);
+    }
+}
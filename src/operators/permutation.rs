@@ -0,0 +1,144 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Mutations for permutations (like the path of a TSP), they work on plain slices so that
+//! they can be used in any `Individual::mutate` implementation.
+
+use rand::Rng;
+
+use operators::{Mutator, MutationContext, Permutation};
+
+/// Returns two different random positions after the fixed prefix, or None if there are
+/// less than two positions that may change.
+pub fn random_positions(len: usize, fixed_prefix: usize, mut rng: &mut dyn Rng) -> Option<(usize, usize)> {
+    if len < fixed_prefix + 2 {
+        return None;
+    }
+
+    let i = Rng::gen_range(&mut rng, fixed_prefix, len);
+    let mut j = Rng::gen_range(&mut rng, fixed_prefix, len - 1);
+
+    if j >= i {
+        j += 1;
+    }
+
+    Some((i, j))
+}
+
+/// Swaps two random elements of the path, the first `fixed_prefix` elements never change.
+pub fn swap_positions(path: &mut [usize], rng: &mut dyn Rng, fixed_prefix: usize) {
+    if let Some((i, j)) = random_positions(path.len(), fixed_prefix, rng) {
+        path.swap(i, j);
+    }
+}
+
+/// Reverses a random contiguous segment of the path, the first `fixed_prefix` elements never
+/// change. For the TSP this is the core of the well known 2-opt move: it replaces two edges
+/// of the tour and is much more effective than swapping cities alone.
+pub fn reverse_segment(path: &mut [usize], rng: &mut dyn Rng, fixed_prefix: usize) {
+    if let Some((i, j)) = random_positions(path.len(), fixed_prefix, rng) {
+        path[i.min(j)..(i.max(j) + 1)].reverse();
+    }
+}
+
+/// An individual that contains a permutation, this allows to use the `SwapPositions` and
+/// `ReverseSegment` mutators (for example combined with `RandomOperator`).
+pub trait Path {
+    /// The permutation of the individual.
+    fn path_mut(&mut self) -> &mut [usize];
+    /// The number of positions at the beginning of the path that never change.
+    /// The default implementation returns 0.
+    fn fixed_prefix(&self) -> usize {
+        0
+    }
+}
+
+impl Path for Permutation {
+    fn path_mut(&mut self) -> &mut [usize] {
+        &mut self.order
+    }
+
+    fn fixed_prefix(&self) -> usize {
+        self.fixed_prefix
+    }
+}
+
+/// A mutator that swaps two random elements of the path, see `swap_positions`.
+/// Only the fixed prefix is kept, not the frozen components.
+#[derive(Debug,Clone,Copy)]
+pub struct SwapPositions;
+
+impl<T: Path> Mutator<T> for SwapPositions {
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, _context: &MutationContext) {
+        let fixed_prefix = individual.fixed_prefix();
+        swap_positions(individual.path_mut(), rng, fixed_prefix);
+    }
+}
+
+/// A mutator that reverses a random segment of the path, see `reverse_segment`.
+/// Only the fixed prefix is kept, not the frozen components.
+#[derive(Debug,Clone,Copy)]
+pub struct ReverseSegment;
+
+impl<T: Path> Mutator<T> for ReverseSegment {
+    fn mutate(&self, individual: &mut T, rng: &mut dyn Rng, _context: &MutationContext) {
+        let fixed_prefix = individual.fixed_prefix();
+        reverse_segment(individual.path_mut(), rng, fixed_prefix);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{XorShiftRng, SeedableRng};
+
+    use operators::{Mutator, MutationContext, Permutation, RandomOperator};
+    use super::{reverse_segment, ReverseSegment, SwapPositions};
+
+    #[test]
+    fn reverse_segment_keeps_permutation() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut path: Vec<usize> = (0..10).collect();
+
+        for _ in 0..50 {
+            reverse_segment(&mut path, &mut rng, 3);
+
+            let mut sorted = path.clone();
+            sorted.sort();
+
+            assert_eq!(sorted, (0..10).collect::<Vec<usize>>());
+            assert_eq!(&path[..3], &[0, 1, 2]);
+        }
+
+        assert!(path != (0..10).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn swap_and_reverse_operators() {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let mut permutation = Permutation::initializer(8, 1, |order| order[1] as f64)(&mut rng);
+        let operator = RandomOperator::new()
+            .add("swap", 1.0, Box::new(SwapPositions))
+            .add("reverse", 1.0, Box::new(ReverseSegment));
+        let context = MutationContext { generation: 0, frozen_components: &[], scale: 1.0 };
+        let mut applied = vec![0; 2];
+
+        for _ in 0..50 {
+            applied[operator.mutate_with_operator(&mut permutation, &mut rng, &context).unwrap()] += 1;
+        }
+
+        let mut sorted = permutation.order.clone();
+        sorted.sort();
+
+        assert_eq!(sorted, (0..8).collect::<Vec<usize>>());
+        assert_eq!(permutation.order[0], 0);
+        assert!(applied[0] > 0 && applied[1] > 0);
+    }
+}
@@ -11,10 +11,1003 @@
 //!
 //!
 
-use std::sync::Mutex;
+use std::collections::{HashSet, VecDeque};
+use std::iter;
+use std::mem;
+use std::ops::Range;
+#[cfg(feature = "async")]
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+#[cfg(feature = "profiling")]
+use std::time::Instant;
 
-use simulation::SimulationResult;
-use individual::{Individual, IndividualWrapper};
+use rand::{self, Rng, SeedableRng, XorShiftRng};
+#[cfg(feature = "profiling")]
+use profiling::{elapsed_nanos, Profile};
+
+use simulation::{Pipeline, SimulationResult, SimulationType};
+use individual::{Individual, IndividualWrapper, FitnessContext, FitnessGoal, Origin};
+use operators::{Crossover, Mutator, MutationContext, OperatorStat};
+use statistics::{mean, percentile, standard_deviation};
+#[cfg(feature = "async")]
+use async_evaluation::join_bounded;
+
+/// On a fitness plateau (see `SimulationBuilder::plateau_threshold`) the individuals are mutated
+/// this many times more often than their mutation rate says.
+pub const HYPER_MUTATION_FACTOR: u32 = 10;
+
+/// The `PopulationStats` type. Contains some statistics about the last iteration of a
+/// population.
+#[derive(Debug,Clone)]
+pub struct PopulationStats {
+    /// The mean variance of the repeated fitness evaluations (see `fitness_samples`) of all
+    /// the individuals evaluated in the last iteration. This is a measure for the noise of the
+    /// fitness function, it is always 0.0 if the fitness is only calculated once.
+    pub sample_variance: f64,
+    /// True if all the individuals were discarded and re-initialized in the last iteration
+    /// because the reset limit was reached.
+    pub reset: bool,
+    /// True if the fitness variance of the population was below the plateau threshold in the
+    /// last iteration, so that the individuals were hyper-mutated.
+    pub plateau_kick: bool,
+    /// The number of duplicates that were replaced in the last iteration,
+    /// see `PopulationBuilder::dedup`.
+    pub duplicates_replaced: usize,
+    /// The number of offspring that were changed by `Individual::repair` in the last
+    /// iteration, see `PopulationBuilder::enable_repair`.
+    pub repairs: u64,
+    /// The mean fitness of the individuals the survivors were selected from in the last
+    /// iteration, see `PopulationBuilder::fitness_scaling`.
+    pub fitness_mean: f64,
+    /// The standard deviation of the fitness of the individuals the survivors were selected
+    /// from in the last iteration.
+    pub fitness_std_dev: f64,
+    /// The number of different fitness values among the survivors of the last iteration
+    /// divided by their number: 1.0 if all are different, nearly 0.0 if the population has
+    /// converged. See `SimulationBuilder::auto_tune_selection`.
+    pub diversity: f64,
+    /// The recovery policy that was applied in the last iteration because no individual was
+    /// feasible, see `SimulationBuilder::infeasibility_recovery`.
+    pub infeasibility_recovery: Option<InfeasibilityRecovery>,
+}
+
+/// Transforms the fitness for the selection, see `PopulationBuilder::fitness_scaling`.
+pub type FitnessScaling = Arc<dyn Fn(f64, &PopulationStats) -> f64 + Send + Sync>;
+
+/// A `FitnessScaling` as it is given to `PopulationBuilder::fitness_scaling`.
+pub type BoxedFitnessScaling = Box<dyn Fn(f64, &PopulationStats) -> f64 + Send + Sync>;
+
+/// Sorts the individuals for the selection: by the scaled fitness if a fitness scaling is
+/// given (ties are sorted by fitness), otherwise just by fitness. Only for choosing the
+/// survivors: they must be sorted by fitness again afterwards, since the rest of the
+/// iteration expects the fittest individual first.
+fn sort_for_selection<T: Individual>(population: &mut [IndividualWrapper<T>], fitness_scaling: &Option<FitnessScaling>,
+        stats: &PopulationStats) {
+    match *fitness_scaling {
+        Some(ref fitness_scaling) => population.sort_by(|a, b| {
+            fitness_scaling(a.fitness, stats).partial_cmp(&fitness_scaling(b.fitness, stats))
+                .expect("Scaled fitness is NaN")
+                .then_with(|| a.cmp(b))
+        }),
+        None => population.sort()
+    }
+}
+
+/// Decides if an offspring (first argument) replaces its parent (second argument), see
+/// `PopulationBuilder::replacement_policy`.
+pub type ReplacementPolicy<T> = Arc<dyn Fn(&IndividualWrapper<T>, &IndividualWrapper<T>) -> bool + Send + Sync>;
+
+/// A `ReplacementPolicy` as it is given to `PopulationBuilder::replacement_policy`.
+pub type BoxedReplacementPolicy<T> = Box<dyn Fn(&IndividualWrapper<T>, &IndividualWrapper<T>) -> bool + Send + Sync>;
+
+/// Creates new individuals instead of `Individual::new`, see `PopulationBuilder::initializer`.
+/// It gets the random number generator as a `Rng` trait object, like
+/// `Individual::mutate_with_rng` (`Rng` is the object safe base trait of rand 0.3).
+pub type Initializer<T> = Arc<dyn Fn(&mut dyn Rng) -> T + Send + Sync>;
+
+/// An `Initializer` as it is given to `PopulationBuilder::initializer`.
+pub type BoxedInitializer<T> = Box<dyn Fn(&mut dyn Rng) -> T + Send + Sync>;
+
+/// Creates a new individual with the given initializer or, if there is none, with
+/// `Individual::new`. The initializer gets a random number generator seeded for the
+/// individual with the given index (see `FitnessEvaluator::seeded_rng`), an unseeded one if
+/// the population has no mutation seed.
+pub fn new_individual<S, T: Individual>(data_source: &S, initializer: &Option<Initializer<T>>,
+        evaluator: &mut FitnessEvaluator, index: usize) -> T {
+    match *initializer {
+        Some(ref initializer) => initializer(&mut evaluator.seeded_rng(index, RNG_STREAM_NEW)),
+        None => Individual::new(data_source)
+    }
+}
+
+/// Mutates the individual (as often as its mutation rate says) and calculates its new fitness.
+/// This is the expensive part of each iteration and the simulation runs it for all individuals
+/// of all populations in parallel.
+/// If a mutator is given it is used instead of `Individual::mutate`.
+/// Infeasible offspring are kept as they are, use `mutate_and_evaluate_feasible` to retry
+/// their mutation (see `PopulationBuilder::feasibility_retries`).
+pub fn mutate_and_evaluate<T: Individual>(wrapper: &mut IndividualWrapper<T>, mutator: Option<&dyn Mutator<T>>,
+        evaluator: &mut FitnessEvaluator) {
+    let parent_fitness = wrapper.fitness;
+    let operators = mutate_offspring(wrapper, mutator, evaluator);
+
+    evaluate_offspring(wrapper, parent_fitness, &operators, evaluator);
+}
+
+/// Like `mutate_and_evaluate`, but the mutation of an infeasible offspring is retried from
+/// the original individual, see `PopulationBuilder::feasibility_retries`. This is the one the
+/// simulation uses.
+pub fn mutate_and_evaluate_feasible<T: Individual + Clone>(wrapper: &mut IndividualWrapper<T>,
+        mutator: Option<&dyn Mutator<T>>, evaluator: &mut FitnessEvaluator) {
+    let parent_fitness = wrapper.fitness;
+    let operators = mutate_offspring_feasible(wrapper, mutator, evaluator);
+
+    evaluate_offspring(wrapper, parent_fitness, &operators, evaluator);
+}
+
+/// The second part of `mutate_and_evaluate`: calculates the fitness of the mutated individual
+/// and counts the mutation and the applied operators.
+fn evaluate_offspring<T: Individual>(wrapper: &mut IndividualWrapper<T>, parent_fitness: f64, operators: &[usize],
+        evaluator: &mut FitnessEvaluator) {
+    #[cfg(feature = "profiling")]
+    let start = Instant::now();
+
+    evaluator.evaluate(wrapper);
+
+    #[cfg(feature = "profiling")]
+    evaluator.add_profile(&["evaluation"], elapsed_nanos(start));
+
+    evaluator.count_mutation(parent_fitness, wrapper.fitness);
+    evaluator.count_operators(operators, parent_fitness, wrapper.fitness);
+}
+
+/// Calculates the fitness of the given individuals with `Individual::calculate_fitness_async`,
+/// at most `max_concurrent` calculations (samples) at the same time, and counts each one with
+/// its evaluator, see `SimulationBuilder::max_concurrent_evals`. Individuals with a valid
+/// fitness are skipped (see `PopulationBuilder::dirty_tracking`), a cancelled calculation
+/// makes the individual the least fit one. A panic in a calculation is caught, the other
+/// calculations still finish. Returns the indices of the jobs that panicked, these individuals
+/// are the least fit ones as well.
+#[cfg(feature = "async")]
+pub fn evaluate_async<T: Individual>(jobs: &mut [(&mut IndividualWrapper<T>, &mut FitnessEvaluator)],
+        max_concurrent: usize, cancel: &AtomicBool) -> Vec<usize> {
+    let pending: Vec<usize> = (0..jobs.len()).filter(|index| jobs[*index].1.needs_evaluation(jobs[*index].0))
+        .collect();
+    let mut panicked = Vec::new();
+    let mut started = Vec::with_capacity(pending.len());
+    let mut futures = Vec::new();
+
+    for index in pending {
+        let (ref wrapper, ref evaluator) = jobs[index];
+        let samples = panic::catch_unwind(AssertUnwindSafe(|| (0..evaluator.samples())
+            .map(|_| wrapper.individual.calculate_fitness_async())
+            .collect::<Vec<_>>()));
+
+        match samples {
+            Ok(samples) => {
+                futures.extend(samples);
+                started.push(index);
+            }
+            Err(_) => panicked.push(index),
+        }
+    }
+
+    let mut samples = join_bounded(futures, max_concurrent, cancel).into_iter();
+
+    for index in started {
+        let (ref mut wrapper, ref mut evaluator) = jobs[index];
+        let mut fitness = Vec::new();
+        let mut cancelled = false;
+        let mut failed = false;
+
+        for sample in samples.by_ref().take(evaluator.samples() as usize) {
+            match sample {
+                Some(Ok(value)) => fitness.push(value),
+                Some(Err(_)) => failed = true,
+                None => cancelled = true,
+            }
+        }
+
+        if failed {
+            panicked.push(index);
+        }
+        if cancelled || failed {
+            fitness.clear();
+        }
+        evaluator.evaluate_samples(wrapper, &fitness);
+    }
+
+    panicked.sort();
+    panicked
+}
+
+/// The first part of `mutate_and_evaluate`: mutates the individual (and repairs it if enabled),
+/// but does not calculate its fitness yet. Returns the indices of the applied operators.
+pub fn mutate_offspring<T: Individual>(wrapper: &mut IndividualWrapper<T>, mutator: Option<&dyn Mutator<T>>,
+        evaluator: &mut FitnessEvaluator) -> Vec<usize> {
+    let operators = mutate(&mut wrapper.individual, wrapper.fitness, wrapper.num_of_mutations, mutator, evaluator);
+    finish_offspring(wrapper, evaluator);
+
+    operators
+}
+
+/// The first part of `mutate_and_evaluate_feasible`, like `mutate_offspring` but with the
+/// retries of infeasible offspring.
+pub fn mutate_offspring_feasible<T: Individual + Clone>(wrapper: &mut IndividualWrapper<T>,
+        mutator: Option<&dyn Mutator<T>>, evaluator: &mut FitnessEvaluator) -> Vec<usize> {
+    let operators = mutate_feasible(&mut wrapper.individual, wrapper.fitness, wrapper.num_of_mutations, mutator,
+        evaluator);
+    finish_offspring(wrapper, evaluator);
+
+    operators
+}
+
+/// Marks the mutated individual as dirty, repairs it (if enabled) and records the mutation in
+/// its lineage.
+fn finish_offspring<T: Individual>(wrapper: &mut IndividualWrapper<T>, evaluator: &mut FitnessEvaluator) {
+    wrapper.dirty |= wrapper.num_of_mutations > 0;
+    wrapper.dirty |= repair(&mut wrapper.individual, evaluator);
+
+    if evaluator.track_lineage && wrapper.num_of_mutations > 0 {
+        wrapper.record_lineage(Origin::Mutation, evaluator.context.generation);
+    }
+}
+
+/// Like `mutate`, but if the mutated individual is infeasible the mutation of the original
+/// individual is repeated (repair by rejection), see `PopulationBuilder::feasibility_retries`.
+fn mutate_feasible<T: Individual + Clone>(individual: &mut T, fitness: f64, num_of_mutations: u32,
+        mutator: Option<&dyn Mutator<T>>, evaluator: &mut FitnessEvaluator) -> Vec<usize> {
+    if evaluator.feasibility_retries == 0 || num_of_mutations == 0 {
+        return mutate(individual, fitness, num_of_mutations, mutator, evaluator);
+    }
+
+    let parent = individual.clone();
+    let mut operators = mutate(individual, fitness, num_of_mutations, mutator, evaluator);
+    let mut retries = 0;
+
+    while retries < evaluator.feasibility_retries && individual.constraint_violation() > 0.0 {
+        individual.reuse_from(&parent);
+        evaluator.reseed_rng();
+        operators = mutate(individual, fitness, num_of_mutations, mutator, evaluator);
+        retries += 1;
+    }
+
+    operators
+}
+
+/// Repairs the individual if the population has repair enabled and counts the repair, see
+/// `PopulationBuilder::enable_repair`. Returns true if the individual was changed.
+fn repair<T: Individual>(individual: &mut T, evaluator: &mut FitnessEvaluator) -> bool {
+    if evaluator.repair && individual.repair() {
+        evaluator.repairs += 1;
+        true
+    } else {
+        false
+    }
+}
+
+/// Mutates the individual `num_of_mutations` times, either with the given mutator or with
+/// `Individual::mutate_with_rng`. The fitness of the individual before the mutation is used
+/// for the mutation scale, see `PopulationBuilder::fitness_scaled_mutation`.
+/// The random number generator is seeded by the evaluator if the population has a mutation
+/// seed (see `FitnessEvaluator::seed_rng`), otherwise the thread local one is used.
+/// Returns the indices of the applied operators, see `Mutator::mutate_with_operator`.
+fn mutate<T: Individual>(individual: &mut T, fitness: f64, num_of_mutations: u32, mutator: Option<&dyn Mutator<T>>,
+        evaluator: &mut FitnessEvaluator) -> Vec<usize> {
+    match evaluator.rng_seed {
+        Some(seed) => mutate_with(individual, fitness, num_of_mutations, mutator, evaluator,
+            &mut XorShiftRng::from_seed(seed)),
+        None => mutate_with(individual, fitness, num_of_mutations, mutator, evaluator, &mut rand::thread_rng())
+    }
+}
+
+/// The part of `mutate` that uses the given random number generator.
+fn mutate_with<T: Individual, R: Rng>(individual: &mut T, fitness: f64, num_of_mutations: u32,
+        mutator: Option<&dyn Mutator<T>>, evaluator: &mut FitnessEvaluator, rng: &mut R) -> Vec<usize> {
+    let mut operators = Vec::new();
+
+    match mutator {
+        Some(mutator) => {
+            let frozen_components = evaluator.frozen_components.clone();
+            let context = MutationContext {
+                generation: evaluator.context.generation,
+                frozen_components: &frozen_components,
+                scale: evaluator.mutation_scale(fitness),
+            };
+
+            for _ in 0..num_of_mutations {
+                #[cfg(feature = "profiling")]
+                let start = Instant::now();
+
+                let operator = mutator.mutate_with_operator(individual, rng, &context);
+
+                // The operators are named in `Population::update_result`
+                #[cfg(feature = "profiling")]
+                match operator {
+                    Some(operator) => evaluator.add_profile(&["mutation", &operator.to_string()], elapsed_nanos(start)),
+                    None => evaluator.add_profile(&["mutation"], elapsed_nanos(start)),
+                }
+
+                if let Some(operator) = operator {
+                    operators.push(operator);
+                }
+            }
+        }
+        None => {
+            #[cfg(feature = "profiling")]
+            let start = Instant::now();
+
+            for _ in 0..num_of_mutations {
+                individual.mutate_with_rng(rng);
+            }
+
+            #[cfg(feature = "profiling")]
+            evaluator.add_profile(&["mutation"], elapsed_nanos(start));
+        }
+    }
+
+    operators
+}
+
+/// The random number streams of `FitnessEvaluator::seed_rng`, so that the different kinds of
+/// mutation of the same individual in the same generation don't use the same numbers.
+pub const RNG_STREAM_MUTATION: u64 = 0;
+/// The stream for the offspring of the survivors, see `PopulationBuilder::survival_rate`.
+pub const RNG_STREAM_OFFSPRING: u64 = 1;
+/// The stream for the hyper mutation, see `SimulationBuilder::plateau_threshold`.
+pub const RNG_STREAM_HYPER_MUTATION: u64 = 2;
+/// The stream for the mutations of the initial population, see
+/// `PopulationBuilder::initial_mutations`.
+pub const RNG_STREAM_INITIAL: u64 = 3;
+/// The stream for the mutated copies of the seeds, see `PopulationBuilder::seed_individuals`.
+pub const RNG_STREAM_SEEDS: u64 = 4;
+/// The stream for the selection of the parents, see `PopulationBuilder::selection_strategy`.
+pub const RNG_STREAM_SELECTION: u64 = 5;
+/// The stream for the crossover, see `PopulationBuilder::crossover`.
+pub const RNG_STREAM_CROSSOVER: u64 = 6;
+/// The stream for new individuals, see `PopulationBuilder::initializer`.
+pub const RNG_STREAM_NEW: u64 = 7;
+
+/// Below this diversity the selection pressure is lowered, see
+/// `SimulationBuilder::auto_tune_selection`.
+pub const DIVERSITY_LOW: f64 = 0.2;
+/// Above this diversity the selection pressure is raised again.
+pub const DIVERSITY_HIGH: f64 = 0.5;
+/// The lowest selection pressure the automatic tuning goes down to.
+pub const MIN_SELECTION_PRESSURE: f64 = 0.1;
+
+/// Returns the number of different fitness values divided by the number of individuals, see
+/// `PopulationStats::diversity`.
+fn fitness_diversity<T: Individual>(population: &[IndividualWrapper<T>]) -> f64 {
+    if population.is_empty() {
+        return 0.0;
+    }
+
+    let mut fitness: Vec<f64> = population.iter().map(|wrapper| wrapper.fitness).collect();
+    fitness.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Equal));
+    fitness.dedup();
+
+    (fitness.len() as f64) / (population.len() as f64)
+}
+
+/// Returns the goal of the objective with the given index, minimize if there is none.
+fn goal_or_minimize(objective_goals: &[FitnessGoal], index: usize) -> FitnessGoal {
+    objective_goals.get(index).cloned().unwrap_or(FitnessGoal::Minimize)
+}
+
+/// Normalizes the given objectives (with their original sign) with the objective bounds, so
+/// that each bound range maps to [0.0, 1.0]. Values outside the bounds are not clamped, they
+/// map below 0.0 or above 1.0. A maximized objective is flipped, so that 0.0 is always the
+/// best bound. Without bounds the objectives are returned unchanged. See
+/// `SimulationBuilder::objective_bounds`.
+pub fn normalize_objectives(objectives: &[f64], objective_bounds: &[(f64, f64)],
+        objective_goals: &[FitnessGoal]) -> Vec<f64> {
+    if objective_bounds.is_empty() {
+        return objectives.to_vec();
+    }
+
+    objectives.iter().zip(objective_bounds.iter()).enumerate()
+        .map(|(index, (objective, &(min, max)))| {
+            let normalized = (objective - min) / (max - min);
+
+            match goal_or_minimize(objective_goals, index) {
+                FitnessGoal::Maximize => 1.0 - normalized,
+                FitnessGoal::Minimize => normalized
+            }
+        })
+        .collect()
+}
+
+/// One step of the SplitMix64 generator, used to derive the seeds of the individuals.
+fn split_mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// Derives the seed of a random number generator from the mutation seed of a population, the
+/// stream of the simulation (see `SimulationBuilder::rng_stream`), the generation, the index of
+/// the individual and the stream of the kind of mutation, see `FitnessEvaluator::seed_rng`.
+/// A simulation stream of 0 leaves the mutation seed as it is.
+fn derive_seed(mutation_seed: u64, rng_stream: u64, generation: u32, index: usize, stream: u64) -> [u32; 4] {
+    // Mixed, because with a plain xor of the stream a neighbouring mutation seed would give
+    // the same generators as another stream
+    let mutation_seed = if rng_stream == 0 {
+        mutation_seed
+    } else {
+        split_mix(split_mix(mutation_seed) ^ rng_stream)
+    };
+    let first = split_mix(mutation_seed ^ split_mix(((generation as u64) << 32) ^ index as u64));
+    let second = split_mix(first ^ stream);
+
+    // XorShift must not be seeded with zeros only
+    [first as u32, (first >> 32) as u32, second as u32, ((second >> 32) as u32) | 1]
+}
+
+/// How the fitness samples of an individual (see `PopulationBuilder::fitness_samples`) are
+/// combined into one fitness value.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Aggregator {
+    /// The mean of all samples (default).
+    Mean,
+    /// The best (lowest) sample, optimistic.
+    Min,
+    /// The worst (highest) sample, worst case.
+    Max,
+    /// The mean plus the given multiple of the standard deviation of the samples, risk averse.
+    MeanPlusStd(f64),
+    /// The given percentile (0.0 - 100.0) of the samples.
+    Percentile(f64),
+}
+
+impl Aggregator {
+    /// Combines the samples into one fitness value.
+    pub fn aggregate(&self, samples: &[f64]) -> f64 {
+        match *self {
+            Aggregator::Mean => mean(samples),
+            Aggregator::Min => samples.iter().cloned().fold(f64::MAX, f64::min),
+            Aggregator::Max => samples.iter().cloned().fold(f64::MIN, f64::max),
+            Aggregator::MeanPlusStd(factor) => mean(samples) + factor * standard_deviation(samples),
+            Aggregator::Percentile(value) => percentile(samples, value),
+        }
+    }
+}
+
+/// How the parent of each offspring is chosen among the survivors (see
+/// `PopulationBuilder::survival_rate`), set it with `PopulationBuilder::selection_strategy`.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum SelectionStrategy {
+    /// Every survivor is the parent of the same number of offspring (default).
+    RoundRobin,
+    /// Linear ranking: the probability to be chosen only depends on the rank of a survivor,
+    /// not on its fitness, so the selection pressure does not depend on the scale of the
+    /// fitness values. The pressure (1.0 - 2.0) is the expected number of offspring of the
+    /// fittest survivor relative to the average, 1.0 chooses uniformly.
+    RankBased { pressure: f64 },
+    /// Tournament selection: the fittest of `size` randomly chosen survivors. Ties between
+    /// equally fit competitors are broken as configured with
+    /// `PopulationBuilder::tournament_tie_break`.
+    Tournament { size: usize },
+    /// Boltzmann selection: the probability to be chosen is proportional to
+    /// exp(-fitness / T), the temperature T = initial_temperature * cooling_rate^generation
+    /// goes down over the generations. A high temperature chooses nearly uniformly
+    /// (exploration), a low one nearly always chooses the fittest survivors (exploitation).
+    /// The temperature must be > 0.0 and the cooling rate between 0.0 and 1.0, 1.0 keeps
+    /// the temperature constant.
+    Boltzmann { initial_temperature: f64, cooling_rate: f64 },
+}
+
+/// What is done if no individual of a population has been feasible (see
+/// `Individual::constraint_violation`) for several iterations, see
+/// `SimulationBuilder::infeasibility_recovery`. Afterwards all individuals are evaluated again.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum InfeasibilityRecovery {
+    /// Replace all individuals with new ones, like a reset (see
+    /// `PopulationBuilder::reset_limit_end`).
+    Reseed,
+    /// Halve the penalty of constraint violations (`FitnessContext::penalty_scale`), so that
+    /// the fitness guides less towards the feasible region and more towards good solutions.
+    /// This only helps if the fitness uses the penalty scale, see
+    /// `Individual::calculate_fitness_with_context`. The penalty is not raised again.
+    RelaxPenalty,
+    /// Repair all individuals with `Individual::repair`, even if the repair is not enabled
+    /// (see `PopulationBuilder::enable_repair`).
+    Repair,
+}
+
+/// How a tie between equally fit competitors of a tournament is broken, see
+/// `SelectionStrategy::Tournament`.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum TieBreak {
+    /// Choose one of the tied competitors randomly (default). This uses the random number
+    /// generator of the selection, so it is reproducible with a
+    /// `PopulationBuilder::mutation_seed`.
+    Random,
+    /// Choose the competitor that comes first in the population, deterministic.
+    First,
+    /// Choose the competitor with the greatest total distance to all the other survivors (the
+    /// most unique one), see `Individual::distance`.
+    ByDiversity,
+}
+
+impl TieBreak {
+    /// Chooses one of the tied competitors (indices into `parents`, in ascending order).
+    fn choose<T: Individual, R: Rng>(&self, tied: &[usize], parents: &[IndividualWrapper<T>], rng: &mut R) -> usize {
+        match *self {
+            TieBreak::Random => tied[rng.gen_range(0, tied.len())],
+            TieBreak::First => tied[0],
+            TieBreak::ByDiversity => {
+                let uniqueness = |index: usize| parents.iter()
+                    .map(|parent| parents[index].individual.distance(&parent.individual))
+                    .sum::<f64>();
+
+                tied.iter().cloned()
+                    .map(|index| (index, uniqueness(index)))
+                    .fold((tied[0], f64::MIN), |best, candidate| if candidate.1 > best.1 { candidate } else { best })
+                    .0
+            }
+        }
+    }
+}
+
+impl SelectionStrategy {
+    /// Returns this strategy with its selection pressure scaled by `pressure` (0.0 - 1.0, 1.0
+    /// keeps the strategy as it is): a smaller tournament, a lower rank based pressure or a
+    /// higher Boltzmann temperature. Round robin has no pressure to scale.
+    /// See `SimulationBuilder::auto_tune_selection`.
+    pub fn scaled(&self, pressure: f64) -> SelectionStrategy {
+        let pressure = pressure.clamp(0.0, 1.0);
+
+        match *self {
+            SelectionStrategy::RoundRobin => SelectionStrategy::RoundRobin,
+            SelectionStrategy::Tournament { size } => {
+                SelectionStrategy::Tournament { size: ((size as f64) * pressure).round().max(1.0) as usize }
+            }
+            SelectionStrategy::RankBased { pressure: rank_pressure } => {
+                SelectionStrategy::RankBased { pressure: 1.0 + (rank_pressure - 1.0) * pressure }
+            }
+            SelectionStrategy::Boltzmann { initial_temperature, cooling_rate } => SelectionStrategy::Boltzmann {
+                initial_temperature: initial_temperature / pressure.max(f64::MIN_POSITIVE),
+                cooling_rate,
+            },
+        }
+    }
+
+    /// Returns the index of the parent of the offspring with the given index, the parents
+    /// must be sorted by fitness (0 = fittest). The generation is only needed for the
+    /// temperature of the Boltzmann selection.
+    pub fn select<T: Individual, R: Rng>(&self, index: usize, parents: &[IndividualWrapper<T>], tie_break: TieBreak,
+            generation: u32, rng: &mut R) -> usize {
+        let num_of_parents = parents.len();
+
+        match *self {
+            SelectionStrategy::RoundRobin => index % num_of_parents,
+            SelectionStrategy::Tournament { size } => {
+                let mut competitors: Vec<usize> = (0..size.max(1)).map(|_| rng.gen_range(0, num_of_parents)).collect();
+                competitors.sort();
+                competitors.dedup();
+
+                let winner = competitors.iter().map(|competitor| &parents[*competitor]).min()
+                    .expect("Tournament without competitors");
+                let tied: Vec<usize> = competitors.iter().cloned()
+                    .filter(|competitor| parents[*competitor] == *winner)
+                    .collect();
+
+                tie_break.choose(&tied, parents, rng)
+            }
+            SelectionStrategy::RankBased { pressure } => {
+                if num_of_parents < 2 {
+                    return 0;
+                }
+
+                let n = num_of_parents as f64;
+                let mut remaining = rng.gen_range(0.0, 1.0);
+
+                for rank in 0..num_of_parents {
+                    remaining -= (pressure - (2.0 * pressure - 2.0) * (rank as f64) / (n - 1.0)) / n;
+
+                    if remaining < 0.0 {
+                        return rank;
+                    }
+                }
+
+                num_of_parents - 1
+            }
+            SelectionStrategy::Boltzmann { initial_temperature, cooling_rate } => {
+                let temperature = (initial_temperature * cooling_rate.powf(generation as f64))
+                    .max(f64::MIN_POSITIVE);
+                // Shift by the best fitness so that exp() can not overflow, the fittest
+                // survivor always has weight 1.0
+                let best_fitness = parents[0].fitness;
+                let weights: Vec<f64> = parents.iter()
+                    .map(|parent| (-(parent.fitness - best_fitness) / temperature).exp())
+                    .collect();
+                let mut remaining = rng.gen_range(0.0, 1.0) * weights.iter().sum::<f64>();
+
+                for (rank, weight) in weights.iter().enumerate() {
+                    remaining -= *weight;
+
+                    if remaining < 0.0 {
+                        return rank;
+                    }
+                }
+
+                0
+            }
+        }
+    }
+}
+
+/// Which cells of the grid are the neighbors of a cell in a spatial population.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Neighborhood {
+    /// The four direct neighbors (north, east, south, west).
+    VonNeumann,
+    /// The eight surrounding cells (including the diagonal ones).
+    Moore,
+}
+
+/// The grid of a spatial population, see `PopulationBuilder::spatial`.
+#[derive(Debug,Clone)]
+pub struct Grid {
+    /// The number of cells in x direction.
+    pub width: usize,
+    /// The number of cells in y direction.
+    pub height: usize,
+    /// Which cells are neighbors.
+    pub neighborhood: Neighborhood,
+}
+
+impl Grid {
+    /// Returns the neighbors of the given cell (index = y * width + x), the edges of the grid
+    /// wrap around (torus).
+    pub fn neighbors(&self, cell: usize) -> Vec<usize> {
+        let (x, y) = ((cell % self.width) as isize, (cell / self.width) as isize);
+        let offsets: &[(isize, isize)] = match self.neighborhood {
+            Neighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+            Neighborhood::Moore => &[(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)],
+        };
+        let (width, height) = (self.width as isize, self.height as isize);
+
+        offsets.iter()
+            .map(|&(dx, dy)| ((((y + dy) % height + height) % height) * width + ((x + dx) % width + width) % width) as usize)
+            .filter(|neighbor| *neighbor != cell)
+            .collect()
+    }
+}
+
+/// Calculates the fitness of individuals and counts the number of fitness evaluations,
+/// mutations and selections.
+#[derive(Debug,Clone)]
+pub struct FitnessEvaluator {
+    /// Passed to the fitness calculation of each individual.
+    context: FitnessContext,
+    /// How often the fitness of each individual is calculated.
+    samples: u32,
+    /// If true only changed (dirty) individuals are evaluated.
+    dirty_tracking: bool,
+    /// If true the lineage of mutated individuals is recorded.
+    track_lineage: bool,
+    /// How the samples are combined into one fitness value.
+    aggregator: Aggregator,
+    /// The total number of calls to calculate_fitness.
+    fitness_evaluations: u64,
+    /// The number of evaluated individuals.
+    num_of_evaluated: u64,
+    /// The sum of the sample variances of all evaluated individuals.
+    variance_sum: f64,
+    /// The number of lexicographically compared objectives, 0 means scalar fitness.
+    num_of_objectives: usize,
+    /// The goal of each objective, empty means all objectives are minimized.
+    objective_goals: Arc<Vec<FitnessGoal>>,
+    /// The components of the individuals that the mutator must not change.
+    frozen_components: Arc<Vec<usize>>,
+    /// The number of mutated individuals.
+    mutations: u64,
+    /// The number of mutated individuals that are fitter than their parent.
+    successful_mutations: u64,
+    /// The minimal fitness improvement for a successful mutation.
+    improvement_epsilon: f64,
+    /// The best and the worst fitness of the population before the mutation, only set for
+    /// fitness scaled mutation.
+    fitness_range: Option<(f64, f64)>,
+    /// The number of individuals selected to survive or to be a parent.
+    selections: u64,
+    /// The number of crossover operations.
+    crossovers: u64,
+    /// The number of applications of each operator of the mutator, see `OperatorStat`.
+    operator_applications: Vec<u64>,
+    /// The number of applications of each operator that made the individual fitter.
+    operator_improvements: Vec<u64>,
+    /// Passed to the fitness calculation, set to cancel it.
+    cancel: Arc<AtomicBool>,
+    /// The seed of the population, see `PopulationBuilder::mutation_seed`.
+    mutation_seed: Option<u64>,
+    /// The seed for the random number generator of the next mutation, see `seed_rng`.
+    rng_seed: Option<[u32; 4]>,
+    /// Selects another stream of the seeded random number generators.
+    rng_stream: u64,
+    /// How often an infeasible offspring is mutated again from its parent.
+    feasibility_retries: u32,
+    /// If true the offspring are repaired before the fitness calculation.
+    repair: bool,
+    /// The number of offspring that were changed by the repair.
+    repairs: u64,
+    /// The timings of this population, see `SimulationResult::profile`.
+    #[cfg(feature = "profiling")]
+    profile: Profile,
+}
+
+impl FitnessEvaluator {
+    /// Creates a new evaluator for the given generation, the fitness of each individual is
+    /// calculated `samples` times. If `num_of_objectives` is greater than zero the
+    /// objectives are calculated with `Individual::calculate_fitness_multi` and maximized
+    /// objectives (see `objective_goals`) are negated.
+    pub fn new(generation: u32, samples: u32, num_of_objectives: usize,
+            objective_goals: Arc<Vec<FitnessGoal>>) -> FitnessEvaluator {
+        FitnessEvaluator {
+            context: FitnessContext::new(generation),
+            samples: samples.max(1),
+            aggregator: Aggregator::Mean,
+            dirty_tracking: false,
+            track_lineage: false,
+            fitness_evaluations: 0,
+            num_of_evaluated: 0,
+            variance_sum: 0.0,
+            num_of_objectives,
+            objective_goals,
+            frozen_components: Arc::new(Vec::new()),
+            mutations: 0,
+            successful_mutations: 0,
+            improvement_epsilon: 0.0,
+            fitness_range: None,
+            selections: 0,
+            crossovers: 0,
+            operator_applications: Vec::new(),
+            operator_improvements: Vec::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            mutation_seed: None,
+            rng_seed: None,
+            rng_stream: 0,
+            feasibility_retries: 0,
+            repair: false,
+            repairs: 0,
+            #[cfg(feature = "profiling")]
+            profile: Profile::new(),
+        }
+    }
+
+    /// Calculates the fitness of the wrapped individual as the mean of all the samples.
+    /// In lexicographic mode the objectives are stored as well and the fitness is the first
+    /// objective.
+    /// With dirty tracking unchanged individuals keep their fitness.
+    pub fn evaluate<T: Individual>(&mut self, wrapper: &mut IndividualWrapper<T>) {
+        if !self.needs_evaluation(wrapper) {
+            return;
+        }
+
+        wrapper.dirty = false;
+
+        if self.num_of_objectives > 0 {
+            wrapper.objectives = self.objectives(&wrapper.individual);
+            wrapper.fitness = wrapper.objectives.first().cloned().unwrap_or(f64::MAX);
+        } else {
+            wrapper.fitness = self.fitness(&wrapper.individual);
+        }
+    }
+
+    /// Returns false if the fitness of the individual is still valid, see
+    /// `PopulationBuilder::dirty_tracking`.
+    pub fn needs_evaluation<T: Individual>(&self, wrapper: &IndividualWrapper<T>) -> bool {
+        !self.dirty_tracking || wrapper.dirty
+    }
+
+    /// The number of fitness samples of each individual, see `PopulationBuilder::fitness_samples`.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Sets the fitness of the individual from the given samples, which were calculated
+    /// outside of the evaluator, see `Individual::calculate_fitness_async`. An empty slice
+    /// means that the calculation was cancelled, the individual is then the least fit one.
+    /// Only for the scalar fitness, not for lexicographic objectives.
+    #[cfg(feature = "async")]
+    pub fn evaluate_samples<T: Individual>(&mut self, wrapper: &mut IndividualWrapper<T>, samples: &[f64]) {
+        wrapper.dirty = false;
+        wrapper.fitness = if samples.is_empty() {
+            self.fitness_evaluations += self.samples as u64;
+            self.num_of_evaluated += 1;
+            f64::MAX
+        } else {
+            self.combine_samples(samples)
+        };
+    }
+
+    /// Calculates the fitness of the individual without a wrapper. In lexicographic mode
+    /// this is the first objective.
+    pub fn evaluate_individual<T: Individual>(&mut self, individual: &T) -> f64 {
+        if self.num_of_objectives > 0 {
+            self.objectives(individual).first().cloned().unwrap_or(f64::MAX)
+        } else {
+            self.fitness(individual)
+        }
+    }
+
+    /// Calculates the fitness of the individual from all the samples, by default their mean
+    /// (see `Aggregator`).
+    pub fn fitness<T: Individual>(&mut self, individual: &T) -> f64 {
+        // A cancelled fitness calculation makes the individual the least fit one
+        let samples: Vec<f64> = match (0..self.samples)
+            .map(|_| individual.calculate_fitness_cancellable(&self.context, &self.cancel))
+            .collect() {
+            Some(samples) => samples,
+            None => {
+                self.fitness_evaluations += self.samples as u64;
+                self.num_of_evaluated += 1;
+                return f64::MAX;
+            }
+        };
+
+        self.combine_samples(&samples)
+    }
+
+    /// Counts the evaluation and combines the fitness samples of one individual into its
+    /// fitness, see `Aggregator`.
+    fn combine_samples(&mut self, samples: &[f64]) -> f64 {
+        self.fitness_evaluations += samples.len() as u64;
+        self.num_of_evaluated += 1;
+
+        if samples.len() == 1 {
+            return samples[0];
+        }
+
+        let mean = mean(samples);
+
+        self.variance_sum += samples.iter().map(|fitness| (fitness - mean) * (fitness - mean)).sum::<f64>() /
+            ((samples.len() - 1) as f64);
+
+        self.aggregator.aggregate(samples)
+    }
+
+    /// Calculates all objectives of the individual, each one is the mean of all the samples.
+    fn objectives<T: Individual>(&mut self, individual: &T) -> Vec<f64> {
+        self.fitness_evaluations += self.samples as u64;
+        self.num_of_evaluated += 1;
+
+        let mut objectives = individual.calculate_fitness_multi();
+
+        for _ in 1..self.samples {
+            for (sum, objective) in objectives.iter_mut().zip(individual.calculate_fitness_multi()) {
+                *sum += objective;
+            }
+        }
+
+        for objective in &mut objectives {
+            *objective /= self.samples as f64;
+        }
+
+        for (objective, goal) in objectives.iter_mut().zip(self.objective_goals.iter()) {
+            *objective = goal.apply(*objective);
+        }
+
+        objectives
+    }
+
+    /// Seeds the random number generator for the mutation of the individual with the given
+    /// index (in its population) from the mutation seed of the population, the generation and
+    /// the stream (see `RNG_STREAM_MUTATION`), and from the `rng_stream`. So the mutation is the
+    /// same no matter which thread runs it. Does nothing if the population has no mutation seed.
+    pub fn seed_rng(&mut self, index: usize, stream: u64) {
+        if let Some(mutation_seed) = self.mutation_seed {
+            self.rng_seed = Some(derive_seed(mutation_seed, self.rng_stream, self.context.generation, index, stream));
+        }
+    }
+
+    /// Returns a random number generator seeded for the individual with the given index and
+    /// the stream (see `seed_rng`), or an unseeded one if the population has no mutation seed.
+    pub fn seeded_rng(&mut self, index: usize, stream: u64) -> XorShiftRng {
+        self.seed_rng(index, stream);
+
+        match self.rng_seed {
+            Some(seed) => XorShiftRng::from_seed(seed),
+            None => rand::weak_rng()
+        }
+    }
+
+    /// Adds one measurement to the timings of this population, see `SimulationResult::profile`.
+    #[cfg(feature = "profiling")]
+    pub fn add_profile(&mut self, path: &[&str], nanos: u64) {
+        self.profile.add(path, nanos);
+    }
+
+    /// Derives a new seed from the current one, so that another attempt of the same mutation
+    /// (see `PopulationBuilder::feasibility_retries`) gives a different offspring. Does nothing
+    /// if the population has no mutation seed.
+    fn reseed_rng(&mut self) {
+        if let Some(seed) = self.rng_seed {
+            let first = split_mix(((seed[0] as u64) << 32) ^ seed[1] as u64);
+            let second = split_mix(first ^ ((seed[2] as u64) << 32) ^ seed[3] as u64);
+
+            self.rng_seed = Some([first as u32, (first >> 32) as u32, second as u32, ((second >> 32) as u32) | 1]);
+        }
+    }
+
+    /// Adds the counters of another evaluator (of the same iteration) to this one.
+    pub fn merge(&mut self, other: &FitnessEvaluator) {
+        self.fitness_evaluations += other.fitness_evaluations;
+        self.num_of_evaluated += other.num_of_evaluated;
+        self.variance_sum += other.variance_sum;
+        self.mutations += other.mutations;
+        self.successful_mutations += other.successful_mutations;
+        self.selections += other.selections;
+        self.crossovers += other.crossovers;
+        self.repairs += other.repairs;
+
+        #[cfg(feature = "profiling")]
+        self.profile.merge(&[], &other.profile);
+
+        for (index, applications) in other.operator_applications.iter().enumerate() {
+            self.count_operator(index, *applications, other.operator_improvements[index]);
+        }
+    }
+
+    /// Counts one mutated individual, it is successful if its fitness is better than the
+    /// fitness of its parent by more than the improvement epsilon.
+    pub fn count_mutation(&mut self, parent_fitness: f64, fitness: f64) {
+        self.mutations += 1;
+
+        if fitness < parent_fitness - self.improvement_epsilon {
+            self.successful_mutations += 1;
+        }
+    }
+
+    /// Counts the applied operators of one mutated individual, each one counts an
+    /// improvement if the individual is fitter than its parent (like `count_mutation`).
+    pub fn count_operators(&mut self, operators: &[usize], parent_fitness: f64, fitness: f64) {
+        let improved = fitness < parent_fitness - self.improvement_epsilon;
+
+        for operator in operators {
+            self.count_operator(*operator, 1, if improved { 1 } else { 0 });
+        }
+    }
+
+    /// Adds applications and improvements to the counters of the given operator.
+    fn count_operator(&mut self, operator: usize, applications: u64, improvements: u64) {
+        if operator >= self.operator_applications.len() {
+            self.operator_applications.resize(operator + 1, 0);
+            self.operator_improvements.resize(operator + 1, 0);
+        }
+
+        self.operator_applications[operator] += applications;
+        self.operator_improvements[operator] += improvements;
+    }
+
+    /// Returns the mutation scale (see `MutationContext::scale`) of an individual with the
+    /// given fitness: 0.0 for the best and 1.0 for the worst individual of the population.
+    /// Always 1.0 if fitness scaled mutation is disabled or all individuals are equally fit.
+    pub fn mutation_scale(&self, fitness: f64) -> f64 {
+        match self.fitness_range {
+            Some((best, worst)) if worst > best => ((fitness - best) / (worst - best)).clamp(0.0, 1.0),
+            _ => 1.0
+        }
+    }
+
+    /// The number of calls to calculate_fitness so far.
+    pub fn fitness_evaluations(&self) -> u64 {
+        self.fitness_evaluations
+    }
+
+    /// The mean of the sample variances of all evaluated individuals.
+    pub fn mean_variance(&self) -> f64 {
+        if self.num_of_evaluated == 0 {
+            0.0
+        } else {
+            self.variance_sum / (self.num_of_evaluated as f64)
+        }
+    }
+}
 
 /// The `Population` type. Contains the actual individuals (through a wrapper) and information
 /// the reset_limit. Use the `PopulationBuilder` in your main program to create populations.
@@ -41,20 +1034,200 @@ pub struct Population<S, T: Individual> {
     pub reset_counter: u32,
     /// The ID of the population, only used for statistics.
     pub id: u32,
+    /// The fraction of the population that survives each iteration and acts as parents for the
+    /// next one. The remaining slots are filled with mutated copies (offspring) of the survivors.
+    /// A value of 1.0 keeps the whole population (no additional selection pressure).
+    pub survival_rate: f64,
+    /// The fraction of the population (0.0 - 1.0) that is replaced by offspring each
+    /// iteration, the rest are the fittest parents carried over (overlapping generations).
+    /// If not set, parents and offspring compete and the fittest of both survive.
+    pub generation_gap: Option<f64>,
+    /// Optional stop criterion for this population only. If set it overrides the stop
+    /// criterion of the simulation for this population.
+    pub stop_criterion: Option<SimulationType>,
+    /// The recent fitness values of the fittest individual of this population, only used for
+    /// the `EndConvergence` stop criterion of this population.
+    pub fitness_history: VecDeque<f64>,
+    /// Set to true once the stop criterion of this population is reached. A stopped
+    /// population does not change anymore.
+    pub stopped: bool,
+    /// The best fitness this population has reached since the last reset.
+    pub best_fitness: f64,
+    /// The number of iterations since the fittest individual of this population last improved
+    /// (by more than the improvement epsilon), see `SimulationBuilder::prune_stalled`.
+    pub stalled_iterations: u32,
+    /// The fitness of the fittest individual at the last migration, used to measure the
+    /// improvement since then, see `SimulationBuilder::migration_adaptive`.
+    pub migration_fitness: f64,
+    /// The fitness of this population at the beginning of the simulation. Used for the
+    /// improvement factor of the population.
+    pub original_fitness: f64,
+    /// If true, the fitness of the unmutated individuals is re-calculated every iteration
+    /// instead of re-using the fitness of the last iteration (no caching).
+    /// This is needed if the fitness changes over time.
+    pub dynamic_fitness: bool,
+    /// If true, the fitness of the individuals that survived the last iteration is
+    /// re-calculated, see `PopulationBuilder::reevaluate_elites`.
+    pub reevaluate_elites: bool,
+    /// If true, only the fitness of changed individuals is calculated,
+    /// see `PopulationBuilder::dirty_tracking`.
+    pub dirty_tracking: bool,
+    /// If true, duplicates are replaced with new individuals, see `PopulationBuilder::dedup`.
+    pub dedup: bool,
+    /// The seed for reproducible mutations, see `PopulationBuilder::mutation_seed`.
+    pub mutation_seed: Option<u64>,
+    /// Selects another stream of the seeded random number generators, see
+    /// `SimulationBuilder::rng_stream`.
+    pub rng_stream: u64,
+    /// How often an infeasible offspring is mutated again, see
+    /// `PopulationBuilder::feasibility_retries`.
+    pub feasibility_retries: u32,
+    /// If true, the offspring are repaired before the fitness calculation, see
+    /// `PopulationBuilder::enable_repair`.
+    pub repair: bool,
+    /// Every `.0` iterations the least fit fraction `.1` of the population is replaced with
+    /// new individuals, see `PopulationBuilder::diversity_injection`.
+    pub diversity_injection: Option<(u32, f64)>,
+    /// The recovery policy and the number of iterations without any feasible individual
+    /// that trigger it, see `SimulationBuilder::infeasibility_recovery`.
+    pub infeasibility_recovery: Option<(InfeasibilityRecovery, u32)>,
+    /// The number of iterations in a row without any feasible individual.
+    pub infeasible_generations: u32,
+    /// The current factor for the penalty of constraint violations, see
+    /// `InfeasibilityRecovery::RelaxPenalty`.
+    pub penalty_scale: f64,
+    /// The grid of a spatial population, None for a panmictic population (default),
+    /// see `PopulationBuilder::spatial`.
+    pub grid: Option<Grid>,
+    /// If true, each individual records how it was produced, see
+    /// `PopulationBuilder::track_lineage`.
+    pub track_lineage: bool,
+    /// If true, each surviving individual records its fitness in its lineage, see
+    /// `PopulationBuilder::track_individual_history`.
+    pub track_individual_history: bool,
+    /// How often the fitness of each individual is calculated, the mean of all these samples
+    /// is used as fitness. This is useful for noisy (stochastic) fitness functions.
+    pub fitness_samples: u32,
+    /// If the variance of the fitness of all individuals drops below this threshold, all but
+    /// the fittest individual are hyper-mutated, see `SimulationBuilder::plateau_threshold`.
+    pub plateau_threshold: Option<f64>,
+    /// The minimal fitness improvement for a mutation to count as successful,
+    /// see `SimulationBuilder::improvement_epsilon`.
+    pub improvement_epsilon: f64,
+    /// The cancel flag of the simulation, see `Simulation::cancel_flag`.
+    pub cancel: Arc<AtomicBool>,
+    /// How the fitness samples are combined, see `PopulationBuilder::sample_aggregator`.
+    pub sample_aggregator: Aggregator,
+    /// How the parents of the offspring are chosen, see `PopulationBuilder::selection_strategy`.
+    pub selection_strategy: SelectionStrategy,
+    /// If true the parents don't compete with their offspring (comma selection), only with
+    /// `Pipeline::SelectMutateEvaluate`, see `SimulationBuilder::es_scheme`.
+    pub comma_selection: bool,
+    /// If true the selection pressure follows the diversity of the population,
+    /// see `SimulationBuilder::auto_tune_selection`.
+    pub auto_tune_selection: bool,
+    /// The current factor (0.0 - 1.0) of the selection strategy and survival rate, 1.0 means
+    /// as configured. Only changed if `auto_tune_selection` is set.
+    pub selection_pressure: f64,
+    /// How ties in a tournament are broken, see `PopulationBuilder::tournament_tie_break`.
+    pub tournament_tie_break: TieBreak,
+    /// The core of the thread that mutates and evaluates the individuals, see
+    /// `PopulationBuilder::cpu_affinity`.
+    pub cpu_affinity: Option<usize>,
+    /// Optional transformation of the fitness that is only used for the selection,
+    /// see `PopulationBuilder::fitness_scaling`.
+    pub fitness_scaling: Option<FitnessScaling>,
+    /// Optional rule for an offspring to replace its parent,
+    /// see `PopulationBuilder::replacement_policy`.
+    pub replacement_policy: Option<ReplacementPolicy<T>>,
+    /// If true, the mutator gets the relative fitness of each individual, see
+    /// `PopulationBuilder::fitness_scaled_mutation`.
+    pub fitness_scaled_mutation: bool,
+    /// The number of objectives that are compared lexicographically, see
+    /// `SimulationBuilder::lexicographic_objectives`. 0 means scalar fitness.
+    pub num_of_objectives: usize,
+    /// The goal (minimize or maximize) of each objective, see
+    /// `SimulationBuilder::objective_goals`. Empty means all objectives are minimized.
+    pub objective_goals: Arc<Vec<FitnessGoal>>,
+    /// Statistics about the last iteration.
+    pub stats: PopulationStats,
+    /// The individuals that died in the last iteration. Their buffers are re-used in the next
+    /// iteration instead of allocating new individuals.
+    pub spare: Vec<IndividualWrapper<T>>,
+    /// The order of selection, mutation and evaluation, see `SimulationBuilder::pipeline`.
+    pub pipeline: Pipeline,
+    /// The index of the first individual that is mutated in the current iteration. Always 0
+    /// for `Pipeline::MutateEvaluateSelect`, the number of parents for
+    /// `Pipeline::SelectMutateEvaluate`.
+    pub mutation_start: usize,
+    /// Optional population size per generation, see `PopulationBuilder::size_schedule`.
+    /// If not set the size is constant.
+    pub size_schedule: Option<Arc<dyn Fn(u32) -> usize + Send + Sync>>,
+    /// The indices of the components of the individuals that must not change during
+    /// mutation, see `PopulationBuilder::frozen_components`.
+    pub frozen_components: Arc<Vec<usize>>,
+    /// Optional function that creates the individuals of this population instead of
+    /// `Individual::new`, see `PopulationBuilder::initializer`.
+    pub initializer: Option<Initializer<T>>,
+    /// Optional mutation strategy, see `PopulationBuilder::mutator`. If not set
+    /// `Individual::mutate` is used.
+    pub mutator: Option<Arc<dyn Mutator<T>>>,
+    /// Optional crossover operator, see `PopulationBuilder::crossover`.
+    pub crossover: Option<Arc<dyn Crossover<T>>>,
+    /// The probability that an offspring is created by crossover.
+    pub crossover_rate: f64,
+    /// How many children of each crossover are kept (1 or 2), see
+    /// `PopulationBuilder::offspring_per_crossover`.
+    pub offspring_per_crossover: usize,
     /// The data source for each individual.
     pub data_source: Option<S>
 }
 
 impl<S, T: Individual + Send + Sync + Clone> Population<S, T> {
-    /// Just calculates the fitness for each individual.
-    pub fn calculate_fitness(&mut self) {
+    /// Just calculates the fitness for each individual and sorts the population, so that the
+    /// fittest individual is at index 0. Returns the number of fitness evaluations.
+    pub fn calculate_fitness(&mut self) -> u64 {
+        let mut evaluator = self.new_evaluator(0);
+
         for wrapper in &mut self.population {
-            wrapper.fitness = wrapper.individual.calculate_fitness();
+            evaluator.evaluate(wrapper);
+        }
+
+        self.sort_keep_mutation_rates();
+        self.stats.sample_variance = evaluator.mean_variance();
+        evaluator.fitness_evaluations
+    }
+
+    /// Like `calculate_fitness`, but awaits `Individual::calculate_fitness_async` of at most
+    /// `max_concurrent` individuals at the same time, see `SimulationBuilder::max_concurrent_evals`.
+    /// Like `calculate_fitness` this panics if a fitness calculation panics, once all the
+    /// others are finished.
+    #[cfg(feature = "async")]
+    pub fn calculate_fitness_async(&mut self, max_concurrent: usize, cancel: &AtomicBool) -> u64 {
+        let mut evaluator = self.new_evaluator(0);
+        let mut evaluators: Vec<FitnessEvaluator> = self.population.iter().map(|_| evaluator.clone()).collect();
+
+        let panicked = {
+            let mut jobs: Vec<_> = self.population.iter_mut().zip(evaluators.iter_mut()).collect();
+            evaluate_async(&mut jobs, max_concurrent, cancel)
+        };
+
+        if !panicked.is_empty() {
+            panic!("The fitness calculation of {} individuals panicked, id: {}", panicked.len(), self.id);
+        }
+
+        for other in &evaluators {
+            evaluator.merge(other);
         }
+
+        self.sort_keep_mutation_rates();
+        self.stats.sample_variance = evaluator.mean_variance();
+        evaluator.fitness_evaluations
     }
 
     /// This is the body that gets called for every iteration.
-    /// This function does the following:
+    /// This function does the following (for the default `Pipeline::MutateEvaluateSelect`,
+    /// which all the examples use):
     /// 1. Check if the reset limit is reached. If it is, this whole population is
     /// discarded and re-initialized from the start. All the information about the
     /// current fittest individual is lost. This is done to avoid local minima.
@@ -64,82 +1237,967 @@ impl<S, T: Individual + Send + Sync + Clone> Population<S, T> {
     /// population twice the size.
     /// 5. Sort this new big population by fitness. So the fittest individual is at position 0.
     /// 6. Truncated the big population to its original size and thus gets rid of all the less fittest
-    /// individuals (they "die").
-    /// 7. Check if the fittest individual (at index 0) in the current sorted population is better
+    /// individuals (they "die"). If a generation gap is set, steps 4 - 6 take the fittest
+    /// offspring and the fittest parents separately instead (see `generation_gap`).
+    /// 7. If the survival rate is below 1.0, only the fittest fraction of the population is
+    /// kept and the rest is replaced by mutated copies of these survivors.
+    /// 8. Check if the fittest individual (at index 0) in the current sorted population is better
     /// (= fitter) than the global
     /// fittest individual of the whole simulation. If yes, the global fittest individual is
     /// replaced.
-    /// 8. Calculate the new improvement factor and prepare for the next iteration.
-    pub fn run_body(&mut self, simulation_result: &Mutex<SimulationResult<T>>,
+    /// 9. Calculate the new improvement factor and prepare for the next iteration.
+    ///
+    /// The simulation itself does not call this function, instead it calls the three phases
+    /// With `Pipeline::SelectMutateEvaluate` the parents are selected first instead (see
+    /// `survival_rate`) and only their copies are mutated and evaluated, there is no merge.
+    ///
+    /// `prepare_iteration` (steps 1 - 2), `mutate_and_evaluate_feasible` (step 3) for all
+    /// individuals of all populations at once and `finish_iteration` (steps 4 - 9, split into
+    /// `finish_population` in parallel and `update_result` in the order of the population ids).
+    /// That way the work of large and small populations is spread evenly over all threads.
+    pub fn run_body(&mut self, simulation_result: &Mutex<&mut SimulationResult<T>>,
             iteration_counter: u32) {
-        // First check if reset limit is reached
-        if self.reset_limit_end > 0 {
-            self.reset_counter += 1;
+        let mut evaluator = self.prepare_iteration(iteration_counter);
 
-            if self.reset_counter > self.reset_limit {
-                self.reset_limit += self.reset_limit_increment;
-                if self.reset_limit >= self.reset_limit_end {
+        let mutator = self.mutator.as_deref();
+
+        for (index, wrapper) in self.population.iter_mut().enumerate().skip(self.mutation_start) {
+            evaluator.seed_rng(index, RNG_STREAM_MUTATION);
+            mutate_and_evaluate_feasible(wrapper, mutator, &mut evaluator);
+        }
+
+        self.finish_iteration(evaluator, simulation_result, iteration_counter);
+    }
+
+    /// First phase of an iteration: checks the reset limit and keeps a copy of the current
+    /// (unmutated) population. Returns the fitness evaluator for this iteration.
+    pub fn prepare_iteration(&mut self, iteration_counter: u32) -> FitnessEvaluator {
+        // Counts the calls to calculate_fitness in this iteration
+        let mut evaluator = self.new_evaluator(iteration_counter);
+
+        self.stats.reset = false;
+        self.stats.infeasibility_recovery = None;
+
+        if let (Some(size_schedule), None) = (self.size_schedule.clone(), self.grid.as_ref()) {
+            self.resize(size_schedule(iteration_counter).max(1), &mut evaluator);
+        }
+
+        // First check if reset limit is reached
+        if self.reset_limit_end > 0 {
+            self.reset_counter += 1;
+
+            if self.reset_counter > self.reset_limit {
+                self.reset_limit += self.reset_limit_increment;
+                if self.reset_limit >= self.reset_limit_end {
                     self.reset_limit = self.reset_limit_start;
                     println!("reset_limit reset to reset_limit_start: {}, id: {}", self.reset_limit_start, self.id);
                 }
                 self.reset_counter = 0;
+                self.stats.reset = true;
+                self.best_fitness = f64::MAX;
+                self.stalled_iterations = 0;
                 println!("new reset_limit: {}, id: {}", self.reset_limit, self.id);
 
                 // Kill all individuals since we are most likely stuck in a local minimum.
                 // Why is it so ? Because the simulation is still running!
                 // Keep number of mutations.
                 if let Some(ref data_source) = self.data_source {
-                    for wrapper in &mut self.population {
-                        wrapper.individual = Individual::new(data_source);
-                        wrapper.fitness = wrapper.individual.calculate_fitness();
+                    for (index, wrapper) in self.population.iter_mut().enumerate() {
+                        wrapper.individual = new_individual(data_source, &self.initializer, &mut evaluator, index);
+                        wrapper.dirty = true;
+                        wrapper.lineage = None;
+
+                        if self.track_lineage {
+                            wrapper.record_lineage(Origin::Reset, iteration_counter);
+                        }
+
+                        evaluator.evaluate(wrapper);
                     }
                 }
             }
         }
 
-        // Keep original population
-        let orig_population = self.population.clone();
+        if let Some((policy, generations)) = self.infeasibility_recovery {
+            if self.infeasible_generations >= generations {
+                self.recover_feasibility(policy, iteration_counter, &mut evaluator);
+            }
+        }
 
-        // Mutate population
-        for wrapper in &mut self.population {
-            for _ in 0..wrapper.num_of_mutations {
-                wrapper.individual.mutate();
+        // Replace the least fit individuals with new ones (soft restart)
+        if let Some((every, fraction)) = self.diversity_injection {
+            if iteration_counter > 0 && iteration_counter % every == 0 {
+                self.inject_diversity(fraction, &mut evaluator);
             }
-            wrapper.fitness = wrapper.individual.calculate_fitness();
         }
 
-        // Append original (unmutated) population to new (mutated) population
-        self.population.extend(orig_population.iter().cloned());
+        // The fitness may have changed since the last iteration, or the survivors may just
+        // have been lucky with a noisy fitness
+        if self.dynamic_fitness || self.reevaluate_elites {
+            for wrapper in &mut self.population {
+                wrapper.dirty = true;
+                evaluator.evaluate(wrapper);
+            }
+        }
 
-        // Sort by fitness
-        self.population.sort();
+        if self.pipeline == Pipeline::SelectMutateEvaluate && self.grid.is_none() {
+            self.select_parents(iteration_counter);
+            self.recombine(&mut evaluator);
+            return evaluator;
+        }
 
-        // Reduce population to original length
-        self.population.truncate(self.num_of_individuals as usize);
+        self.mutation_start = 0;
 
-        // Restore original number of mutation rate, since these will be lost because of sorting
-        for (individual, orig_individual) in self.population
-            .iter_mut()
-            .zip(orig_population.iter()) {
-            individual.num_of_mutations = orig_individual.num_of_mutations;
+        // Keep original population in the spare buffer. Instead of cloning, re-use the buffers
+        // of the individuals that died in the last iteration (see `Individual::reuse_from`).
+        self.spare.truncate(self.population.len());
+
+        for (index, wrapper) in self.population.iter().enumerate() {
+            if index < self.spare.len() {
+                let orig_wrapper = &mut self.spare[index];
+                orig_wrapper.individual.reuse_from(&wrapper.individual);
+                orig_wrapper.fitness = wrapper.fitness;
+                orig_wrapper.num_of_mutations = wrapper.num_of_mutations;
+                orig_wrapper.id = wrapper.id;
+                orig_wrapper.objectives.clone_from(&wrapper.objectives);
+                orig_wrapper.dirty = wrapper.dirty;
+                orig_wrapper.lineage = wrapper.lineage.clone();
+                orig_wrapper.cell = wrapper.cell;
+            } else {
+                self.spare.push(wrapper.clone());
+            }
         }
 
-        match simulation_result.lock() {
-            Ok(mut simulation_result) => {
-                // Check if we have new fittest individual and store it globally
-                if self.population[0].fitness < simulation_result.fittest[0].fitness {
-                    // Insert it to the first position (at index 0) so that the order of fitness
-                    // is preserved (fittest at index 0, then decreasing fitness).
-                    simulation_result.fittest.insert(0, self.population[0].clone());
-                    println!("{}: new fittest: {}, id: {}",
-                             iteration_counter, simulation_result.fittest[0].fitness, self.id);
-                }
+        match self.grid.clone() {
+            Some(grid) => self.select_neighbors(&grid),
+            None => self.recombine(&mut evaluator)
+        }
 
-                simulation_result.improvement_factor = simulation_result.fittest[0].fitness / simulation_result.original_fitness;
-            },
+        evaluator
+    }
+
+    /// Last phase of an iteration: merges the mutated and the original population, selects
+    /// the fittest individuals and updates the simulation result.
+    /// The evaluator must contain all fitness evaluations of this iteration.
+    pub fn finish_iteration(&mut self, evaluator: FitnessEvaluator,
+            simulation_result: &Mutex<&mut SimulationResult<T>>, iteration_counter: u32) {
+        let evaluator = self.finish_population(evaluator);
+
+        match simulation_result.lock() {
+            Ok(mut simulation_result) => self.update_result(&evaluator, &mut simulation_result, iteration_counter),
             Err(e) => println!("Mutex (poison) error (simulation_result): {}, id: {}", e, self.id)
         }
         // No need to unlock simulation_result, since it goes out of scope and then
         // drop() (= destructor) is called.
     }
+
+    /// The part of `finish_iteration` that only changes this population: merges the mutated
+    /// and the original population and selects the fittest individuals. Returns the
+    /// evaluator with all the counters of this iteration for `update_result`.
+    pub fn finish_population(&mut self, mut evaluator: FitnessEvaluator) -> FitnessEvaluator {
+        let num_of_mutations: Vec<u32> = self.population.iter().map(|wrapper| wrapper.num_of_mutations).collect();
+
+        {
+            let fitness: Vec<f64> = match self.pipeline {
+                Pipeline::SelectMutateEvaluate if self.grid.is_none() => {
+                    self.population.iter().map(|wrapper| wrapper.fitness).collect()
+                }
+                _ => self.population.iter().chain(self.spare.iter()).map(|wrapper| wrapper.fitness).collect()
+            };
+            self.stats.fitness_mean = mean(&fitness);
+            self.stats.fitness_std_dev = standard_deviation(&fitness);
+        }
+
+        match (self.pipeline, self.generation_gap) {
+            // Each offspring competes with the original individual of its cell only
+            _ if self.grid.is_some() => self.replace_parents(),
+            // Each offspring competes with its own parent only
+            (Pipeline::MutateEvaluateSelect, _) if self.replacement_policy.is_some() => self.replace_parents(),
+            // Parents and offspring are already in the population (see `select_parents`)
+            // Comma selection: the parents die, the offspring are moved to the front
+            (Pipeline::SelectMutateEvaluate, _) if self.comma_selection => {
+                let num_of_offspring = self.population.len() - self.mutation_start;
+                self.population.rotate_left(self.mutation_start);
+                self.population[..num_of_offspring].sort()
+            }
+            (Pipeline::SelectMutateEvaluate, _) => self.population.sort(),
+            (_, Some(generation_gap)) => self.replace_generation(generation_gap),
+            (_, None) => {
+                // Append original (unmutated) population to new (mutated) population
+                self.population.append(&mut self.spare);
+
+                // Sort by fitness
+                sort_for_selection(&mut self.population, &self.fitness_scaling, &self.stats);
+
+                // Reduce population to original length, keep the dead individuals for the next iteration
+                self.spare = self.population.split_off(self.num_of_individuals as usize);
+                if self.fitness_scaling.is_some() {
+                    self.population.sort();
+                }
+            }
+        }
+
+        evaluator.selections += self.population.len() as u64;
+
+        self.stats.diversity = fitness_diversity(&self.population);
+        if self.auto_tune_selection {
+            self.tune_selection_pressure();
+        }
+
+        if self.track_individual_history && self.track_lineage {
+            for wrapper in &mut self.population {
+                wrapper.record_fitness(evaluator.context.generation);
+            }
+        }
+
+        // Replace the less fit individuals with offspring of the survivors
+        if self.pipeline == Pipeline::MutateEvaluateSelect && self.survival_rate < 1.0 && self.grid.is_none() &&
+            self.replacement_policy.is_none() {
+            self.regenerate(&mut evaluator);
+        }
+
+        // Restore original number of mutation rate, since these will be lost because of sorting
+        for (individual, num_of_mutations) in self.population
+            .iter_mut()
+            .zip(num_of_mutations) {
+            individual.num_of_mutations = num_of_mutations;
+        }
+
+        self.stats.duplicates_replaced = if self.dedup {
+            self.replace_duplicates(&mut evaluator)
+        } else {
+            0
+        };
+
+        self.stats.plateau_kick = match self.plateau_threshold {
+            Some(plateau_threshold) => self.plateau_kick(plateau_threshold, &mut evaluator),
+            None => false
+        };
+
+        self.stats.sample_variance = evaluator.mean_variance();
+        self.stats.repairs = evaluator.repairs;
+
+        if let Some(ref mutator) = self.mutator {
+            mutator.update_operators(&evaluator.operator_applications, &evaluator.operator_improvements);
+        }
+
+        if self.population[0].fitness < self.best_fitness - self.improvement_epsilon {
+            self.best_fitness = self.population[0].fitness;
+            self.stalled_iterations = 0;
+        } else {
+            self.stalled_iterations += 1;
+        }
+
+        if self.infeasibility_recovery.is_some() {
+            if self.population.iter().all(|wrapper| wrapper.individual.constraint_violation() > 0.0) {
+                self.infeasible_generations += 1;
+            } else {
+                self.infeasible_generations = 0;
+            }
+        }
+
+        evaluator
+    }
+
+    /// Applies the given recovery policy because no individual has been feasible for too
+    /// long and evaluates all individuals again, see `SimulationBuilder::infeasibility_recovery`.
+    fn recover_feasibility(&mut self, policy: InfeasibilityRecovery, iteration_counter: u32,
+            evaluator: &mut FitnessEvaluator) {
+        match policy {
+            InfeasibilityRecovery::Reseed => {
+                if let Some(ref data_source) = self.data_source {
+                    for (index, wrapper) in self.population.iter_mut().enumerate() {
+                        wrapper.individual = new_individual(data_source, &self.initializer, evaluator, index);
+                        wrapper.lineage = None;
+
+                        if self.track_lineage {
+                            wrapper.record_lineage(Origin::Reset, iteration_counter);
+                        }
+                    }
+                }
+            }
+            InfeasibilityRecovery::RelaxPenalty => {
+                self.penalty_scale *= 0.5;
+                evaluator.context.penalty_scale = self.penalty_scale;
+            }
+            InfeasibilityRecovery::Repair => {
+                for wrapper in &mut self.population {
+                    if wrapper.individual.repair() {
+                        evaluator.repairs += 1;
+                    }
+                }
+            }
+        }
+
+        for wrapper in &mut self.population {
+            wrapper.dirty = true;
+            evaluator.evaluate(wrapper);
+        }
+
+        self.sort_keep_mutation_rates();
+        self.best_fitness = f64::MAX;
+        self.stalled_iterations = 0;
+        self.infeasible_generations = 0;
+        self.stats.infeasibility_recovery = Some(policy);
+    }
+
+    /// The part of `finish_iteration` that changes the simulation result: adds the counters
+    /// of the evaluator and stores the fittest individual if it is fitter than the global one.
+    pub fn update_result(&self, evaluator: &FitnessEvaluator, simulation_result: &mut SimulationResult<T>,
+            iteration_counter: u32) {
+        simulation_result.fitness_evaluations += evaluator.fitness_evaluations;
+        simulation_result.total_mutations += evaluator.mutations;
+        simulation_result.successful_mutations += evaluator.successful_mutations;
+        simulation_result.total_selections += evaluator.selections;
+        simulation_result.total_crossovers += evaluator.crossovers;
+        simulation_result.total_repairs += evaluator.repairs;
+
+        if let Some(policy) = self.stats.infeasibility_recovery {
+            simulation_result.infeasibility_recoveries.push((iteration_counter, self.id, policy));
+        }
+
+        if self.auto_tune_selection {
+            match simulation_result.selection_pressure_history.iter().position(|&(id, _)| id == self.id) {
+                Some(index) => simulation_result.selection_pressure_history[index].1.push(self.selection_pressure),
+                None => simulation_result.selection_pressure_history.push((self.id, vec![self.selection_pressure])),
+            }
+        }
+
+        #[cfg(feature = "profiling")]
+        {
+            let names = self.mutator.as_ref().map_or(Vec::new(), |mutator| mutator.operator_names());
+
+            for (path, timing) in &evaluator.profile.timings {
+                let mut full_path = vec![format!("population {}", self.id)];
+
+                for name in path {
+                    let operator = name.parse::<usize>().ok().and_then(|index| names.get(index));
+                    full_path.push(operator.cloned().unwrap_or_else(|| name.clone()));
+                }
+
+                simulation_result.profile.add_timing(full_path, *timing);
+            }
+        }
+
+        if let Some(ref mutator) = self.mutator {
+            let names = mutator.operator_names();
+
+            for (index, applications) in evaluator.operator_applications.iter().enumerate() {
+                let name = match names.get(index) {
+                    Some(name) => name.clone(),
+                    None => index.to_string()
+                };
+                let position = match simulation_result.operator_stats.iter().position(|stat| stat.name == name) {
+                    Some(position) => position,
+                    None => {
+                        simulation_result.operator_stats.push(OperatorStat {
+                            name, applications: 0, improvements: 0 });
+                        simulation_result.operator_stats.len() - 1
+                    }
+                };
+
+                simulation_result.operator_stats[position].applications += *applications;
+                simulation_result.operator_stats[position].improvements += evaluator.operator_improvements[index];
+            }
+        }
+
+        // Check if we have new fittest individual and store it globally. With fitness
+        // scaling it may not be at index 0.
+        let fittest = self.population.iter().min().expect("Population is empty");
+
+        if *fittest < simulation_result.fittest[0] {
+            // Insert it to the first position (at index 0) so that the order of fitness
+            // is preserved (fittest at index 0, then decreasing fitness).
+            simulation_result.fittest.insert(0, fittest.clone());
+            println!("{}: new fittest: {}, id: {}",
+                     iteration_counter, simulation_result.fittest[0].fitness, self.id);
+        }
+
+        // The validation fitness is only calculated for the fittest individual of each
+        // population, so it can be expensive
+        if let Some(validation_fitness) = fittest.individual.validation_fitness() {
+            let better = match simulation_result.best_validation {
+                Some((best, _)) => validation_fitness < best,
+                None => true,
+            };
+
+            if better {
+                simulation_result.best_validation = Some((validation_fitness, fittest.clone()));
+            }
+        }
+
+        simulation_result.improvement_factor = simulation_result.fittest[0].fitness / simulation_result.original_fitness;
+    }
+
+    /// Returns the least fit individual of this population, or None if it is empty.
+    pub fn worst(&self) -> Option<&T> {
+        self.worst_wrapper().map(|wrapper| &wrapper.individual)
+    }
+
+    /// Returns the least fit individual with its fitness. The population is sorted, but with
+    /// fitness scaling it may not be the last one.
+    pub fn worst_wrapper(&self) -> Option<&IndividualWrapper<T>> {
+        self.population.iter().max()
+    }
+
+    /// Replaces the least fit individual with the given one and keeps the population sorted.
+    pub fn inject(&mut self, individual: T, generation: u32) {
+        let mut evaluator = self.new_evaluator(generation);
+
+        if let Some(wrapper) = self.population.last_mut() {
+            wrapper.individual = individual;
+            wrapper.dirty = true;
+            wrapper.lineage = None;
+
+            if self.track_lineage {
+                wrapper.record_lineage(Origin::Injection, generation);
+            }
+
+            evaluator.evaluate(wrapper);
+        }
+
+        self.sort_keep_mutation_rates();
+    }
+
+    /// Replaces the least fit individual with the given migrant from another population and
+    /// keeps the population sorted. With dirty tracking the migrant keeps its fitness,
+    /// otherwise it is calculated again.
+    pub fn immigrate(&mut self, migrant: IndividualWrapper<T>, generation: u32) {
+        let mut evaluator = self.new_evaluator(generation);
+        let id = self.id;
+
+        if let Some(wrapper) = self.population.last_mut() {
+            wrapper.individual = migrant.individual;
+            wrapper.fitness = migrant.fitness;
+            wrapper.objectives = migrant.objectives;
+            wrapper.dirty = migrant.dirty;
+            wrapper.lineage = migrant.lineage;
+            wrapper.id = id;
+
+            if self.track_lineage {
+                wrapper.record_lineage(Origin::Migration, generation);
+            }
+
+            evaluator.evaluate(wrapper);
+        }
+
+        self.sort_keep_mutation_rates();
+    }
+
+    /// Merges the individuals of the other population into this one, for example to pool the
+    /// final populations of several runs. The merged population keeps the settings and the
+    /// id of this population, the individuals of the other population get this id.
+    /// The individuals are sorted by fitness and the population is ready for a new simulation.
+    pub fn merge(mut self, other: Population<S, T>) -> Population<S, T> {
+        let id = self.id;
+
+        for mut wrapper in other.population {
+            wrapper.id = id;
+            self.population.push(wrapper);
+        }
+
+        self.sort_keep_mutation_rates();
+        self.num_of_individuals = self.population.len() as u32;
+        self.reset_counter = 0;
+        self.fitness_history.clear();
+        self.stopped = false;
+        self.best_fitness = f64::MAX;
+        self.stalled_iterations = 0;
+        self.spare.clear();
+        self
+    }
+
+    /// Lowers the selection pressure if the diversity of the survivors collapsed and raises it
+    /// again (up to the configured pressure) once it has recovered, see
+    /// `SimulationBuilder::auto_tune_selection`.
+    fn tune_selection_pressure(&mut self) {
+        if self.stats.diversity < DIVERSITY_LOW {
+            self.selection_pressure = (self.selection_pressure * 0.8).max(MIN_SELECTION_PRESSURE);
+        } else if self.stats.diversity > DIVERSITY_HIGH {
+            self.selection_pressure = (self.selection_pressure * 1.25).min(1.0);
+        }
+    }
+
+    /// The selection strategy, scaled by the current selection pressure.
+    fn effective_selection_strategy(&self) -> SelectionStrategy {
+        if self.selection_pressure < 1.0 {
+            self.selection_strategy.scaled(self.selection_pressure)
+        } else {
+            self.selection_strategy
+        }
+    }
+
+    /// The survival rate, raised towards 1.0 (no selection) by a lower selection pressure.
+    fn effective_survival_rate(&self) -> f64 {
+        self.survival_rate + (1.0 - self.survival_rate) * (1.0 - self.selection_pressure)
+    }
+
+    /// Selects the fittest individuals given by the survival rate as parents (at least one,
+    /// and at least one slot is left for offspring) and overwrites the remaining individuals
+    /// with copies of the parents. Only these copies are mutated and evaluated in this
+    /// iteration, see `Pipeline::SelectMutateEvaluate`.
+    fn select_parents(&mut self, generation: u32) {
+        let num_of_individuals = self.population.len();
+
+        // With comma selection the parents of the last iteration (at the end, see
+        // `finish_population`) are not candidates, unless the population was just reset
+        let num_of_candidates = if self.comma_selection && !self.stats.reset {
+            num_of_individuals - self.mutation_start
+        } else {
+            num_of_individuals
+        };
+        self.sort_prefix_keep_mutation_rates(num_of_candidates);
+
+        let num_of_parents = ((num_of_individuals as f64) * self.effective_survival_rate()).ceil() as usize;
+        let num_of_parents = num_of_parents.min(num_of_individuals.saturating_sub(1)).max(1);
+
+        let selection_strategy = self.effective_selection_strategy();
+        let mut rng = self.seeded_rng(generation, RNG_STREAM_SELECTION);
+        let (parents, offspring) = self.population.split_at_mut(num_of_parents);
+
+        for (index, wrapper) in offspring.iter_mut().enumerate() {
+            let parent = &parents[selection_strategy.select(index, parents, self.tournament_tie_break,
+                generation, &mut rng)];
+            wrapper.individual.reuse_from(&parent.individual);
+            wrapper.fitness = parent.fitness;
+            wrapper.objectives.clone_from(&parent.objectives);
+            wrapper.dirty = parent.dirty;
+        }
+
+        self.mutation_start = num_of_parents.min(num_of_individuals);
+    }
+
+    /// Replaces each offspring with the probability given by the crossover rate with a child
+    /// of two parents chosen by the selection strategy, see `PopulationBuilder::crossover`.
+    /// With `offspring_per_crossover(2)` the second child replaces the next offspring. The
+    /// children are mutated afterwards like all the other offspring.
+    fn recombine(&mut self, evaluator: &mut FitnessEvaluator) {
+        let crossover = match self.crossover {
+            Some(ref crossover) => crossover.clone(),
+            None => return
+        };
+
+        let generation = evaluator.context.generation;
+        let selection_strategy = self.effective_selection_strategy();
+        let mut rng = self.seeded_rng(generation, RNG_STREAM_CROSSOVER);
+
+        // The parents are the originals in the spare buffer, or the selected parents in front
+        // of the offspring with `Pipeline::SelectMutateEvaluate`
+        let (parents, offspring) = if self.pipeline == Pipeline::SelectMutateEvaluate {
+            let (parents, offspring) = self.population.split_at_mut(self.mutation_start);
+            (&*parents, offspring)
+        } else {
+            (&self.spare[..], &mut self.population[..])
+        };
+
+        let mut index = 0;
+
+        while index < offspring.len() {
+            if rng.gen_range(0.0, 1.0) >= self.crossover_rate {
+                index += 1;
+                continue;
+            }
+
+            let first = &parents[selection_strategy.select(index, parents, self.tournament_tie_break,
+                generation, &mut rng)];
+            let second = &parents[selection_strategy.select(index + 1, parents, self.tournament_tie_break,
+                generation, &mut rng)];
+            let (child, second_child) = crossover.crossover(&first.individual, &second.individual, &mut rng);
+
+            let mut children = vec![(child, first, second)];
+            if let (2, Some(second_child)) = (self.offspring_per_crossover, second_child) {
+                children.push((second_child, second, first));
+            }
+
+            for (child, parent, other_parent) in children {
+                if index == offspring.len() {
+                    break;
+                }
+
+                let wrapper = &mut offspring[index];
+                wrapper.individual = child;
+                wrapper.fitness = parent.fitness;
+                wrapper.objectives.clone_from(&parent.objectives);
+                wrapper.dirty = true;
+                wrapper.lineage = parent.lineage.clone();
+
+                if self.track_lineage {
+                    wrapper.record_lineage_with_parent(Origin::Crossover, generation, other_parent.lineage_id());
+                }
+
+                index += 1;
+            }
+
+            evaluator.crossovers += 1;
+        }
+    }
+
+    /// Overwrites each individual with a copy of the fittest individual in the neighborhood
+    /// of its cell (including the cell itself), these copies are mutated in this iteration.
+    /// The original individuals must already be in the spare buffer.
+    fn select_neighbors(&mut self, grid: &Grid) {
+        let mut by_cell = vec![0; self.spare.len()];
+        for (index, wrapper) in self.spare.iter().enumerate() {
+            by_cell[wrapper.cell] = index;
+        }
+
+        for wrapper in &mut self.population {
+            let spare = &self.spare;
+            let parent = grid.neighbors(wrapper.cell).into_iter()
+                .chain(iter::once(wrapper.cell))
+                .map(|cell| &spare[by_cell[cell]])
+                .min()
+                .expect("Cell without individual");
+
+            wrapper.individual.reuse_from(&parent.individual);
+            wrapper.fitness = parent.fitness;
+            wrapper.objectives.clone_from(&parent.objectives);
+            wrapper.dirty = parent.dirty;
+            wrapper.lineage = parent.lineage.clone();
+        }
+
+        self.mutation_start = 0;
+    }
+
+    /// Each offspring competes with the original individual at its position (its parent, or
+    /// the individual of its cell): the offspring replaces it if the replacement policy says
+    /// so, by default if it is at least as fit. The other one is kept in the spare buffer.
+    /// Sorts the population afterwards.
+    fn replace_parents(&mut self) {
+        for (offspring, original) in self.population.iter_mut().zip(self.spare.iter_mut()) {
+            let replace = match self.replacement_policy {
+                Some(ref replacement_policy) => replacement_policy(offspring, original),
+                None => *offspring <= *original
+            };
+
+            if !replace {
+                mem::swap(offspring, original);
+            }
+        }
+
+        self.population.sort();
+    }
+
+    /// Builds the next generation from the fittest offspring (the mutated population) and
+    /// the fittest parents (in the spare buffer): the generation gap gives the fraction of
+    /// offspring. The individuals that are not taken are kept in the spare buffer.
+    fn replace_generation(&mut self, generation_gap: f64) {
+        let num_of_individuals = self.num_of_individuals as usize;
+        let num_of_offspring = ((num_of_individuals as f64) * generation_gap).round() as usize;
+        let num_of_parents = num_of_individuals - num_of_offspring.min(num_of_individuals);
+
+        let mut offspring = mem::take(&mut self.population);
+        sort_for_selection(&mut offspring, &self.fitness_scaling, &self.stats);
+        sort_for_selection(&mut self.spare, &self.fitness_scaling, &self.stats);
+
+        let mut dead = offspring.split_off(num_of_offspring.min(offspring.len()));
+        dead.extend(self.spare.split_off(num_of_parents.min(self.spare.len())));
+
+        self.population = offspring;
+        self.population.append(&mut self.spare);
+        self.population.sort();
+        self.spare = dead;
+    }
+
+    /// Checks if the population is on a fitness plateau (fitness variance below the threshold)
+    /// and if so mutates all individuals except the fittest one `HYPER_MUTATION_FACTOR` times as
+    /// often as usual. Returns true if that happened. The population must already be sorted.
+    fn plateau_kick(&mut self, plateau_threshold: f64, evaluator: &mut FitnessEvaluator) -> bool {
+        let fitness: Vec<f64> = self.population.iter().map(|wrapper| wrapper.fitness).collect();
+        let variance = standard_deviation(&fitness).powi(2);
+
+        if variance >= plateau_threshold {
+            return false;
+        }
+
+        let mutator = self.mutator.as_deref();
+
+        for (index, wrapper) in self.population.iter_mut().enumerate().skip(1) {
+            let parent_fitness = wrapper.fitness;
+
+            evaluator.seed_rng(index, RNG_STREAM_HYPER_MUTATION);
+            mutate(&mut wrapper.individual, parent_fitness, wrapper.num_of_mutations * HYPER_MUTATION_FACTOR,
+                mutator, evaluator);
+            wrapper.dirty |= wrapper.num_of_mutations > 0;
+
+            if self.track_lineage {
+                wrapper.record_lineage(Origin::HyperMutation, evaluator.context.generation);
+            }
+
+            evaluator.evaluate(wrapper);
+            evaluator.count_mutation(parent_fitness, wrapper.fitness);
+        }
+
+        self.sort_keep_mutation_rates();
+
+        true
+    }
+
+    /// Changes the size of the population: new individuals are created (if there is a data
+    /// source) or the least fit individuals are removed.
+    fn resize(&mut self, size: usize, evaluator: &mut FitnessEvaluator) {
+        if size < self.population.len() {
+            self.population.truncate(size);
+        } else if size > self.population.len() {
+            if let Some(ref data_source) = self.data_source {
+                while self.population.len() < size {
+                    let index = self.population.len();
+                    let mut wrapper = IndividualWrapper {
+                        individual: new_individual(data_source, &self.initializer, evaluator, index),
+                        fitness: f64::MAX,
+                        num_of_mutations: 1,
+                        id: self.id,
+                        objectives: Vec::new(),
+                        dirty: true,
+                        lineage: None,
+                        cell: self.population.len(),
+                    };
+
+                    if self.track_lineage {
+                        wrapper.record_lineage(Origin::Reset, evaluator.context.generation);
+                    }
+
+                    evaluator.evaluate(&mut wrapper);
+                    self.population.push(wrapper);
+                }
+            }
+
+            self.sort_keep_mutation_rates();
+        }
+
+        self.num_of_individuals = self.population.len() as u32;
+    }
+
+    /// Sorts the population by fitness, but keeps the mutation rates at their positions.
+    fn sort_keep_mutation_rates(&mut self) {
+        let len = self.population.len();
+        self.sort_prefix_keep_mutation_rates(len);
+    }
+
+    /// Like `sort_keep_mutation_rates`, but only sorts the first `len` individuals.
+    fn sort_prefix_keep_mutation_rates(&mut self, len: usize) {
+        let num_of_mutations: Vec<u32> = self.population.iter().map(|wrapper| wrapper.num_of_mutations).collect();
+
+        self.population[..len].sort();
+
+        for (wrapper, num_of_mutations) in self.population.iter_mut().zip(num_of_mutations) {
+            wrapper.num_of_mutations = num_of_mutations;
+        }
+    }
+
+    /// Replaces the least fit `fraction` of the population with new individuals, the fittest
+    /// individual is always kept. The population must already be sorted by fitness.
+    fn inject_diversity(&mut self, fraction: f64, evaluator: &mut FitnessEvaluator) {
+        let data_source = match self.data_source {
+            Some(ref data_source) => data_source,
+            None => return
+        };
+
+        let num_of_individuals = self.population.len();
+        let num_of_new = ((num_of_individuals as f64) * fraction).round() as usize;
+        let num_of_new = num_of_new.min(num_of_individuals.saturating_sub(1));
+
+        for (index, wrapper) in self.population.iter_mut().enumerate().skip(num_of_individuals - num_of_new) {
+            wrapper.individual = new_individual(data_source, &self.initializer, evaluator, index);
+            wrapper.dirty = true;
+            wrapper.lineage = None;
+
+            if self.track_lineage {
+                wrapper.record_lineage(Origin::Reset, evaluator.context.generation);
+            }
+
+            evaluator.evaluate(wrapper);
+        }
+    }
+
+    /// Replaces every individual with the same signature as a fitter one with a new
+    /// individual. The population must already be sorted by fitness, so the fittest copy is
+    /// kept. Returns the number of replaced individuals.
+    fn replace_duplicates(&mut self, evaluator: &mut FitnessEvaluator) -> usize {
+        let data_source = match self.data_source {
+            Some(ref data_source) => data_source,
+            None => return 0
+        };
+
+        let mut signatures = HashSet::new();
+        let mut replaced = 0;
+
+        for (index, wrapper) in self.population.iter_mut().enumerate() {
+            let duplicate = match wrapper.individual.signature() {
+                Some(signature) => !signatures.insert(signature),
+                None => false
+            };
+
+            if duplicate {
+                wrapper.individual = new_individual(data_source, &self.initializer, evaluator, index);
+                wrapper.dirty = true;
+                wrapper.lineage = None;
+
+                if self.track_lineage {
+                    wrapper.record_lineage(Origin::Reset, evaluator.context.generation);
+                }
+
+                evaluator.evaluate(wrapper);
+                replaced += 1;
+            }
+        }
+
+        if replaced > 0 {
+            self.sort_keep_mutation_rates();
+        }
+
+        replaced
+    }
+
+    /// Keeps only the fittest individuals given by the survival rate (at least one) and
+    /// replaces all the others with mutated copies of these survivors. The population must
+    /// already be sorted by fitness.
+    fn regenerate(&mut self, evaluator: &mut FitnessEvaluator) {
+        let num_of_survivors = ((self.population.len() as f64) * self.effective_survival_rate()).ceil() as usize;
+        let num_of_survivors = num_of_survivors.max(1);
+        let selection_strategy = self.effective_selection_strategy();
+        let mut rng = self.seeded_rng(evaluator.context.generation, RNG_STREAM_SELECTION);
+
+        for index in num_of_survivors..self.population.len() {
+            let parent_index = selection_strategy.select(index, &self.population[..num_of_survivors],
+                self.tournament_tie_break, evaluator.context.generation, &mut rng);
+            let parent = self.population[parent_index].clone();
+            let parent_fitness = parent.fitness;
+            let num_of_mutations = self.population[index].num_of_mutations;
+            let mut offspring = parent.individual;
+
+            evaluator.seed_rng(index, RNG_STREAM_OFFSPRING);
+            mutate_feasible(&mut offspring, parent_fitness, num_of_mutations,
+                self.mutator.as_deref(), evaluator);
+            let repaired = repair(&mut offspring, evaluator);
+
+            let wrapper = &mut self.population[index];
+            wrapper.individual = offspring;
+            wrapper.fitness = parent_fitness;
+            wrapper.objectives = parent.objectives;
+            wrapper.dirty = parent.dirty || num_of_mutations > 0 || repaired;
+            wrapper.lineage = parent.lineage;
+
+            if self.track_lineage {
+                wrapper.record_lineage(Origin::Offspring, evaluator.context.generation);
+            }
+
+            evaluator.evaluate(wrapper);
+            evaluator.selections += 1;
+            evaluator.count_mutation(parent_fitness, wrapper.fitness);
+        }
+
+        // Offspring may be fitter than their parents
+        self.population.sort();
+    }
+}
+
+/// The methods that are also needed while the population is built, see `PopulationBuilder`.
+impl<S, T: Individual> Population<S, T> {
+    /// Creates a new fitness evaluator with the settings of this population.
+    pub fn new_evaluator(&self, iteration_counter: u32) -> FitnessEvaluator {
+        let mut evaluator = FitnessEvaluator::new(iteration_counter, self.fitness_samples,
+            self.num_of_objectives, self.objective_goals.clone());
+        evaluator.frozen_components = self.frozen_components.clone();
+        evaluator.aggregator = self.sample_aggregator;
+        evaluator.dirty_tracking = self.dirty_tracking;
+        evaluator.track_lineage = self.track_lineage;
+        evaluator.improvement_epsilon = self.improvement_epsilon;
+        evaluator.cancel = self.cancel.clone();
+        evaluator.mutation_seed = self.mutation_seed;
+        evaluator.rng_stream = self.rng_stream;
+        evaluator.feasibility_retries = self.feasibility_retries;
+        evaluator.repair = self.repair;
+        evaluator.context.penalty_scale = self.penalty_scale;
+
+        if self.fitness_scaled_mutation {
+            evaluator.fitness_range = self.population.iter()
+                .fold(None, |range, wrapper| match range {
+                    Some((best, worst)) => Some((wrapper.fitness.min(best), wrapper.fitness.max(worst))),
+                    None => Some((wrapper.fitness, wrapper.fitness))
+                });
+        }
+
+        evaluator
+    }
+
+    /// Configures this population for the evolution strategy with `mu` parents and `lambda`
+    /// offspring, see `SimulationBuilder::es_scheme`: `mu + lambda` individuals (new ones are
+    /// evaluated at the beginning of the simulation), each offspring is mutated once and its
+    /// parent is chosen uniformly at random among the `mu` fittest.
+    pub fn apply_es_scheme(&mut self, mu: usize, lambda: usize, comma: bool) {
+        let size = mu + lambda;
+
+        self.population.truncate(size);
+        let mut evaluator = self.new_evaluator(0);
+
+        if let Some(ref data_source) = self.data_source {
+            while self.population.len() < size {
+                let index = self.population.len();
+                let mut wrapper = IndividualWrapper {
+                    individual: new_individual(data_source, &self.initializer, &mut evaluator, index),
+                    fitness: f64::MAX,
+                    num_of_mutations: 1,
+                    id: self.id,
+                    objectives: Vec::new(),
+                    dirty: true,
+                    lineage: None,
+                    cell: self.population.len(),
+                };
+
+                if self.track_lineage {
+                    wrapper.record_lineage(Origin::Initial, 0);
+                }
+
+                self.population.push(wrapper);
+            }
+        }
+
+        for wrapper in &mut self.population {
+            wrapper.num_of_mutations = 1;
+        }
+
+        self.num_of_individuals = self.population.len() as u32;
+        // A little less than mu / size, so that rounding up gives exactly mu parents
+        self.survival_rate = ((mu as f64) - 0.5) / (size as f64);
+        self.selection_strategy = SelectionStrategy::Tournament { size: 1 };
+        self.generation_gap = None;
+        self.comma_selection = comma;
+    }
+
+    /// Returns a random number generator for the given stream of the given generation, seeded
+    /// from the mutation seed so that the selection and the crossover are reproducible (see
+    /// `PopulationBuilder::mutation_seed`). Without a mutation seed an unseeded one is returned.
+    fn seeded_rng(&self, generation: u32, stream: u64) -> XorShiftRng {
+        match self.mutation_seed {
+            Some(mutation_seed) => {
+                XorShiftRng::from_seed(derive_seed(mutation_seed, self.rng_stream, generation, 0, stream))
+            }
+            None => rand::weak_rng()
+        }
+    }
+
+    /// Mutates each individual except the first `skip` ones (the seeds) a random number of
+    /// times within the given range, see `PopulationBuilder::initial_mutations`. An empty range
+    /// means `range.start` mutations for each individual.
+    pub fn mutate_initial(&mut self, skip: usize, range: &Range<u32>) {
+        let mut evaluator = self.new_evaluator(0);
+        let mutator = self.mutator.as_deref();
+
+        for (index, wrapper) in self.population.iter_mut().enumerate().skip(skip) {
+            // The number of mutations and the mutations themselves use different seeds
+            evaluator.seed_rng(index, RNG_STREAM_INITIAL);
+            let num_of_mutations = if range.start < range.end {
+                match evaluator.rng_seed {
+                    Some(seed) => XorShiftRng::from_seed(seed).gen_range(range.start, range.end),
+                    None => rand::thread_rng().gen_range(range.start, range.end)
+                }
+            } else {
+                range.start
+            };
+            evaluator.reseed_rng();
+
+            mutate(&mut wrapper.individual, wrapper.fitness, num_of_mutations, mutator, &mut evaluator);
+            wrapper.dirty = true;
+        }
+    }
 }
@@ -12,15 +12,28 @@
 //!
 
 use std;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-use individual::{Individual, IndividualWrapper};
-use population::Population;
+use individual::{Individual, IndividualWrapper, Origin};
+use population::{Population, PopulationStats, Aggregator, SelectionStrategy, TieBreak, Grid, Neighborhood, Initializer, new_individual,
+    BoxedInitializer, BoxedFitnessScaling, BoxedReplacementPolicy, RNG_STREAM_NEW, RNG_STREAM_SEEDS};
+use simulation::{Pipeline, SimulationType};
+use operators::{shuffle_copies, AdaptivePursuit, Crossover, Mutator};
 
 /// This is a helper struct in order to build (configure) a valid population.
 /// See builder pattern: https://en.wikipedia.org/wiki/Builder_pattern
 pub struct PopulationBuilder<S, T: Individual> {
     /// The actual simulation
     population: Population<S, T>,
+    /// The maximum number of individuals allowed for this population, used as a sanity check.
+    max_individuals: u32,
+    /// The range of the number of mutations of each initial individual, see `initial_mutations`.
+    initial_mutations: Range<u32>,
+    /// The number of individuals given to `seed_individuals`, they are not mutated initially.
+    num_of_seeds: usize,
 }
 
 quick_error! {
@@ -28,10 +41,35 @@ quick_error! {
     pub enum Error {
         /// The number of individuals is too low, should be >= 3
         IndividualsTooLow {}
+        /// The number of individuals is greater than the allowed maximum, see `max_individuals`
+        IndividualsTooHigh {}
         /// reset_limit_start must be greater than reset_limit_end
         LimitEndTooLow {}
         /// The user must specify a data source
         DataSourceMissing {}
+        /// The survival rate must be greater than 0.0 and less or equal to 1.0
+        SurvivalRateOutOfRange {}
+        /// The generation gap must be between 0.0 and 1.0
+        GenerationGapOutOfRange {}
+        /// The number of fitness samples must be >= 1
+        FitnessSamplesTooLow {}
+        /// The percentile of the sample aggregator must be between 0.0 and 100.0
+        PercentileOutOfRange {}
+        /// The pressure of the rank based selection must be between 1.0 and 2.0
+        SelectionPressureOutOfRange {}
+        /// The tournament size must be >= 1
+        TournamentSizeTooLow {}
+        /// The temperature of the Boltzmann selection must be > 0.0 and the cooling rate
+        /// between 0.0 and 1.0
+        TemperatureOutOfRange {}
+        /// The diversity injection interval must be >= 1 and the fraction between 0.0 and 1.0
+        DiversityInjectionOutOfRange {}
+        /// The number of individuals of a spatial population must be width * height
+        GridSizeMismatch {}
+        /// The crossover rate must be between 0.0 and 1.0
+        CrossoverRateOutOfRange {}
+        /// The number of children kept per crossover must be 1 or 2
+        OffspringPerCrossoverOutOfRange {}
     }
 }
 
@@ -43,8 +81,11 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
     /// It creates a default population with some dummy (but invalid) values.
     pub fn new() -> PopulationBuilder<S, T> {
         PopulationBuilder {
+            max_individuals: 1_000_000,
+            initial_mutations: 0..0,
+            num_of_seeds: 0,
             population: Population {
-                num_of_individuals: 10,
+                num_of_individuals: 50,
                 population: Vec::new(),
                 reset_limit: 0,
                 reset_limit_start: 1000,
@@ -52,6 +93,67 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
                 reset_limit_increment: 1000,
                 reset_counter: 0,
                 id: 1,
+                survival_rate: 1.0,
+                generation_gap: None,
+                stop_criterion: None,
+                best_fitness: f64::MAX,
+                stalled_iterations: 0,
+                migration_fitness: f64::MAX,
+                fitness_history: VecDeque::new(),
+                stopped: false,
+                original_fitness: f64::MAX,
+                dynamic_fitness: false,
+                reevaluate_elites: false,
+                dirty_tracking: false,
+                dedup: false,
+                mutation_seed: None,
+                rng_stream: 0,
+                feasibility_retries: 0,
+                repair: false,
+                diversity_injection: None,
+                infeasibility_recovery: None,
+                infeasible_generations: 0,
+                penalty_scale: 1.0,
+                grid: None,
+                track_lineage: false,
+                track_individual_history: false,
+                fitness_samples: 1,
+                sample_aggregator: Aggregator::Mean,
+                selection_strategy: SelectionStrategy::RoundRobin,
+                auto_tune_selection: false,
+                comma_selection: false,
+                selection_pressure: 1.0,
+                tournament_tie_break: TieBreak::Random,
+                cpu_affinity: None,
+                fitness_scaled_mutation: false,
+                fitness_scaling: None,
+                replacement_policy: None,
+                plateau_threshold: None,
+                improvement_epsilon: 0.0,
+                cancel: Arc::new(AtomicBool::new(false)),
+                num_of_objectives: 0,
+                objective_goals: Arc::new(Vec::new()),
+                stats: PopulationStats {
+                    sample_variance: 0.0,
+                    reset: false,
+                    plateau_kick: false,
+                    duplicates_replaced: 0,
+                    repairs: 0,
+                    fitness_mean: 0.0,
+                    fitness_std_dev: 0.0,
+                    diversity: 1.0,
+                    infeasibility_recovery: None,
+                },
+                spare: Vec::new(),
+                pipeline: Pipeline::MutateEvaluateSelect,
+                mutation_start: 0,
+                size_schedule: None,
+                frozen_components: Arc::new(Vec::new()),
+                initializer: None,
+                mutator: None,
+                crossover: None,
+                crossover_rate: 0.0,
+                offspring_per_crossover: 1,
                 data_source: None
             }
         }
@@ -63,23 +165,90 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
     }
 
     /// Sets the number of individuals and creates the population, must be >= 3
+    /// and <= `max_individuals`. Default value is 50.
     pub fn individuals(mut self, individuals: u32) -> PopulationBuilder<S, T> {
         self.population.num_of_individuals = individuals;
 
+        if individuals <= self.max_individuals {
+            self.population.population.truncate(individuals as usize);
+            self.fill_population();
+        }
+
+        self
+    }
+
+    /// Sets the maximum number of individuals allowed, this is just a sanity check to detect
+    /// a wrong configuration before the simulation runs out of memory.
+    /// Default value is 1000000.
+    pub fn max_individuals(mut self, max_individuals: u32) -> PopulationBuilder<S, T> {
+        self.max_individuals = max_individuals;
+        self
+    }
+
+    /// Creates new individuals until the population has the configured size.
+    /// Does nothing if there is no data source yet.
+    fn fill_population(&mut self) {
+        let mut evaluator = self.population.new_evaluator(0);
+
         if let Some(ref data_source) = self.population.data_source {
-            for _ in 0..individuals {
+            while self.population.population.len() < self.population.num_of_individuals as usize {
+                let index = self.population.population.len();
                 self.population.population.push(IndividualWrapper {
-                    individual: Individual::new(data_source),
+                    individual: new_individual(data_source, &self.population.initializer, &mut evaluator, index),
                     fitness: std::f64::MAX,
                     num_of_mutations: 1,
                     id: self.population.id,
+                    objectives: Vec::new(),
+                    dirty: true,
+                    lineage: None,
+                    cell: self.population.population.len(),
                 });
             }
         }
+    }
+
+    /// Seeds the population with the given (for example heuristic) solutions, they replace
+    /// the first individuals. If there are fewer seeds than individuals, the rest of the
+    /// population is filled with copies of the seeds, each one pre-mutated a random number of
+    /// times (up to `max_mutations`) to guarantee some initial diversity.
+    /// The copies are mutated with `Individual::mutate_with_rng`, with a `mutation_seed` (set
+    /// before this) they are reproducible.
+    /// This must be called after `individuals()`.
+    pub fn seed_individuals(mut self, seeds: Vec<T>, max_mutations: u32) -> PopulationBuilder<S, T>
+        where T: Clone {
+        if seeds.is_empty() {
+            return self;
+        }
+
+        let mut evaluator = self.population.new_evaluator(0);
+        self.num_of_seeds = seeds.len();
+
+        for (index, wrapper) in self.population.population.iter_mut().enumerate() {
+            if index < seeds.len() {
+                wrapper.individual = seeds[index].clone();
+            } else {
+                let mut rng = evaluator.seeded_rng(index, RNG_STREAM_SEEDS);
+                wrapper.individual = shuffle_copies(&seeds[index % seeds.len()], 1, max_mutations, &mut rng)
+                    .remove(0);
+            }
+        }
 
         self
     }
 
+    /// Mutates each initial individual a random number of times (within the given range,
+    /// the end is excluded) once before the first iteration. This gives a population of
+    /// identical individuals (for example when `Individual::new` always returns the same
+    /// solution) some diversity to start with. The individuals given to `seed_individuals`
+    /// stay unchanged, their copies are mutated as well. The mutator (see `mutator`) is used if
+    /// there is one, and with `mutation_seed` the mutations are reproducible.
+    /// Like the seeds the mutated individuals are replaced when the population is reset, see
+    /// `reset_limit_end`. Default value is 0..0 (no initial mutations).
+    pub fn initial_mutations(mut self, initial_mutations: Range<u32>) -> PopulationBuilder<S, T> {
+        self.initial_mutations = initial_mutations;
+        self
+    }
+
     /// Configures the mutation rates (number of mutation runs) for all the individuals
     /// in the population: The first individual will mutate once, the second will mutate twice,
     /// the nth individual will Mutate n-times per iteration.
@@ -152,7 +321,340 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
         self
     }
 
-    /// Set the population id. Currently this is only used for statistics
+    /// Configures the selection pressure: the fraction of the population (0.0 - 1.0) that
+    /// survives each iteration. The remaining individuals are replaced by mutated copies of
+    /// the survivors, so with 0.5 the worse half is replaced by offspring of the better half.
+    /// Default value is 1.0 (keep all individuals).
+    pub fn survival_rate(mut self, survival_rate: f64) -> PopulationBuilder<S, T> {
+        self.population.survival_rate = survival_rate;
+        self
+    }
+
+    /// Sets the generation gap: the fraction of the population (0.0 - 1.0) that is replaced
+    /// by the fittest offspring each iteration, the remaining slots keep the fittest parents.
+    /// With 1.0 the population is fully generational (only offspring), with 0.0 the
+    /// population does not change. Unlike elitism there is no guarantee that the fittest
+    /// individual survives. By default parents and offspring compete for all slots.
+    pub fn generation_gap(mut self, generation_gap: f64) -> PopulationBuilder<S, T> {
+        self.population.generation_gap = Some(generation_gap);
+        self
+    }
+
+    /// Sets a stop criterion for this population only, overriding the one of the simulation.
+    /// Once it is reached the population is frozen, but its fittest individual is still part
+    /// of the simulation result. The simulation finishes when all populations have stopped.
+    pub fn stop_criterion(mut self, stop_criterion: SimulationType) -> PopulationBuilder<S, T> {
+        self.population.stop_criterion = Some(stop_criterion);
+        self
+    }
+
+    /// Enables re-calculation of the fitness of all the unmutated individuals every iteration.
+    /// Use this for dynamic problems where the fitness depends on the current generation
+    /// (see `Individual::calculate_fitness_with_context`). This disables the re-use (caching)
+    /// of the fitness from the last iteration, so it doubles the number of fitness evaluations.
+    /// Default value is false.
+    pub fn dynamic_fitness(mut self, dynamic_fitness: bool) -> PopulationBuilder<S, T> {
+        self.population.dynamic_fitness = dynamic_fitness;
+        self
+    }
+
+    /// Enables re-calculation of the fitness of the individuals that survived the last
+    /// iteration (the elites). With a noisy fitness (see `fitness_samples`) an individual that
+    /// survived because of one lucky evaluation would otherwise keep its fitness and dominate
+    /// the population forever. It is recommended for noisy fitness functions. The elites are
+    /// evaluated even if they haven't changed (see `dirty_tracking`), so this doubles the
+    /// number of fitness evaluations. Default value is false.
+    pub fn reevaluate_elites(mut self, reevaluate_elites: bool) -> PopulationBuilder<S, T> {
+        self.population.reevaluate_elites = reevaluate_elites;
+        self
+    }
+
+    /// Enables dirty tracking: the framework marks an individual as changed (dirty) when it is
+    /// mutated or created and only calculates the fitness of the changed individuals.
+    /// Unchanged individuals (for example with a mutation rate of 0, copies of parents or
+    /// migrants from other populations) keep their cached fitness. Don't use this if the
+    /// fitness of an unchanged individual can change, unless `dynamic_fitness` is enabled as
+    /// well (that marks all individuals as changed each iteration). Default value is false.
+    pub fn dirty_tracking(mut self, dirty_tracking: bool) -> PopulationBuilder<S, T> {
+        self.population.dirty_tracking = dirty_tracking;
+        self
+    }
+
+    /// Enables the removal of duplicates: after the selection, every individual with the same
+    /// signature as a fitter one (see `Individual::signature`, which must be implemented for
+    /// this) is replaced with a new individual, so that a converged population does not waste
+    /// evaluations on identical copies. The number of replaced individuals is reported in
+    /// `PopulationStats::duplicates_replaced`. Default value is false.
+    pub fn dedup(mut self, dedup: bool) -> PopulationBuilder<S, T> {
+        self.population.dedup = dedup;
+        self
+    }
+
+    /// Makes the mutations reproducible: the random number generator of each mutation is
+    /// seeded from this seed, the generation and the index of the individual, so the result
+    /// doesn't depend on the number of threads or on which thread mutates which individual.
+    /// Only a `Mutator` and `Individual::mutate_with_rng` get this generator, an individual
+    /// that mutates itself with `rand::thread_rng` is not reproducible. The selection of the
+    /// parents (see `selection_strategy`), the crossover and the `initializer` are seeded from
+    /// it as well. Use different seeds for different populations. By default unseeded random
+    /// number generators are used.
+    pub fn mutation_seed(mut self, seed: u64) -> PopulationBuilder<S, T> {
+        self.population.mutation_seed = Some(seed);
+        self
+    }
+
+    /// Keeps the population mostly feasible without a repair operator (repair by rejection):
+    /// if a mutated individual violates the constraints (see
+    /// `Individual::constraint_violation`), the mutation of its parent is repeated up to
+    /// `retries` times until the offspring is feasible, then the last one is accepted anyway.
+    /// The offspring of a crossover count as the parents of their mutation. Each retry costs
+    /// another mutation, so this can slow down the generation a lot if the feasible region is
+    /// tiny. Default value is 0 (no retries).
+    pub fn feasibility_retries(mut self, retries: u32) -> PopulationBuilder<S, T> {
+        self.population.feasibility_retries = retries;
+        self
+    }
+
+    /// Repairs the offspring after the mutation (and crossover) and before the fitness
+    /// calculation with `Individual::repair`, for example to drop items until a knapsack
+    /// fits. For heavily constrained problems this is often more efficient than rejecting
+    /// infeasible offspring (see `feasibility_retries`), which happens first if both are
+    /// enabled. The number of repaired offspring is reported in `PopulationStats::repairs`
+    /// and `SimulationResult::total_repairs`. Default value is false.
+    pub fn enable_repair(mut self, repair: bool) -> PopulationBuilder<S, T> {
+        self.population.repair = repair;
+        self
+    }
+
+    /// Enables diversity injection (a soft restart): every `every` iterations the least fit
+    /// `fraction` (0.0 - 1.0) of the population is replaced with new individuals at the
+    /// beginning of the iteration, the fittest individual is always kept. Unlike a reset (see
+    /// `reset_limit_end`) the good individuals survive, so this adds a steady trickle of new
+    /// genetic material. A reset in the same iteration happens first and replaces all
+    /// individuals anyway. The new individuals still have to compete with the fittest ones in
+    /// the selection of the same iteration (elitism), so only the reasonably fit ones survive.
+    /// Default: disabled.
+    pub fn diversity_injection(mut self, every: u32, fraction: f64) -> PopulationBuilder<S, T> {
+        self.population.diversity_injection = Some((every, fraction));
+        self
+    }
+
+    /// Makes this a spatial (cellular) population: the individuals live on a `width` x
+    /// `height` grid (the edges wrap around) and each cell only mates with its neighbors.
+    /// Every iteration the offspring of each cell is a mutated copy of the fittest individual
+    /// in its neighborhood (including itself), and it replaces the individual of the cell only
+    /// if it is fitter. Good solutions spread slowly over the grid, so the population stays
+    /// diverse much longer than a panmictic one.
+    /// This creates width * height individuals, so it must be called after `set_data_source`
+    /// and instead of `individuals`. The survival rate, the generation gap, the pipeline, the
+    /// selection strategy and the size schedule have no effect on a spatial population.
+    /// Default: panmictic (not spatial).
+    pub fn spatial(self, width: usize, height: usize, neighborhood: Neighborhood) -> PopulationBuilder<S, T> {
+        let mut builder = self.individuals((width * height) as u32);
+
+        builder.population.grid = Some(Grid {
+            width,
+            height,
+            neighborhood,
+        });
+
+        builder
+    }
+
+    /// Enables lineage tracking: each individual records how it was produced (mutation,
+    /// offspring, migration, ...), in which generation and in which population, so that the
+    /// history of the fittest individual can be analyzed (see `SimulationResult::best_lineage`).
+    /// Each record has a lineage id and the ids of its parents (both parents of a crossover),
+    /// see `Lineage::parent_ids`. This needs a lot of memory for long simulations. Default value is false.
+    pub fn track_lineage(mut self, track_lineage: bool) -> PopulationBuilder<S, T> {
+        self.population.track_lineage = track_lineage;
+        self
+    }
+
+    /// Records the fitness of each surviving individual in every generation in its lineage
+    /// (this enables lineage tracking as well), so that the fitness trajectory of a family of
+    /// solutions can be studied, see `SimulationResult::fitness_trajectories`. This adds one
+    /// record per individual and generation, so it needs a lot more memory than lineage
+    /// tracking alone. Default value is false.
+    pub fn track_individual_history(mut self, track_individual_history: bool) -> PopulationBuilder<S, T> {
+        self.population.track_individual_history = track_individual_history;
+        self.population.track_lineage |= track_individual_history;
+        self
+    }
+
+    /// Sets how often the fitness of each individual is calculated. The mean value of these
+    /// samples is used as the fitness of the individual, this is needed for noisy (stochastic)
+    /// fitness functions. The variance of the samples is available in `stats.sample_variance`.
+    /// Note that the fitness of the unmutated individuals is not calculated again in the next
+    /// iteration (cached), so a lucky evaluation persists. Enable `reevaluate_elites` to
+    /// re-sample them every iteration. Default value is 1.
+    pub fn fitness_samples(mut self, fitness_samples: u32) -> PopulationBuilder<S, T> {
+        self.population.fitness_samples = fitness_samples;
+        self
+    }
+
+    /// Sets the size of the population per generation (iteration), for example to start with a
+    /// small population for cheap exploration and grow it later on (or the other way round).
+    /// At the beginning of each iteration new individuals are added or the least fit
+    /// individuals are removed to match the given size (at least one individual).
+    /// By default the size is constant.
+    pub fn size_schedule(mut self, size_schedule: Box<dyn Fn(u32) -> usize + Send + Sync>) -> PopulationBuilder<S, T> {
+        self.population.size_schedule = Some(Arc::from(size_schedule));
+        self
+    }
+
+    /// Sets a function that creates the individuals of this population instead of
+    /// `Individual::new`, for example to start one population close to a heuristic solution
+    /// and another one fully random. It is used for the initial population, for resets and
+    /// to grow the population (see `size_schedule`). Individuals that already have been
+    /// created are replaced. The function gets a random number generator seeded from the
+    /// `mutation_seed` (set before this), the generation and the index of the new individual,
+    /// so the new individuals are reproducible. Without a mutation seed it gets an unseeded
+    /// one. Use `seed_individuals` after this method to start with given individuals.
+    pub fn initializer(mut self, initializer: BoxedInitializer<T>) -> PopulationBuilder<S, T> {
+        let initializer: Initializer<T> = Arc::from(initializer);
+        let mut evaluator = self.population.new_evaluator(0);
+
+        for (index, wrapper) in self.population.population.iter_mut().enumerate() {
+            wrapper.individual = initializer(&mut evaluator.seeded_rng(index, RNG_STREAM_NEW));
+            wrapper.dirty = true;
+        }
+
+        self.population.initializer = Some(initializer);
+        self
+    }
+
+    /// Sets the mutation strategy for all the individuals of this population, it replaces
+    /// `Individual::mutate`. The mutation rates still decide how often it is applied.
+    pub fn mutator(mut self, mutator: Box<dyn Mutator<T>>) -> PopulationBuilder<S, T> {
+        self.population.mutator = Some(Arc::from(mutator));
+        self
+    }
+
+    /// Uses the given mutation operators (with their names) instead of `Individual::mutate`
+    /// and lets the framework choose between them (adaptive operator selection): operators
+    /// that often improve an individual are used more often, see `operators::AdaptivePursuit`.
+    /// This replaces the `mutator`. The usage of the operators is reported by name in
+    /// `SimulationResult::operator_stats`. An empty list keeps `Individual::mutate`.
+    pub fn adaptive_operators(mut self, operators: Vec<(&str, Box<dyn Mutator<T>>)>) -> PopulationBuilder<S, T>
+        where T: 'static {
+        if !operators.is_empty() {
+            self.population.mutator = Some(Arc::new(AdaptivePursuit::new(operators)));
+        }
+        self
+    }
+
+    /// Sets the crossover (recombination) operator: each offspring is created by crossover
+    /// with the probability `crossover_rate` (0.0 - 1.0), the others are copies of a single
+    /// parent as usual. The parents are chosen with the selection strategy (see
+    /// `selection_strategy`) and all offspring are mutated afterwards. Has no effect on a
+    /// spatial population. By default there is no crossover.
+    pub fn crossover(mut self, crossover: Box<dyn Crossover<T>>, crossover_rate: f64) -> PopulationBuilder<S, T> {
+        self.population.crossover = Some(Arc::from(crossover));
+        self.population.crossover_rate = crossover_rate;
+        self
+    }
+
+    /// Sets how many children of each crossover are kept: 1 or 2. With 2 both children of an
+    /// operator that creates two (for example the complementary children of a one point
+    /// crossover) replace two offspring. Default value is 1.
+    pub fn offspring_per_crossover(mut self, offspring_per_crossover: usize) -> PopulationBuilder<S, T> {
+        self.population.offspring_per_crossover = offspring_per_crossover;
+        self
+    }
+
+    /// Sets the indices of the components (genes) of the individuals that must not change
+    /// during mutation, for example a fixed start city or the given cells of a Sudoku.
+    /// They are passed to the mutator (see `PopulationBuilder::mutator` and
+    /// `MutationContext::random_unfrozen_index`) which must honor them,
+    /// `Individual::mutate` does not know about them.
+    pub fn frozen_components(mut self, frozen_components: Vec<usize>) -> PopulationBuilder<S, T> {
+        self.population.frozen_components = Arc::new(frozen_components);
+        self
+    }
+
+    /// Sets how the fitness samples (see `fitness_samples`) are combined into one fitness
+    /// value. For robust optimization a risk averse aggregation like `Aggregator::Max` (worst
+    /// case) or `Aggregator::MeanPlusStd(k)` prefers solutions that are reliably good over
+    /// solutions that are only sometimes great. Only has an effect if `fitness_samples` > 1 and
+    /// only for the scalar fitness (lexicographic objectives always use the mean).
+    /// Default value is `Aggregator::Mean`.
+    pub fn sample_aggregator(mut self, sample_aggregator: Aggregator) -> PopulationBuilder<S, T> {
+        self.population.sample_aggregator = sample_aggregator;
+        self
+    }
+
+    /// Sets a function that transforms the fitness of an individual for the selection only,
+    /// the reported fitness stays unchanged. It gets the fitness and the statistics of the
+    /// population (`PopulationStats::fitness_mean` and `fitness_std_dev` are already
+    /// updated), for example sigma scaling:
+    /// `|fitness, stats| 1.0 + (fitness - stats.fitness_mean) / (2.0 * stats.fitness_std_dev)`.
+    /// The selection keeps the individuals with the lowest scaled fitness. Since it keeps the
+    /// fittest ones anyway, only a transformation that changes the order has an effect (for
+    /// example one that clamps or windows the fitness, or one that prefers values near the
+    /// mean). Individuals with the same scaled fitness are ordered by their fitness (or
+    /// lexicographic objectives). The survivors are sorted by their fitness again, so
+    /// the fittest one is still the first.
+    pub fn fitness_scaling(mut self, fitness_scaling: BoxedFitnessScaling) -> PopulationBuilder<S, T> {
+        self.population.fitness_scaling = Some(Arc::from(fitness_scaling));
+        self
+    }
+
+    /// Sets the rule for an offspring (first argument) to replace its parent (second
+    /// argument): each mutated individual only competes with the individual it was copied
+    /// from instead of the whole population, and replaces it if the function returns true.
+    /// This allows niching schemes like deterministic crowding, since an offspring takes the
+    /// place of a similar individual instead of the least fit one. For example
+    /// `|offspring, parent| offspring.fitness < parent.fitness` keeps the parent on ties.
+    /// The survival rate and the generation gap have no effect with a replacement policy.
+    /// It does not work with `Pipeline::SelectMutateEvaluate` (or an ES scheme), the
+    /// simulation builder rejects that. By default the fittest of all parents and offspring
+    /// survive.
+    pub fn replacement_policy(mut self, replacement_policy: BoxedReplacementPolicy<T>) -> PopulationBuilder<S, T> {
+        self.population.replacement_policy = Some(Arc::from(replacement_policy));
+        self
+    }
+
+    /// Enables fitness scaled mutation: the mutator (see `mutator`) gets the relative fitness
+    /// of each individual as `MutationContext::scale` (0.0 for the fittest, 1.0 for the least
+    /// fit individual), so that it can mutate individuals close to the best one less. The
+    /// mutator must honor the scale, for example for the TSP a single swap for good tours and
+    /// a segment reversal for bad ones. Has no effect without a mutator. Default value is false.
+    pub fn fitness_scaled_mutation(mut self, fitness_scaled_mutation: bool) -> PopulationBuilder<S, T> {
+        self.population.fitness_scaled_mutation = fitness_scaled_mutation;
+        self
+    }
+
+    /// Sets how the parents of the offspring are chosen among the survivors, see
+    /// `survival_rate`. Only has an effect if the survival rate is below 1.0 or with
+    /// `Pipeline::SelectMutateEvaluate`. Default value is `SelectionStrategy::RoundRobin`.
+    pub fn selection_strategy(mut self, selection_strategy: SelectionStrategy) -> PopulationBuilder<S, T> {
+        self.population.selection_strategy = selection_strategy;
+        self
+    }
+
+    /// Sets how a tie between equally fit competitors of a tournament is broken, see
+    /// `SelectionStrategy::Tournament`. Default value is `TieBreak::Random`.
+    pub fn tournament_tie_break(mut self, tournament_tie_break: TieBreak) -> PopulationBuilder<S, T> {
+        self.population.tournament_tie_break = tournament_tie_break;
+        self
+    }
+
+    /// Pins the mutation and fitness calculation of this population to the given core: its
+    /// individuals are not shared with the thread pool, but mutated and evaluated on a thread
+    /// of their own that stays on that core. This is meant to keep large problem data in the
+    /// caches of one core (or NUMA node). The other phases of an iteration still use the
+    /// thread pool, and nothing is pinned with an executor (see `SimulationBuilder::executor`).
+    /// Only available with the `core_affinity` feature, without it (or if the operating
+    /// system doesn't support it) the population is not pinned. Default value is None.
+    pub fn cpu_affinity(mut self, cpu_affinity: Option<usize>) -> PopulationBuilder<S, T> {
+        self.population.cpu_affinity = cpu_affinity;
+        self
+    }
+
+    /// Set the population id. It is used for statistics and to find the population (for
+    /// example in the migration topology), so each population of a simulation needs its own
+    /// id. Default value is 1.
     pub fn set_id(mut self, id: u32) -> PopulationBuilder<S, T> {
         for individual in &mut self.population.population {
             individual.id = id;
@@ -164,11 +666,32 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
 
     /// This checks the configuration of the simulation and returns an error or Ok if no errors
     /// where found.
-    pub fn finalize(self) -> Result<S, T> {
+    /// If `individuals()` was not called (or was called before the data source was set) the
+    /// population is created here.
+    pub fn finalize(mut self) -> Result<S, T> {
+        if self.population.num_of_individuals <= self.max_individuals {
+            self.fill_population();
+        }
+
+        if self.initial_mutations.end > 0 {
+            self.population.mutate_initial(self.num_of_seeds, &self.initial_mutations);
+        }
+
+        if self.population.track_lineage {
+            for wrapper in &mut self.population.population {
+                if wrapper.lineage.is_none() {
+                    wrapper.record_lineage(Origin::Initial, 0);
+                }
+            }
+        }
+
         match self.population {
             Population { num_of_individuals: 0...2, ..} => {
                 Err(Error::IndividualsTooLow)
             }
+            Population { num_of_individuals, ..} if num_of_individuals > self.max_individuals => {
+                Err(Error::IndividualsTooHigh)
+            }
             Population { reset_limit_start: start,
                          reset_limit_end: end, ..} if (end > 0) && (start >= end) => {
                 Err(Error::LimitEndTooLow)
@@ -176,6 +699,42 @@ impl<S, T: Individual> PopulationBuilder<S, T> {
             Population { data_source: None, ..} => {
                 Err(Error::DataSourceMissing)
             }
+            Population { survival_rate: rate, ..} if !(rate > 0.0 && rate <= 1.0) => {
+                Err(Error::SurvivalRateOutOfRange)
+            }
+            Population { generation_gap: Some(gap), ..} if !(0.0..=1.0).contains(&gap) => {
+                Err(Error::GenerationGapOutOfRange)
+            }
+            Population { fitness_samples: 0, ..} => {
+                Err(Error::FitnessSamplesTooLow)
+            }
+            Population { sample_aggregator: Aggregator::Percentile(value), ..} if !(0.0..=100.0).contains(&value) => {
+                Err(Error::PercentileOutOfRange)
+            }
+            Population { selection_strategy: SelectionStrategy::RankBased { pressure }, ..}
+                if !(1.0..=2.0).contains(&pressure) => {
+                Err(Error::SelectionPressureOutOfRange)
+            }
+            Population { selection_strategy: SelectionStrategy::Tournament { size: 0 }, ..} => {
+                Err(Error::TournamentSizeTooLow)
+            }
+            Population { selection_strategy: SelectionStrategy::Boltzmann { initial_temperature, cooling_rate }, ..}
+                if !(initial_temperature > 0.0 && cooling_rate > 0.0 && cooling_rate <= 1.0) => {
+                Err(Error::TemperatureOutOfRange)
+            }
+            Population { crossover_rate, ..} if !(0.0..=1.0).contains(&crossover_rate) => {
+                Err(Error::CrossoverRateOutOfRange)
+            }
+            Population { offspring_per_crossover, ..} if !(1..=2).contains(&offspring_per_crossover) => {
+                Err(Error::OffspringPerCrossoverOutOfRange)
+            }
+            Population { grid: Some(ref grid), ref population, ..} if population.len() != grid.width * grid.height => {
+                Err(Error::GridSizeMismatch)
+            }
+            Population { diversity_injection: Some((every, fraction)), ..}
+                if every == 0 || !(0.0..=1.0).contains(&fraction) => {
+                Err(Error::DiversityInjectionOutOfRange)
+            }
             _ => Ok(self.population)
         }
     }
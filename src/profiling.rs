@@ -0,0 +1,147 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Fine grained timings of the simulation, only available with the `profiling` feature.
+//! Without the feature nothing is measured, so there is no overhead.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Instant;
+
+/// Returns the time since `start` in nanoseconds.
+pub fn elapsed_nanos(start: Instant) -> u64 {
+    let elapsed = start.elapsed();
+    elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64
+}
+
+/// How often something was measured and how long it took in total.
+#[derive(Debug,Clone,Copy,Default,PartialEq)]
+pub struct Timing {
+    /// The number of measurements.
+    pub count: u64,
+    /// The sum of all measurements in nanoseconds.
+    pub total_ns: u64,
+}
+
+impl Timing {
+    /// The total time in milliseconds.
+    pub fn total_ms(&self) -> f64 {
+        self.total_ns as f64 / 1_000_000.0
+    }
+}
+
+/// The `Profile` type. Hierarchical timings, each one is identified by its path (for example
+/// `["population 1", "mutation", "swap"]`), see `SimulationResult::profile`.
+///
+/// The simulation records these paths:
+///
+/// - `iteration` with the phases `prepare`, `mutate and evaluate`, `finish`, `update result`
+///   and `migration`: wall clock time of the whole simulation.
+/// - `population <id>` with `prepare`, `mutation`, `evaluation` and `finish`: the time spent
+///   on this population summed up over all threads, so it can be more than the wall clock
+///   time. `mutation` is split up by operator (see `Mutator::operator_names`) if the
+///   population uses a mutator.
+#[derive(Debug,Clone,Default)]
+pub struct Profile {
+    /// All timings by their path. The paths are sorted, so every parent comes right before
+    /// its children.
+    pub timings: BTreeMap<Vec<String>, Timing>,
+}
+
+impl Profile {
+    /// Creates an empty profile.
+    pub fn new() -> Profile {
+        Profile {
+            timings: BTreeMap::new(),
+        }
+    }
+
+    /// Adds one measurement of the given duration to the timing with the given path.
+    pub fn add(&mut self, path: &[&str], nanos: u64) {
+        let path: Vec<String> = path.iter().map(|name| name.to_string()).collect();
+        self.add_timing(path, Timing { count: 1, total_ns: nanos });
+    }
+
+    /// Adds the given timing to the timing with the given path.
+    pub fn add_timing(&mut self, path: Vec<String>, timing: Timing) {
+        let entry = self.timings.entry(path).or_default();
+        entry.count += timing.count;
+        entry.total_ns += timing.total_ns;
+    }
+
+    /// Adds all the timings of another profile to this one, the other paths get the given
+    /// prefix.
+    pub fn merge(&mut self, prefix: &[String], other: &Profile) {
+        for (path, timing) in &other.timings {
+            let mut full_path = prefix.to_vec();
+            full_path.extend(path.iter().cloned());
+            self.add_timing(full_path, *timing);
+        }
+    }
+
+    /// Returns the timing with the given path, if it was measured.
+    pub fn get(&self, path: &[&str]) -> Option<Timing> {
+        let path: Vec<String> = path.iter().map(|name| name.to_string()).collect();
+        self.timings.get(&path).cloned()
+    }
+
+    /// Exports the profile in the collapsed stack format of flamegraph.pl (and compatible
+    /// tools): one line per path with its own time in microseconds (the time of its
+    /// children is subtracted), for example `iteration;finish 1234`.
+    pub fn to_collapsed(&self) -> String {
+        let mut collapsed = String::new();
+
+        for (path, timing) in &self.timings {
+            let children: u64 = self.timings.iter()
+                .filter(|&(child, _)| child.len() == path.len() + 1 && child.starts_with(path))
+                .map(|(_, child)| child.total_ns)
+                .sum();
+            let own_time = timing.total_ns.saturating_sub(children) / 1000;
+
+            if own_time > 0 {
+                collapsed.push_str(&format!("{} {}\n", path.join(";"), own_time));
+            }
+        }
+
+        collapsed
+    }
+}
+
+/// A simple hierarchical text dump, one line per path indented by its depth.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (path, timing) in &self.timings {
+            writeln!(f, "{}{}: {:.3} ms ({} times)", "  ".repeat(path.len() - 1),
+                path.last().map_or("", |name| name.as_str()), timing.total_ms(), timing.count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Profile;
+
+    #[test]
+    fn collapsed_and_display() {
+        let mut profile = Profile::new();
+        profile.add(&["iteration"], 10_000);
+        profile.add(&["iteration", "finish"], 3_000);
+        profile.add(&["iteration", "finish"], 3_000);
+        profile.add(&["iteration", "prepare"], 1_000);
+
+        assert_eq!(profile.get(&["iteration", "finish"]).unwrap().count, 2);
+        assert_eq!(profile.to_collapsed(), "iteration 3\niteration;finish 6\niteration;prepare 1\n");
+        assert_eq!(format!("{}", profile),
+            "iteration: 0.010 ms (1 times)\n  finish: 0.006 ms (2 times)\n  prepare: 0.001 ms (1 times)\n");
+    }
+}
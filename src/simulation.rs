@@ -11,13 +11,39 @@
 //!
 //!
 
-use std::time::Instant;
-use std::sync::Mutex;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+#[cfg(feature = "serde")]
+use std::fs::File;
+#[cfg(feature = "serde")]
+use std::io;
+#[cfg(feature = "serde")]
+use std::path::Path;
 
-use jobsteal::{make_pool, IntoSplitIterator, SplitIterator};
+use jobsteal::{make_pool, Pool, IntoSplitIterator, SplitIterator};
+#[cfg(feature = "indicatif")]
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "serde")]
+use autosave::Autosave;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+#[cfg(feature = "serde")]
+use serde_json;
 
-use individual::{Individual, IndividualWrapper};
-use population::Population;
+#[cfg(feature = "profiling")]
+use profiling::{elapsed_nanos, Profile};
+use individual::{Individual, IndividualWrapper, FitnessGoal, Lineage};
+use operators::{Mutator, OperatorStat};
+use population::{Population, FitnessEvaluator, InfeasibilityRecovery, mutate_and_evaluate_feasible,
+    normalize_objectives, RNG_STREAM_MUTATION};
+#[cfg(feature = "async")]
+use population::{mutate_offspring_feasible, evaluate_async};
+use statistics::{Comparison, better_from_ordering, mean};
 
 /// The `SimulationType` type. Speficies the criteria on how a simulation should stop.
 #[derive(Debug,Clone)]
@@ -33,6 +59,230 @@ pub enum SimulationType {
     /// That means the relation between the very first fitness and the current fitness of the
     /// fittest individual
     EndFactor(f64),
+    /// Finish the simulation when the fitness of the fittest individual has changed by less
+    /// than `epsilon` over the last `window` iterations. Unlike a check for no improvement at
+    /// all, this allows tiny (noise level) changes, useful for noisy real valued problems.
+    EndConvergence { window: u32, epsilon: f64 },
+    /// Finish the simulation when the remaining relative gap to the known optimum (see
+    /// `SimulationBuilder::target_optimum`) is reached:
+    /// `(fitness - optimum) / (original_fitness - optimum) <= gap`. For example 0.05 stops
+    /// when 95% of the possible improvement has been made.
+    EndRelativeGap(f64),
+    /// Finish the simulation only when all the given stop criteria are reached (AND), for
+    /// example a minimum number of iterations and a target fitness, so that a lucky early
+    /// generation does not stop the simulation too early. All the other stop criteria stop
+    /// on their own, so several of them on different populations act like OR: the first one
+    /// reached stops its population. All criteria share the same fitness history, so use at
+    /// most one `EndConvergence` in it.
+    EndAll(Vec<SimulationType>),
+    /// No stop criterion has been set. This is the default of the `SimulationBuilder`,
+    /// `finalize()` rejects it unless all populations have their own stop criterion.
+    NotConfigured,
+}
+
+impl SimulationType {
+    /// Checks if this stop criterion is reached, given the number of iterations done so far,
+    /// the current fittest fitness and the current improvement factor.
+    /// Since the fitness is always minimized, the tolerance is added to the target fitness
+    /// (or factor): the criterion is reached if `fitness <= end_fitness + tolerance`.
+    pub fn stop_reached(&self, iteration_counter: u32, fitness: f64, improvement_factor: f64,
+                        tolerance: f64) -> bool {
+        match *self {
+            SimulationType::EndIteration(end_iteration) => iteration_counter >= end_iteration,
+            SimulationType::EndFitness(end_fitness) => fitness <= end_fitness + tolerance,
+            SimulationType::EndFactor(end_factor) => improvement_factor <= end_factor + tolerance,
+            // Needs the fitness history, see `converged`
+            SimulationType::EndConvergence { .. } => false,
+            // Needs the optimum, see `gap_reached`
+            SimulationType::EndRelativeGap(_) => false,
+            // Needs all the information, see `reached`
+            SimulationType::EndAll(_) => false,
+            SimulationType::NotConfigured => false,
+        }
+    }
+
+    /// Checks if the relative gap to the target optimum is reached. Always false for the
+    /// other stop criteria or if no target optimum is given.
+    pub fn gap_reached(&self, fitness: f64, original_fitness: f64, target_optimum: Option<f64>,
+                       tolerance: f64) -> bool {
+        match (self, target_optimum) {
+            (&SimulationType::EndRelativeGap(gap), Some(optimum)) => {
+                let range = original_fitness - optimum;
+
+                if range > 0.0 {
+                    (fitness - optimum) / range <= gap + tolerance
+                } else {
+                    fitness <= optimum + tolerance
+                }
+            }
+            _ => false
+        }
+    }
+
+    /// Checks all the stop criteria (`stop_reached`, `converged` and `gap_reached`) at once,
+    /// this also updates the fitness history. For `EndAll` all the contained criteria are
+    /// checked, each one must be reached.
+    pub fn reached(&self, iteration_counter: u32, fitness: f64, original_fitness: f64, target_optimum: Option<f64>,
+                   tolerance: f64, fitness_history: &mut VecDeque<f64>) -> bool {
+        match *self {
+            // Every criterion must be checked to keep the fitness history up to date, so no
+            // short circuit here
+            SimulationType::EndAll(ref stop_criteria) => stop_criteria.iter()
+                .map(|stop_criterion| stop_criterion.reached(iteration_counter, fitness, original_fitness,
+                    target_optimum, tolerance, fitness_history))
+                .fold(!stop_criteria.is_empty(), |all, reached| all & reached),
+            _ => {
+                let converged = self.converged(fitness_history, fitness);
+
+                converged || self.gap_reached(fitness, original_fitness, target_optimum, tolerance) ||
+                    self.stop_reached(iteration_counter, fitness, fitness / original_fitness, tolerance)
+            }
+        }
+    }
+
+    /// Adds the current fitness to the history (a ring buffer of the last `window + 1`
+    /// values) and checks if the fitness has converged. Always false and without history
+    /// for the other stop criteria.
+    pub fn converged(&self, fitness_history: &mut VecDeque<f64>, fitness: f64) -> bool {
+        match *self {
+            SimulationType::EndConvergence { window, epsilon } => {
+                fitness_history.push_back(fitness);
+                while fitness_history.len() > (window as usize) + 1 {
+                    fitness_history.pop_front();
+                }
+
+                if fitness_history.len() <= window as usize {
+                    return false;
+                }
+
+                let min = fitness_history.iter().cloned().fold(f64::MAX, f64::min);
+                let max = fitness_history.iter().cloned().fold(f64::MIN, f64::max);
+
+                max - min < epsilon
+            }
+            _ => false
+        }
+    }
+}
+
+/// The order of the steps in each iteration, see `SimulationBuilder::pipeline`.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Pipeline {
+    /// Mutate copies of all individuals, evaluate them, then select the fittest of the
+    /// parents and the mutated copies (default). The fitness of the parents is cached, so
+    /// only the copies are evaluated.
+    MutateEvaluateSelect,
+    /// Select the fittest individuals as parents first (see
+    /// `PopulationBuilder::survival_rate`), replace all other individuals with mutated
+    /// copies of the parents and evaluate only these offspring. The parents always survive.
+    SelectMutateEvaluate,
+}
+
+/// A textbook evolution strategy (ES) scheme, see `SimulationBuilder::es_scheme`. `mu` is the
+/// number of parents and `lambda` the number of offspring of each iteration.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum ESScheme {
+    /// One parent, one offspring, the fitter one survives: the same as `MuPlusLambda` with
+    /// mu = 1 and lambda = 1.
+    OnePlusOne,
+    /// The `mu` fittest of the parents and offspring survive (plus selection, elitist).
+    MuPlusLambda { mu: usize, lambda: usize },
+    /// The `mu` fittest of the offspring survive, all parents die (comma selection).
+    /// `lambda` must be >= `mu`.
+    MuCommaLambda { mu: usize, lambda: usize },
+}
+
+impl ESScheme {
+    /// Returns mu, lambda and true for comma selection.
+    pub fn parameters(&self) -> (usize, usize, bool) {
+        match *self {
+            ESScheme::OnePlusOne => (1, 1, false),
+            ESScheme::MuPlusLambda { mu, lambda } => (mu, lambda, false),
+            ESScheme::MuCommaLambda { mu, lambda } => (mu, lambda, true),
+        }
+    }
+
+    /// Returns true if mu >= 1 and lambda >= 1, and lambda >= mu for the comma selection.
+    pub fn is_valid(&self) -> bool {
+        let (mu, lambda, comma) = self.parameters();
+
+        mu >= 1 && lambda >= 1 && (!comma || lambda >= mu)
+    }
+}
+
+quick_error! {
+    #[derive(Debug)]
+    pub enum RunError {
+        /// The fitness calculation (or mutation) of an individual panicked, the simulation
+        /// was stopped.
+        WorkerPanicked { population_id: u32 } {
+            display("worker panicked, population id: {}", population_id)
+        }
+        /// There is no population with the given id.
+        UnknownPopulation { population_id: u32 } {
+            display("unknown population id: {}", population_id)
+        }
+        /// The number of mutation rates does not match the number of individuals.
+        MutationRateMismatch { population_id: u32, expected: usize, given: usize } {
+            display("population id: {} has {} individuals but {} mutation rates were given",
+                population_id, expected, given)
+        }
+        /// At least one thread is needed.
+        ThreadsTooLow {}
+        /// The simulation was cancelled with the cancel flag, see `Simulation::cancel_flag`.
+        Cancelled {}
+    }
+}
+
+/// A user provided executor for the mutation and fitness evaluation jobs, see
+/// `SimulationBuilder::executor`. The jobs borrow the individuals of the simulation (they are
+/// not `'static`), so the executor must run all of them and only return when every job is
+/// finished. Only scoped executors can do that, for example `rayon::scope`,
+/// `std::thread::scope` or `jobsteal::Pool::scope`.
+pub type Executor = Box<dyn for<'a> Fn(Vec<Box<dyn FnOnce() + Send + 'a>>) + Send>;
+
+/// One unit of work for the thread pool: mutate and evaluate a single individual.
+struct MutationJob<'a, T: 'a + Individual> {
+    /// The index of the population in the habitat.
+    population_index: usize,
+    /// The id of the population, to report panics.
+    population_id: u32,
+    /// The individual to mutate.
+    wrapper: &'a mut IndividualWrapper<T>,
+    /// The mutation strategy of the population, if any.
+    mutator: Option<&'a dyn Mutator<T>>,
+    /// Counts the fitness evaluations of this job.
+    evaluator: FitnessEvaluator,
+    /// The core of the population, see `PopulationBuilder::cpu_affinity`. Always None
+    /// without the `core_affinity` feature.
+    cpu_affinity: Option<usize>,
+}
+
+/// Pins the current thread to the given core, see `PopulationBuilder::cpu_affinity`.
+#[cfg(feature = "core_affinity")]
+fn pin_to_core(core: usize) {
+    // If the operating system doesn't support it the thread just runs anywhere
+    core_affinity::set_for_current(core_affinity::CoreId { id: core });
+}
+
+/// Without the `core_affinity` feature nothing is pinned.
+#[cfg(not(feature = "core_affinity"))]
+fn pin_to_core(_core: usize) {
+}
+
+/// Runs the given function and stores the population id if it panics.
+fn catch_panic<F: FnOnce()>(population_id: u32, panicked_mutex: &Mutex<Option<u32>>, f: F) {
+    if panic::catch_unwind(AssertUnwindSafe(f)).is_err() {
+        set_panicked(population_id, panicked_mutex);
+    }
+}
+
+/// Stores the id of a population that panicked, see `RunError::WorkerPanicked`.
+fn set_panicked(population_id: u32, panicked_mutex: &Mutex<Option<u32>>) {
+    match panicked_mutex.lock() {
+        Ok(mut panicked) => *panicked = Some(population_id),
+        Err(e) => println!("Mutex (poison) error (panicked): {}", e)
+    }
 }
 
 /// The `Simulation` type. Contains all the information / configuration for the simulation to run.
@@ -40,18 +290,144 @@ pub enum SimulationType {
 pub struct Simulation<S: Send + Sync, T: Individual + Send + Sync> {
     /// How should the simulation stop ?
     pub type_of_simulation: SimulationType,
+    /// The recent fitness values of the fittest individual, only used for the
+    /// `EndConvergence` stop criterion.
+    pub fitness_history: VecDeque<f64>,
+    /// The number of objectives compared lexicographically. 0 means that the scalar fitness
+    /// is used (default).
+    pub num_of_objectives: usize,
+    /// The goal (minimize or maximize) of each objective. Empty means that all objectives are
+    /// minimized (default).
+    pub objective_goals: Vec<FitnessGoal>,
+    /// The known (min, max) range of each objective, see `SimulationBuilder::objective_bounds`.
+    /// Empty means no normalization (default).
+    pub objective_bounds: Vec<(f64, f64)>,
+    /// Fitness variance threshold for the plateau detection of each population,
+    /// see `SimulationBuilder::plateau_threshold`.
+    pub plateau_threshold: Option<f64>,
+    /// The known optimum (best possible fitness) of the problem, used for the
+    /// `EndRelativeGap` stop criterion.
+    pub target_optimum: Option<f64>,
+    /// The tolerance for the `EndFitness` and `EndFactor` stop criteria, since floating point
+    /// values rarely hit an exact target.
+    pub tolerance: f64,
+    /// The minimal fitness improvement for a successful mutation, the tolerance is used if
+    /// not set, see `SimulationBuilder::improvement_epsilon`.
+    pub improvement_epsilon: Option<f64>,
+    /// The order of selection, mutation and evaluation in each iteration.
+    pub pipeline: Pipeline,
+    /// The evolution strategy scheme of all populations, see `SimulationBuilder::es_scheme`.
+    pub es_scheme: Option<ESScheme>,
+    /// The recovery policy of all populations if no individual is feasible, see
+    /// `SimulationBuilder::infeasibility_recovery`.
+    pub infeasibility_recovery: Option<(InfeasibilityRecovery, u32)>,
     /// The number of threads to use to speed up calculation.
     pub num_of_threads: usize,
+    /// If set, one thread is used per this many individuals instead of `num_of_threads`,
+    /// see `SimulationBuilder::threads_per_individuals`.
+    pub individuals_per_thread: Option<usize>,
+    /// Show a progress bar (`EndIteration`) or a spinner (other stop criteria) with the
+    /// current fittest fitness during `run()`.
+    #[cfg(feature = "indicatif")]
+    pub progress_bar: bool,
+    /// Writes the fittest individual to a file, see `SimulationBuilder::autosave_best`.
+    #[cfg(feature = "serde")]
+    pub autosave: Option<Autosave<T>>,
+    /// Every `migration_interval` iterations the fittest individual of each population is
+    /// sent to other populations (island model). 0 disables migration (default).
+    pub migration_interval: u32,
+    /// The directed migration edges (source population id, destination population id).
+    /// If not set, the populations form a ring in the order they were added.
+    pub migration_topology: Option<Vec<(usize, usize)>>,
+    /// If true, the number of migrants of each population depends on its improvement since
+    /// the last migration, see `SimulationBuilder::migration_adaptive`.
+    pub migration_adaptive: bool,
+    /// Called with the population id and the iteration when a population resets,
+    /// see `SimulationBuilder::on_reset`.
+    pub on_reset: Option<Box<dyn FnMut(usize, u32) + Send>>,
+    /// Runs the mutation and evaluation jobs instead of the thread pool,
+    /// see `SimulationBuilder::executor`.
+    pub executor: Option<Executor>,
+    /// The maximum number of fitness calculations awaited at the same time,
+    /// see `SimulationBuilder::max_concurrent_evals`.
+    #[cfg(feature = "async")]
+    pub max_concurrent_evals: Option<usize>,
+    /// Populations that have not improved for this many iterations are removed,
+    /// see `SimulationBuilder::prune_stalled`.
+    pub prune_stagnation: Option<u32>,
+    /// Log the fittest individual every n iterations, see `SimulationBuilder::log_best_every`.
+    pub log_best_every: Option<u32>,
+    /// If true, all the individuals of the final populations are kept in the simulation
+    /// result, see `SimulationBuilder::keep_final_population`.
+    pub keep_final_population: bool,
+    /// If true, no history is recorded, see `SimulationBuilder::minimal_memory`.
+    pub minimal_memory: bool,
+    /// If true, the populations are evolved one after another,
+    /// see `SimulationBuilder::sequential_populations`.
+    pub sequential_populations: bool,
+    /// The number of improvements of the fittest individual that are kept in
+    /// `SimulationResult::best_snapshots`, 0 means none.
+    pub recent_bests_capacity: usize,
+    /// If true, the snapshots of the fittest individual contain a copy of it.
+    pub recent_bests_individuals: bool,
+    /// The events of the simulation are sent to this channel, see `SimulationBuilder::events`.
+    pub events: Option<Sender<SimulationEvent<T>>>,
+    /// If true, the `NewBest` events contain a copy of the fittest individual.
+    pub events_individuals: bool,
+    /// The number of local search steps on the fittest individual once the simulation has
+    /// finished, see `SimulationBuilder::final_refinement`.
+    pub final_refinement: u32,
+    /// The stream of the seeded random number generators,
+    /// see `SimulationBuilder::rng_stream`.
+    pub rng_stream: u64,
+    /// If true the selection pressure of each population follows its diversity,
+    /// see `SimulationBuilder::auto_tune_selection`.
+    pub auto_tune_selection: bool,
+    /// Cancels the simulation when set, see `cancel_flag`.
+    pub cancel: Arc<AtomicBool>,
+    /// The thread pool and its number of threads, created by the first call to `run()`,
+    /// `step()` or `run_for()` and reused by the following ones.
+    pub pool: Option<(usize, Pool)>,
     /// All the populations for the simulation. Contains all individuals for the simulation.
     pub habitat: Vec<Population<S, T>>,
-    /// The total run time for the simulation. This will be calculated once the stimulation has
-    /// finished.
-    pub total_time_in_ms: f64,
     /// The result of the simulation: improvement_factor, original_fitness and a vector of
     /// fittest individuals
     pub simulation_result: SimulationResult<T>
 }
 
+/// A snapshot of the fittest individual of the simulation after an iteration in which it
+/// improved, see `SimulationBuilder::recent_bests`.
+#[derive(Debug,Clone)]
+pub struct BestSnapshot<T> {
+    /// The iteration in which the fittest individual improved.
+    pub iteration: u32,
+    /// The fitness of the fittest individual.
+    pub fitness: f64,
+    /// The id of the population the fittest individual belongs to.
+    pub population_id: u32,
+    /// A copy of the fittest individual, only kept if requested.
+    pub individual: Option<T>,
+}
+
+/// An event that is sent to the channel given to `SimulationBuilder::events` while the
+/// simulation runs. More kinds of events may be added, so a match needs a wildcard arm.
+#[derive(Debug,Clone)]
+#[non_exhaustive]
+pub enum SimulationEvent<T> {
+    /// The fittest individual of the simulation improved in an iteration.
+    NewBest(BestSnapshot<T>),
+    /// No individual of a population was feasible for too long and the recovery policy was
+    /// applied, see `SimulationBuilder::infeasibility_recovery`.
+    InfeasibilityRecovery {
+        /// The id of the population.
+        population_id: u32,
+        /// The iteration in which the policy was applied.
+        iteration: u32,
+        /// The applied policy.
+        policy: InfeasibilityRecovery,
+    },
+}
+
 /// The `SimulationResult` Type. Holds the simulation results:
 /// All the fittest individuals, the improvement_factor, the iteration_counter and the
 /// original_fitness.
@@ -60,129 +436,3373 @@ pub struct SimulationResult<T: Individual + Send + Sync> {
     /// The current improvement factor, that means the ration between the very first and the
     /// current fitness.
     pub improvement_factor: f64,
+    /// The progress toward the target optimum (see `SimulationBuilder::target_optimum`)
+    /// between 0.0 (no improvement since the initial population) and 1.0 (optimum reached).
+    /// Always 0.0 without a target optimum.
+    pub normalized_improvement: f64,
     /// The very first calculated fitness, when the simulation just started.
     pub original_fitness: f64,
     /// Vector of fittest individuals. This will change during the simulation as soon as a new
     /// more fittest individual is found and pushed into the first position (index 0).
     pub fittest: Vec<IndividualWrapper<T>>,
     /// Hoe many iteration did the simulation run
-    pub iteration_counter: u32
+    pub iteration_counter: u32,
+    /// The total number of fitness evaluations (calls to `calculate_fitness`) of the simulation
+    pub fitness_evaluations: u64,
+    /// The total number of mutated individuals (each one is mutated as often as its mutation
+    /// rate says).
+    pub total_mutations: u64,
+    /// The number of mutated individuals that are fitter than their parent. The ratio
+    /// `successful_mutations / total_mutations` shows if the mutation operator still helps.
+    pub successful_mutations: u64,
+    /// The total number of crossover operations, see `PopulationBuilder::crossover`.
+    pub total_crossovers: u64,
+    /// The total number of offspring changed by `Individual::repair`, see
+    /// `PopulationBuilder::enable_repair`.
+    pub total_repairs: u64,
+    /// How often each operator of the mutators was applied and how often it improved an
+    /// individual (summed up over all populations by name), see `RandomOperator`.
+    pub operator_stats: Vec<OperatorStat>,
+    /// The total number of individuals selected to survive an iteration or to be a parent.
+    pub total_selections: u64,
+    /// The fitness of all the individuals of all populations after the last iteration, see
+    /// `fitness_histogram`.
+    pub final_fitness: Vec<f64>,
+    /// The fitness of the fittest individual after each iteration, see `convergence_rate`.
+    pub fitness_history: Vec<f64>,
+    /// The share of the individuals of all populations (0.0 - 1.0) that are as fit as the
+    /// fittest individual after each iteration, see `estimated_takeover_time`.
+    pub best_share_history: Vec<f64>,
+    /// All the individuals of each population (population id, individuals) after the last
+    /// iteration, only kept if `SimulationBuilder::keep_final_population` is set.
+    pub final_populations: Vec<(u32, Vec<T>)>,
+    /// The last improvements of the fittest individual, oldest first, only kept if
+    /// `SimulationBuilder::recent_bests` is set. See `recent_bests()`.
+    pub best_snapshots: VecDeque<BestSnapshot<T>>,
+    /// The fitness of the fittest individual before and after the final refinement, only set
+    /// if `SimulationBuilder::final_refinement` is used.
+    pub refinement: Option<(f64, f64)>,
+    /// The best validation fitness and the individual that reached it, only set if the
+    /// individuals implement `Individual::validation_fitness`. It is tracked separately
+    /// from the fittest individuals (the training fitness): if the validation fitness of
+    /// the fittest individual gets worse while its training fitness still improves, the
+    /// simulation is overfitting.
+    pub best_validation: Option<(f64, IndividualWrapper<T>)>,
+    /// The selection pressure of each population (population id, pressure after each
+    /// iteration), only recorded if `SimulationBuilder::auto_tune_selection` is set.
+    /// 1.0 is the configured pressure, lower values mean that the diversity had collapsed.
+    pub selection_pressure_history: Vec<(u32, Vec<f64>)>,
+    /// Each application of the infeasibility recovery (iteration, population id, policy),
+    /// see `SimulationBuilder::infeasibility_recovery`.
+    pub infeasibility_recoveries: Vec<(u32, u32, InfeasibilityRecovery)>,
+    /// Where the time was spent: the wall clock time of the phases of each iteration and
+    /// the time of each population by phase, by mutation operator and for the fitness
+    /// calculation. The population times are summed up over all threads, so they can be
+    /// larger than the wall clock time. See `Profile::to_collapsed` for flame graphs.
+    #[cfg(feature = "profiling")]
+    pub profile: Profile,
+    /// The total run time of the simulation in milliseconds. It is measured with a monotonic
+    /// clock and accumulates over all calls to `run` and `step`, so a simulation that is
+    /// continued later on (even with a saved result) reports the time of all sessions.
+    pub total_time_in_ms: f64
+}
+
+impl<T: Individual + Send + Sync> SimulationResult<T> {
+    /// Returns the last improvements of the fittest individual (at most the capacity given
+    /// to `SimulationBuilder::recent_bests`), the most recent one first.
+    pub fn recent_bests(&self) -> Vec<&BestSnapshot<T>> {
+        self.best_snapshots.iter().rev().collect()
+    }
+
+    /// Compares this result with the result of another simulation run: which one reached
+    /// the better fitness, which one needed fewer fitness evaluations and which one needed
+    /// less time. Since a single run of an evolutionary algorithm could just be lucky, use
+    /// `compare_runs` to compare two sets of runs statistically.
+    pub fn compare(&self, other: &SimulationResult<T>) -> Comparison {
+        Comparison {
+            fitness: better_from_ordering(self.fittest[0].fitness.partial_cmp(&other.fittest[0].fitness)
+                .expect("Fitness of Individual is NaN")),
+            fitness_evaluations: better_from_ordering(self.fitness_evaluations.cmp(&other.fitness_evaluations)),
+            time: better_from_ordering(self.total_time_in_ms.partial_cmp(&other.total_time_in_ms)
+                .expect("Time is NaN")),
+        }
+    }
+
+    /// Returns the history of the current fittest individual (newest record first), empty if
+    /// lineage tracking is disabled (see `PopulationBuilder::track_lineage`).
+    pub fn best_lineage(&self) -> Vec<&Lineage> {
+        match self.fittest.first().and_then(|wrapper| wrapper.lineage.as_ref()) {
+            Some(lineage) => lineage.history(),
+            None => Vec::new()
+        }
+    }
+
+    /// Returns the lineage ids and the fitness trajectories (see `Lineage::fitness_trajectory`)
+    /// of the first `count` fittest individuals, the fittest first. Individuals without a
+    /// lineage are skipped, and the trajectories are empty unless
+    /// `PopulationBuilder::track_individual_history` is set.
+    pub fn fitness_trajectories(&self, count: usize) -> Vec<(u64, Vec<(u32, f64)>)> {
+        self.fittest.iter().take(count)
+            .filter_map(|wrapper| wrapper.lineage.as_ref())
+            .map(|lineage| (lineage.id, lineage.fitness_trajectory()))
+            .collect()
+    }
+
+    /// Counts the fitness values of the final population (all populations) in the given
+    /// number of equally sized bins between the lowest and the highest fitness. Returns the
+    /// lower bound of each bin and its count. A single high bin means that the population
+    /// has converged to one solution, spread out counts mean that it is still diverse.
+    pub fn fitness_histogram(&self, bins: usize) -> Vec<(f64, usize)> {
+        if bins == 0 || self.final_fitness.is_empty() {
+            return Vec::new();
+        }
+
+        let min = self.final_fitness.iter().cloned().fold(f64::MAX, f64::min);
+        let max = self.final_fitness.iter().cloned().fold(f64::MIN, f64::max);
+        let width = (max - min) / (bins as f64);
+
+        let mut histogram: Vec<(f64, usize)> = (0..bins).map(|bin| (min + (bin as f64) * width, 0)).collect();
+
+        for fitness in &self.final_fitness {
+            let bin = if width > 0.0 {
+                (((fitness - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            histogram[bin].1 += 1;
+        }
+
+        histogram
+    }
+
+    /// Returns all the individuals of the population with the given id after the last
+    /// iteration, or None if there is no such population or the populations were not kept
+    /// (see `SimulationBuilder::keep_final_population`).
+    pub fn final_population(&self, population_id: u32) -> Option<&[T]> {
+        self.final_populations.iter()
+            .find(|&&(id, _)| id == population_id)
+            .map(|(_, individuals)| &individuals[..])
+    }
+
+    /// Writes all the kept final populations (see `final_population`) as JSON to the given
+    /// file, as a list of `[population id, [individuals]]` pairs.
+    /// Only available with the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn export_population<P: AsRef<Path>>(&self, path: P) -> io::Result<()> where T: Serialize {
+        let file = File::create(path)?;
+
+        serde_json::to_writer_pretty(file, &self.final_populations).map_err(io::Error::from)
+    }
+
+    /// The average fractional improvement of the fittest fitness per iteration over the last
+    /// `generations` iterations, for example 0.01 means 1% better each iteration. A rate close
+    /// to zero means that it is time to stop or to restart. Returns 0.0 if there are less than
+    /// two iterations in the fitness history.
+    pub fn convergence_rate(&self, generations: usize) -> f64 {
+        let start = self.fitness_history.len().saturating_sub(generations + 1);
+        let improvements: Vec<f64> = self.fitness_history[start..].windows(2)
+            .map(|pair| if pair[0] != 0.0 { (pair[0] - pair[1]) / pair[0].abs() } else { 0.0 })
+            .collect();
+
+        if improvements.is_empty() {
+            0.0
+        } else {
+            mean(&improvements)
+        }
+    }
+
+    /// Estimates the takeover time: the number of iterations the selection alone (without
+    /// new improvements) would need for the copies of the fittest individual to spread from
+    /// one individual to nearly the whole population. A short takeover time means a strong
+    /// selection pressure and a quick loss of diversity.
+    /// The share of the fittest ones (see `best_share_history`) grows logistically, so the
+    /// growth rate of its logit is measured over all iterations in which the fittest fitness
+    /// did not change and the takeover time follows as 2 ln(n - 1) / rate, n is the number of
+    /// individuals. Returns infinity if the fittest ones never spread and NaN if there is not
+    /// enough history (for example with `SimulationBuilder::minimal_memory`).
+    pub fn estimated_takeover_time(&self) -> f64 {
+        let n = self.final_fitness.len() as f64;
+
+        if n < 2.0 {
+            return f64::NAN;
+        }
+
+        let logit = |share: f64| {
+            let share = share.max(0.5 / n).min(1.0 - 0.5 / n);
+            (share / (1.0 - share)).ln()
+        };
+
+        let rates: Vec<f64> = self.best_share_history.windows(2).zip(self.fitness_history.windows(2))
+            .filter(|&(shares, fitness)| fitness[0] == fitness[1] && shares[0] > 0.0 && shares[0] < 1.0)
+            .map(|(shares, _)| logit(shares[1]) - logit(shares[0]))
+            .collect();
+
+        if rates.is_empty() {
+            return f64::NAN;
+        }
+
+        let rate = mean(&rates);
+
+        if rate > 0.0 {
+            2.0 * (n - 1.0).ln() / rate
+        } else {
+            f64::INFINITY
+        }
+    }
 }
 
-/// This implements the two functions `run` and `print_fitness` for the struct `Simulation`.
+/// This implements the functions to run (or step through) the simulation and `print_fitness`
+/// for the struct `Simulation`.
 impl<S: Send + Sync, T: Individual + Send + Sync + Clone> Simulation<S, T> {
     /// This actually runs the simulation.
     /// Depending on the type of simulation (`EndIteration`, `EndFactor` or `EndFitness`)
     /// the iteration loop will check for the stop condition accordingly.
-    pub fn run(&mut self) {
+    /// A population may override the stop condition with its own one, in that case it stops
+    /// independently from the other populations. The simulation finishes when all the
+    /// populations have stopped.
+    /// If a population panics (for example in the user defined `calculate_fitness`), the
+    /// simulation stops after the current iteration and returns `RunError::WorkerPanicked`.
+    /// The simulation result up to that point is still available.
+    pub fn run(&mut self) -> Result<(), RunError> {
         // Initialize timer
         let start_time = Instant::now();
 
-        // Calculate the fitness for all individuals in all populations at the beginning.
+        let mut pool = self.take_pool();
+        let mut result = Ok(());
+
+        self.initialize();
+
+        #[cfg(feature = "indicatif")]
+        let progress_bar = self.make_progress_bar();
+
+        while !self.finished() {
+            result = self.iterate(&mut pool.1);
+
+            #[cfg(feature = "indicatif")]
+            self.update_progress_bar(&progress_bar);
+
+            #[cfg(feature = "serde")]
+            {
+                if let Some(ref mut autosave) = self.autosave {
+                    autosave.iteration_done(&self.simulation_result.fittest[0]);
+                }
+            }
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.pool = Some(pool);
+
+        #[cfg(feature = "indicatif")]
+        {
+            if let Some(ref progress_bar) = progress_bar {
+                progress_bar.finish();
+            }
+        }
+
+        if result.is_ok() {
+            self.refine_finished();
+        }
+
+        #[cfg(feature = "serde")]
+        {
+            if let Some(ref mut autosave) = self.autosave {
+                autosave.simulation_done(&self.simulation_result.fittest[0]);
+            }
+        }
+
+        self.store_final_populations();
+        self.add_time(start_time);
+
+        result
+    }
+
+    /// Runs just one iteration of the simulation (all populations that have not stopped yet).
+    /// This allows the user to control the simulation step by step, for example to look at
+    /// the fittest individual or to inject new individuals between two steps.
+    /// The stop criteria are checked after each step, use `finished()` to see if the
+    /// simulation is done.
+    pub fn step(&mut self) -> Result<(), RunError> {
+        let start_time = Instant::now();
+        let mut pool = self.take_pool();
+
+        self.initialize();
+        let result = self.iterate(&mut pool.1);
+        self.pool = Some(pool);
+
+        if result.is_ok() {
+            self.refine_finished();
+        }
+
+        self.store_final_populations();
+        self.add_time(start_time);
+
+        result
+    }
+
+    /// Runs as many iterations as fit into the given duration (at least one) and returns the
+    /// number of iterations done. Unlike the `EndTime` stop criterion this only pauses the
+    /// simulation, call it again to continue (for example a game AI that may think 50 ms per
+    /// frame). It returns earlier if the simulation is finished (see `finished()`).
+    pub fn run_for(&mut self, duration: Duration) -> Result<u32, RunError> {
+        let start_time = Instant::now();
+        let mut pool = self.take_pool();
+        let mut iterations = 0;
+        let mut result = Ok(());
+
+        self.initialize();
+
+        while !self.finished() && (iterations == 0 || start_time.elapsed() < duration) {
+            result = self.iterate(&mut pool.1);
+            iterations += 1;
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.pool = Some(pool);
+
+        if result.is_ok() {
+            self.refine_finished();
+        }
+
+        self.store_final_populations();
+        self.add_time(start_time);
+
+        result.map(|_| iterations)
+    }
+
+    /// Returns true if all populations have reached their stop criterion.
+    pub fn finished(&self) -> bool {
+        self.habitat.iter().all(|population| population.stopped)
+    }
+
+    /// Returns a copy of the current fittest individual, or None if the simulation has not
+    /// been started yet. Since it is an owned value the simulation is not borrowed, so the
+    /// user can keep stepping through the simulation.
+    pub fn best_cloned(&self) -> Option<T> {
+        self.simulation_result.fittest.first().map(|wrapper| wrapper.individual.clone())
+    }
+
+    /// Returns the fitness of the current fittest individual, or None if the simulation has
+    /// not been started yet.
+    pub fn best_fitness(&self) -> Option<f64> {
+        self.simulation_result.fittest.first().map(|wrapper| wrapper.fitness)
+    }
+
+    /// Returns the least fit individual of all populations, or None if there are no
+    /// populations. Useful to debug mutation or crossover operators that produce invalid
+    /// individuals.
+    pub fn worst(&self) -> Option<&T> {
+        self.habitat.iter()
+            .filter_map(|population| population.worst_wrapper())
+            .max()
+            .map(|wrapper| &wrapper.individual)
+    }
+
+    /// Returns the objectives of the current fittest individual in lexicographic mode with
+    /// their original sign (maximized objectives are stored negated internally), or None if
+    /// the simulation has not been started yet.
+    pub fn best_objectives(&self) -> Option<Vec<f64>> {
+        self.simulation_result.fittest.first().map(|wrapper| {
+            wrapper.objectives.iter().enumerate()
+                .map(|(index, objective)| match self.objective_goals.get(index) {
+                    Some(goal) => goal.apply(*objective),
+                    None => *objective
+                })
+                .collect()
+        })
+    }
+
+    /// Normalizes the given objectives (with their original sign, like `best_objectives`)
+    /// with the objective bounds, so that each bound range maps to [0.0, 1.0]. Values outside
+    /// the bounds are not clamped. A maximized objective is flipped, so that 0.0 is always the
+    /// best bound. Without objective bounds the objectives are returned unchanged, see
+    /// `SimulationBuilder::objective_bounds`.
+    pub fn normalize_objectives(&self, objectives: &[f64]) -> Vec<f64> {
+        normalize_objectives(objectives, &self.objective_bounds, &self.objective_goals)
+    }
+
+    /// Inserts the given individual into the population with the given id, it replaces the
+    /// least fit individual of that population. This allows the user to suggest solutions
+    /// while the simulation is running (interactive evolution).
+    /// This is not thread safe with respect to a running simulation: only call it between
+    /// two calls of `step()`, not during `run()`.
+    pub fn inject(&mut self, population_id: u32, individual: T) -> Result<(), RunError> {
+        self.initialize();
+
+        let generation = self.simulation_result.iteration_counter;
+
+        match self.habitat.iter_mut().find(|population| population.id == population_id) {
+            Some(population) => {
+                population.inject(individual, generation);
+                Ok(())
+            }
+            None => Err(RunError::UnknownPopulation { population_id })
+        }
+    }
+
+    /// Changes the mutation rates (number of mutation runs) of the individuals of the given
+    /// population between two steps, see `PopulationBuilder::mutation_rate`. The number of
+    /// rates must be equal to the current number of individuals. This allows an external
+    /// controller to adapt the mutation rates online.
+    pub fn set_mutation_rate(&mut self, population_id: u32, mutation_rate: Vec<u32>) -> Result<(), RunError> {
+        match self.habitat.iter_mut().find(|population| population.id == population_id) {
+            Some(population) => {
+                if population.population.len() != mutation_rate.len() {
+                    return Err(RunError::MutationRateMismatch {
+                        population_id,
+                        expected: population.population.len(),
+                        given: mutation_rate.len()
+                    });
+                }
+
+                for (wrapper, mutation_rate) in population.population.iter_mut().zip(mutation_rate) {
+                    wrapper.num_of_mutations = mutation_rate;
+                }
+
+                Ok(())
+            }
+            None => Err(RunError::UnknownPopulation { population_id })
+        }
+    }
+
+    /// Returns the mutation seed of each population by population id, to save it with a
+    /// checkpoint and resume bit-exact later on (see `set_mutation_seeds`). The framework
+    /// doesn't keep running generators whose state would have to be saved: the random numbers
+    /// of each iteration are derived from the mutation seed of the population (see
+    /// `PopulationBuilder::mutation_seed`), `SimulationBuilder::rng_stream`, the iteration
+    /// counter and the index of the individual. So the seeds, the populations and the
+    /// simulation result (with the iteration counter) are the whole checkpoint.
+    /// A population without a mutation seed uses unseeded generators, its random numbers can't
+    /// be saved and will be different after a resume. The same is true for an individual that
+    /// uses `rand::thread_rng` itself (see `Individual::mutate_with_rng`).
+    pub fn mutation_seeds(&self) -> Vec<(u32, Option<u64>)> {
+        self.habitat.iter()
+            .map(|population| (population.id, population.mutation_seed))
+            .collect()
+    }
+
+    /// Sets the mutation seed of the populations with the given ids, for example the ones
+    /// saved with `mutation_seeds`. The populations and the simulation result must be restored
+    /// separately (for example from clones). Returns `RunError::UnknownPopulation` if a
+    /// population is not in the habitat, nothing is changed then.
+    pub fn set_mutation_seeds(&mut self, mutation_seeds: &[(u32, Option<u64>)]) -> Result<(), RunError> {
+        let mut indices = Vec::with_capacity(mutation_seeds.len());
+
+        for &(population_id, _) in mutation_seeds {
+            match self.habitat.iter().position(|population| population.id == population_id) {
+                Some(index) => indices.push(index),
+                None => return Err(RunError::UnknownPopulation { population_id })
+            }
+        }
+
+        for (index, &(_, mutation_seed)) in indices.into_iter().zip(mutation_seeds) {
+            self.habitat[index].mutation_seed = mutation_seed;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the cancel flag of the simulation. Setting it (for example from a Ctrl-C
+    /// handler or another thread) stops the simulation after the current iteration with
+    /// `RunError::Cancelled`, all populations are marked as stopped. Long running fitness
+    /// calculations can check it to return early, see
+    /// `Individual::calculate_fitness_cancellable`.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    /// Returns the number of threads used for the next `run()` or `step()`: `num_of_threads`,
+    /// or one thread per `individuals_per_thread` individuals of all populations (at least
+    /// one and at most the number of available cores).
+    pub fn thread_count(&self) -> usize {
+        match self.individuals_per_thread {
+            Some(individuals_per_thread) => {
+                let individuals: usize = self.habitat.iter().map(|population| population.population.len()).sum();
+                let cores = thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1);
+
+                ((individuals + individuals_per_thread - 1) / individuals_per_thread.max(1)).max(1).min(cores)
+            }
+            None => self.num_of_threads
+        }
+    }
+
+    /// Takes the thread pool out of the simulation (so that it can be used while the
+    /// simulation is borrowed), it is created if there is none yet or if the number of
+    /// threads has changed. Put it back when the iterations are done.
+    fn take_pool(&mut self) -> (usize, Pool) {
+        let threads = self.thread_count();
+
+        match self.pool.take() {
+            Some((pool_threads, pool)) if pool_threads == threads => (threads, pool),
+            _ => (threads, make_pool(threads).unwrap())
+        }
+    }
+
+    /// Changes the number of threads between two steps, it must be at least 1.
+    /// This turns off `SimulationBuilder::threads_per_individuals`.
+    pub fn set_threads(&mut self, threads: usize) -> Result<(), RunError> {
+        if threads == 0 {
+            return Err(RunError::ThreadsTooLow);
+        }
+
+        self.num_of_threads = threads;
+        self.individuals_per_thread = None;
+        Ok(())
+    }
+
+    /// Calculates the fitness of the given individual the same way the simulation does it
+    /// (same generation, number of fitness samples and objectives as the first population),
+    /// without changing the simulation. Useful to score a baseline solution or to check the
+    /// final result under identical conditions.
+    pub fn evaluate(&self, individual: &T) -> f64 {
+        self.new_evaluator().evaluate_individual(individual)
+    }
+
+    /// Creates a fitness evaluator with the settings of the first population for the
+    /// current generation.
+    fn new_evaluator(&self) -> FitnessEvaluator {
+        let generation = self.simulation_result.iteration_counter;
+
+        match self.habitat.first() {
+            Some(population) => population.new_evaluator(generation),
+            None => FitnessEvaluator::new(generation, 1, self.num_of_objectives,
+                Arc::new(self.objective_goals.clone()))
+        }
+    }
+
+    /// Runs the final refinement once all populations have stopped, no matter if the
+    /// simulation was driven by `run()`, `step()` or `run_for()`. It is done only once.
+    fn refine_finished(&mut self) {
+        if self.final_refinement > 0 && self.finished() && self.simulation_result.refinement.is_none() {
+            self.refine_fittest();
+        }
+    }
+
+    /// Runs the final refinement on the fittest individual, see
+    /// `SimulationBuilder::final_refinement`. A refined individual that is fitter becomes the
+    /// new fittest individual.
+    fn refine_fittest(&mut self) {
+        let mut evaluator = self.new_evaluator();
+        let mut best = self.simulation_result.fittest[0].clone();
+        let fitness_before = best.fitness;
+
+        for _ in 0..self.final_refinement {
+            let mut candidate = best.clone();
+            candidate.individual.local_search();
+            candidate.dirty = true;
+            evaluator.evaluate(&mut candidate);
+
+            if candidate < best {
+                best = candidate;
+            }
+        }
+
+        self.simulation_result.fitness_evaluations += evaluator.fitness_evaluations();
+        self.simulation_result.refinement = Some((fitness_before, best.fitness));
+
+        if best.fitness < fitness_before {
+            log_debug!("refined fittest: {} -> {}", fitness_before, best.fitness);
+            self.simulation_result.fittest.insert(0, best);
+        }
+    }
+
+    /// Pools all the populations of the given (finished) simulations and keeps the
+    /// `num_of_individuals` fittest individuals overall, see `Population::merge`. The result
+    /// has the settings and the id of the first population. Add it to a new simulation
+    /// (`SimulationBuilder::add_population`) to refine the best solutions of a broad search.
+    /// Returns None if there are no populations.
+    pub fn pool_populations(simulations: Vec<Simulation<S, T>>, num_of_individuals: usize)
+            -> Option<Population<S, T>> {
+        let mut populations = simulations.into_iter().flat_map(|simulation| simulation.habitat);
+
+        populations.next().map(|first| {
+            let mut pooled = populations.fold(first, Population::merge);
+            pooled.population.truncate(num_of_individuals.max(1));
+            pooled.num_of_individuals = pooled.population.len() as u32;
+            pooled
+        })
+    }
+
+    /// Copies all the individuals into the simulation result, if they should be kept.
+    fn store_final_populations(&mut self) {
+        if self.keep_final_population {
+            self.simulation_result.final_populations = self.habitat.iter()
+                .map(|population| (population.id,
+                    population.population.iter().map(|wrapper| wrapper.individual.clone()).collect()))
+                .collect();
+        }
+    }
+
+    /// Adds the time since `start_time` to the total run time.
+    fn add_time(&mut self, start_time: Instant) {
+        let elapsed = start_time.elapsed();
+
+        self.simulation_result.total_time_in_ms += elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1000_000.0;
+    }
+
+    /// Logs the fitness of the fittest individual and its description, if any.
+    fn log_best(&self) {
+        let fittest = &self.simulation_result.fittest[0];
+
+        match fittest.individual.describe() {
+            Some(description) => log_info!("{}: best fitness: {}, {}",
+                self.simulation_result.iteration_counter, fittest.fitness, description),
+            None => log_info!("{}: best fitness: {}", self.simulation_result.iteration_counter, fittest.fitness)
+        }
+    }
+
+    /// Calculates the fitness for all individuals in all populations at the beginning and
+    /// initializes the simulation result. Does nothing if the simulation was already
+    /// initialized.
+    fn initialize(&mut self) {
+        if !self.simulation_result.fittest.is_empty() {
+            return;
+        }
+
+        // Evaluate the unmutated individuals created by the population builder, each
+        // population is sorted afterwards.
+        let mut fitness_evaluations = 0;
         for population in &mut self.habitat {
-            population.calculate_fitness();
+            #[cfg(feature = "async")]
+            {
+                if let (Some(max_concurrent), 0) = (self.max_concurrent_evals, self.num_of_objectives) {
+                    fitness_evaluations += population.calculate_fitness_async(max_concurrent, &self.cancel);
+                    continue;
+                }
+            }
+
+            fitness_evaluations += population.calculate_fitness();
         }
 
-        let mut iteration_counter = 0;
-        let mut pool = make_pool(self.num_of_threads).unwrap();
+        let fittest = self.habitat.iter()
+            .map(|population| &population.population[0])
+            .min()
+            .expect("The habitat is empty")
+            .clone();
 
         // Initialize:
-        // - The fittest individual.
+        // - The fittest individual of all populations.
         // - The fitness at the beginning of the simulation. This is uesed to calculate the
         //   overall improvement later on.
-        let simulation_result = SimulationResult {
-            improvement_factor: 0.0,
-            original_fitness: self.habitat[0].population[0].fitness,
-            fittest: vec![self.habitat[0].population[0].clone()],
-            iteration_counter: 0
+        self.simulation_result = SimulationResult {
+            improvement_factor: 1.0,
+            normalized_improvement: 0.0,
+            original_fitness: fittest.fitness,
+            fittest: vec![fittest],
+            iteration_counter: 0,
+            fitness_evaluations,
+            total_mutations: 0,
+            successful_mutations: 0,
+            total_crossovers: 0,
+            total_repairs: 0,
+            #[cfg(feature = "profiling")]
+            profile: Profile::new(),
+            operator_stats: Vec::new(),
+            best_snapshots: VecDeque::new(),
+            refinement: None,
+            best_validation: None,
+            selection_pressure_history: Vec::new(),
+            infeasibility_recoveries: Vec::new(),
+            total_selections: 0,
+            final_fitness: Vec::new(),
+            fitness_history: Vec::new(),
+            best_share_history: Vec::new(),
+            final_populations: Vec::new(),
+            total_time_in_ms: 0.0
         };
 
-        println!("original_fitness: {}", simulation_result.original_fitness);
+        println!("original_fitness: {}", self.simulation_result.original_fitness);
 
-        let simulation_result_mutex = Mutex::new(simulation_result);
+        for population in &mut self.habitat {
+            population.original_fitness = population.population[0].fitness;
+            population.stopped = false;
+        }
 
-        // Check which type of simulation to run.
-        match self.type_of_simulation {
-            SimulationType::EndIteration(end_iteration) => {
-                for iteration_counter in 0..end_iteration {
-                    (&mut self.habitat).into_split_iter().for_each(
-                        &pool.spawner(), |population| {
-                            population.run_body(&simulation_result_mutex, iteration_counter);
-                        });
-                };
-                match simulation_result_mutex.lock() {
-                    Ok(simulation_result) => {
-                        self.simulation_result = (*simulation_result).clone();
-                        self.simulation_result.iteration_counter = end_iteration;
-                    },
-                    Err(e) => println!("Mutex (poison) error (simulation_result): {}", e)
-                }
-            }
-            SimulationType::EndFactor(end_factor) => {
-                loop {
-                    match simulation_result_mutex.lock() {
-                        Ok(simulation_result) => {
-                            if simulation_result.improvement_factor <= end_factor {
-                                break;
-                            }
-                        },
-                        Err(e) => println!("Mutex (poison) error (simulation_result): {}", e)
-                    }
+        self.update_stopped();
+    }
 
-                    iteration_counter += 1;
-                    (&mut self.habitat).into_split_iter().for_each(
-                        &pool.spawner(), |population| {
-                            population.run_body(&simulation_result_mutex, iteration_counter);
-                        });
-                };
-                match simulation_result_mutex.lock() {
-                    Ok(simulation_result) => {
-                        self.simulation_result = (*simulation_result).clone();
-                        self.simulation_result.iteration_counter = iteration_counter;
-                    },
-                    Err(e) => println!("Mutex (poison) error (simulation_result): {}", e)
-                }
-            }
-            SimulationType::EndFitness(end_fitness) => {
-                loop {
-                    match simulation_result_mutex.lock() {
-                        Ok(simulation_result) => {
-                            if simulation_result.fittest[0].fitness <= end_fitness {
-                                break;
-                            }
-                        },
-                        Err(e) => println!("Mutex (poison) error (simulation_result): {}", e)
-                    }
+    /// Checks the stop criteria: populations with their own stop criterion stop
+    /// independently, all the others use the one of the simulation.
+    fn update_stopped(&mut self) {
+        let iteration_counter = self.simulation_result.iteration_counter;
+        let tolerance = self.tolerance;
+        let fittest = self.simulation_result.fittest[0].fitness;
+        let target_optimum = self.target_optimum;
+        let original_fitness = self.simulation_result.original_fitness;
+        let simulation_reached = self.type_of_simulation.reached(iteration_counter, fittest, original_fitness,
+            target_optimum, tolerance, &mut self.fitness_history);
 
-                    iteration_counter += 1;
-                    (&mut self.habitat).into_split_iter().for_each(
-                        &pool.spawner(), |population| {
-                            population.run_body(&simulation_result_mutex, iteration_counter);
-                        });
-                };
-                match simulation_result_mutex.lock() {
-                    Ok(simulation_result) => {
-                        self.simulation_result = (*simulation_result).clone();
-                        self.simulation_result.iteration_counter = iteration_counter;
-                    },
-                    Err(e) => println!("Mutex (poison) error (simulation_result): {}", e)
-                }
+        for population in &mut self.habitat {
+            if population.stopped {
+                continue;
             }
-        }
 
-        let elapsed = start_time.elapsed();
+            let fitness = population.population[0].fitness;
 
-        self.total_time_in_ms = elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1000_000.0;
+            population.stopped = match population.stop_criterion {
+                Some(ref stop_criterion) => stop_criterion.reached(iteration_counter, fitness,
+                    population.original_fitness, target_optimum, tolerance, &mut population.fitness_history),
+                None => simulation_reached
+            };
 
+            if population.stopped {
+                log_info!("{}: population stopped, id: {}", iteration_counter, population.id);
+            }
+        }
     }
 
-    /// This is a helper function that the user can call after the simulation stops in order to
-    /// see all the fitness values for all the individuals that participated to the overall
-    /// improvement.
-    pub fn print_fitness(&self) {
-        for wrapper in &self.simulation_result.fittest {
-            println!("fitness: {}, num_of_mutations: {}, population: {}",
-                     wrapper.fitness, wrapper.num_of_mutations, wrapper.id);
+    /// Creates the progress bar if the user enabled it: a bar for `EndIteration` and a
+    /// spinner for all the other stop criteria.
+    #[cfg(feature = "indicatif")]
+    fn make_progress_bar(&self) -> Option<ProgressBar> {
+        if !self.progress_bar {
+            return None;
         }
+
+        match self.type_of_simulation {
+            SimulationType::EndIteration(end_iteration) => {
+                let progress_bar = ProgressBar::new(end_iteration as u64);
+                if let Ok(style) = ProgressStyle::with_template("{bar:40} {pos}/{len} {msg}") {
+                    progress_bar.set_style(style);
+                }
+                Some(progress_bar)
+            }
+            _ => Some(ProgressBar::new_spinner())
+        }
+    }
+
+    /// Shows the current iteration and the current fittest fitness.
+    #[cfg(feature = "indicatif")]
+    fn update_progress_bar(&self, progress_bar: &Option<ProgressBar>) {
+        if let Some(ref progress_bar) = *progress_bar {
+            progress_bar.set_position(self.simulation_result.iteration_counter as u64);
+            progress_bar.set_message(format!("fittest: {}", self.simulation_result.fittest[0].fitness));
+        }
+    }
+
+    /// Runs the three phases of one iteration (see `Population::run_body`) for all populations
+    /// that have not stopped yet, or only for the one with the given index, and updates the
+    /// simulation result.
+    fn evolve(&mut self, pool: &mut Pool, only: Option<usize>, panicked_mutex: &Mutex<Option<u32>>) {
+        #[cfg(feature = "profiling")]
+        let start = Instant::now();
+
+        let iteration_counter = self.simulation_result.iteration_counter;
+        let mut evaluators: Vec<Option<FitnessEvaluator>> = self.habitat.iter().map(|_| None).collect();
+        let active: Vec<bool> = self.habitat.iter().enumerate()
+            .map(|(index, population)| !population.stopped && only.map_or(true, |only| only == index))
+            .collect();
+
+        // Stopped populations are frozen, but their fittest individuals are still part
+        // of the simulation result.
+        // A panic must not tear down the worker threads, catch it and report it.
+        (&mut self.habitat).into_split_iter().zip((&mut evaluators).into_split_iter()).zip((&active).into_split_iter())
+            .for_each(&pool.spawner(), |((population, evaluator), active)| {
+                if *active {
+                    let id = population.id;
+                    catch_panic(id, panicked_mutex, || {
+                        #[cfg(feature = "profiling")]
+                        let start = Instant::now();
+
+                        *evaluator = Some(population.prepare_iteration(iteration_counter));
+
+                        #[cfg(feature = "profiling")]
+                        if let Some(ref mut evaluator) = *evaluator {
+                            evaluator.add_profile(&["prepare"], elapsed_nanos(start));
+                        }
+                    });
+                }
+            });
+
+        #[cfg(feature = "profiling")]
+        let start = self.add_profile(&["iteration", "prepare"], start);
+
+        if let Some(ref mut on_reset) = self.on_reset {
+            for (population, evaluator) in self.habitat.iter().zip(&evaluators) {
+                if evaluator.is_some() && population.stats.reset {
+                    on_reset(population.id as usize, iteration_counter);
+                }
+            }
+        }
+
+        if self.events.is_some() {
+            for (population, evaluator) in self.habitat.iter().zip(&evaluators) {
+                if let (&Some(_), Some(policy)) = (evaluator, population.stats.infeasibility_recovery) {
+                    self.send_event(SimulationEvent::InfeasibilityRecovery {
+                        population_id: population.id,
+                        iteration: iteration_counter,
+                        policy,
+                    });
+                }
+            }
+        }
+
+        {
+            let mut jobs = Vec::new();
+
+            for (index, population) in self.habitat.iter_mut().enumerate() {
+                if evaluators[index].is_some() {
+                    let population_id = population.id;
+                    let evaluator = population.new_evaluator(iteration_counter);
+                    let mutator = population.mutator.as_deref();
+                    let mutation_start = population.mutation_start;
+                    let cpu_affinity = if cfg!(feature = "core_affinity") { population.cpu_affinity } else { None };
+
+                    for (individual, wrapper) in population.population.iter_mut().enumerate().skip(mutation_start) {
+                        let mut evaluator = evaluator.clone();
+                        evaluator.seed_rng(individual, RNG_STREAM_MUTATION);
+
+                        jobs.push(MutationJob {
+                            population_index: index,
+                            population_id,
+                            wrapper,
+                            mutator,
+                            evaluator,
+                            cpu_affinity,
+                        });
+                    }
+                }
+            }
+
+            #[cfg(feature = "async")]
+            let evaluated_async = match self.max_concurrent_evals {
+                Some(max_concurrent) if self.num_of_objectives == 0 => {
+                    let mut operators: Vec<Vec<usize>> = jobs.iter().map(|_| Vec::new()).collect();
+                    let parent_fitness: Vec<f64> = jobs.iter().map(|job| job.wrapper.fitness).collect();
+
+                    (&mut jobs).into_split_iter().zip((&mut operators).into_split_iter()).for_each(
+                        &pool.spawner(), |(job, operators)| {
+                            let id = job.population_id;
+                            catch_panic(id, panicked_mutex, || {
+                                *operators = mutate_offspring_feasible(job.wrapper, job.mutator, &mut job.evaluator);
+                            });
+                        });
+
+                    let population_ids: Vec<u32> = jobs.iter().map(|job| job.population_id).collect();
+                    let mut pairs: Vec<_> = jobs.iter_mut()
+                        .map(|job| (&mut *job.wrapper, &mut job.evaluator))
+                        .collect();
+
+                    for index in evaluate_async(&mut pairs, max_concurrent, &self.cancel) {
+                        set_panicked(population_ids[index], panicked_mutex);
+                    }
+
+                    for ((job, operators), parent_fitness) in jobs.iter_mut().zip(&operators).zip(parent_fitness) {
+                        job.evaluator.count_mutation(parent_fitness, job.wrapper.fitness);
+                        job.evaluator.count_operators(operators, parent_fitness, job.wrapper.fitness);
+                    }
+
+                    true
+                }
+                _ => false
+            };
+            #[cfg(not(feature = "async"))]
+            let evaluated_async = false;
+
+            match self.executor {
+                _ if evaluated_async => {}
+                Some(ref executor) => {
+                    let tasks = jobs.iter_mut()
+                        .map(|job| Box::new(move || {
+                            let id = job.population_id;
+                            catch_panic(id, panicked_mutex, || {
+                                mutate_and_evaluate_feasible(job.wrapper, job.mutator, &mut job.evaluator);
+                            });
+                        }) as Box<dyn FnOnce() + Send>)
+                        .collect();
+
+                    executor(tasks);
+                }
+                None => {
+                    // The jobs of a pinned population run on their own thread (the jobs of a
+                    // population are next to each other), all the others share the pool
+                    let (pinned, mut shared): (Vec<&mut MutationJob<T>>, Vec<&mut MutationJob<T>>) = jobs.iter_mut()
+                        .partition(|job| job.cpu_affinity.is_some());
+                    let mut pinned_populations: Vec<Vec<&mut MutationJob<T>>> = Vec::new();
+
+                    for job in pinned {
+                        let same_population = pinned_populations.last()
+                            .map_or(false, |population_jobs| population_jobs[0].population_index == job.population_index);
+
+                        if same_population {
+                            pinned_populations.last_mut().unwrap().push(job);
+                        } else {
+                            pinned_populations.push(vec![job]);
+                        }
+                    }
+
+                    thread::scope(|scope| {
+                        for population_jobs in pinned_populations {
+                            scope.spawn(move || {
+                                if let Some(core) = population_jobs[0].cpu_affinity {
+                                    pin_to_core(core);
+                                }
+
+                                for job in population_jobs {
+                                    let id = job.population_id;
+                                    catch_panic(id, panicked_mutex, || {
+                                        mutate_and_evaluate_feasible(job.wrapper, job.mutator, &mut job.evaluator);
+                                    });
+                                }
+                            });
+                        }
+
+                        (&mut shared).into_split_iter().for_each(
+                            &pool.spawner(), |job| {
+                                let id = job.population_id;
+                                catch_panic(id, panicked_mutex, || {
+                                    mutate_and_evaluate_feasible(job.wrapper, job.mutator, &mut job.evaluator);
+                                });
+                            });
+                    });
+                }
+            }
+
+            for job in &jobs {
+                if let Some(ref mut evaluator) = evaluators[job.population_index] {
+                    evaluator.merge(&job.evaluator);
+                }
+            }
+        }
+
+        #[cfg(feature = "profiling")]
+        let start = self.add_profile(&["iteration", "mutate and evaluate"], start);
+
+        (&mut self.habitat).into_split_iter().zip((&mut evaluators).into_split_iter()).for_each(
+            &pool.spawner(), |(population, evaluator)| {
+                if let Some(finished) = evaluator.take() {
+                    let id = population.id;
+                    catch_panic(id, panicked_mutex, || {
+                        #[cfg(feature = "profiling")]
+                        let start = Instant::now();
+
+                        *evaluator = Some(population.finish_population(finished));
+
+                        #[cfg(feature = "profiling")]
+                        if let Some(ref mut evaluator) = *evaluator {
+                            evaluator.add_profile(&["finish"], elapsed_nanos(start));
+                        }
+                    });
+                }
+            });
+
+        #[cfg(feature = "profiling")]
+        let start = self.add_profile(&["iteration", "finish"], start);
+
+        // The simulation result is updated after all threads are done, always in the order of
+        // the population ids. Otherwise the order in which the threads finish would decide
+        // which one of two equally fit individuals becomes the fittest one.
+        let mut order: Vec<usize> = (0..self.habitat.len()).collect();
+        order.sort_by_key(|index| self.habitat[*index].id);
+
+        for index in order {
+            if let Some(ref evaluator) = evaluators[index] {
+                self.habitat[index].update_result(evaluator, &mut self.simulation_result, iteration_counter);
+            }
+        }
+
+        #[cfg(feature = "profiling")]
+        self.add_profile(&["iteration", "update result"], start);
+    }
+
+    /// Adds the time since `start` to the wall clock timings of the simulation and returns
+    /// the current time as the start of the next measurement.
+    #[cfg(feature = "profiling")]
+    fn add_profile(&mut self, path: &[&str], start: Instant) -> Instant {
+        self.simulation_result.profile.add(path, elapsed_nanos(start));
+        Instant::now()
+    }
+
+    /// Returns a snapshot of the current fittest individual, with a copy of it if
+    /// `with_individual` is true.
+    fn best_snapshot(&self, with_individual: bool) -> BestSnapshot<T> {
+        let fittest = &self.simulation_result.fittest[0];
+
+        BestSnapshot {
+            iteration: self.simulation_result.iteration_counter,
+            fitness: fittest.fitness,
+            population_id: fittest.id,
+            individual: if with_individual { Some(fittest.individual.clone()) } else { None },
+        }
+    }
+
+    /// Adds a snapshot of the current fittest individual to the ring buffer of the recent
+    /// bests and removes the oldest one if the buffer is full.
+    fn push_best_snapshot(&mut self) {
+        let snapshot = self.best_snapshot(self.recent_bests_individuals);
+
+        if self.simulation_result.best_snapshots.len() >= self.recent_bests_capacity {
+            self.simulation_result.best_snapshots.pop_front();
+        }
+
+        self.simulation_result.best_snapshots.push_back(snapshot);
+    }
+
+    /// Sends the given event to the event channel (if any). A closed channel is ignored, so
+    /// the receiver may stop listening at any time.
+    fn send_event(&self, event: SimulationEvent<T>) {
+        if let Some(ref events) = self.events {
+            let _ = events.send(event);
+        }
+    }
+
+    /// Runs one iteration for all populations that have not stopped yet, using the given
+    /// thread pool. The iteration is split into three phases, see `Population::run_body`.
+    /// The expensive middle phase (mutation and fitness calculation) is done for all the
+    /// individuals of all populations together, so that the work stealing thread pool can
+    /// balance the work even if the populations have very different sizes.
+    fn iterate(&mut self, pool: &mut Pool) -> Result<(), RunError> {
+        #[cfg(feature = "profiling")]
+        let iteration_start = Instant::now();
+
+        let panicked_mutex = Mutex::new(None);
+        let best_fitness = self.simulation_result.fittest[0].fitness;
+
+        if self.sequential_populations {
+            for index in 0..self.habitat.len() {
+                self.evolve(pool, Some(index), &panicked_mutex);
+                // Free the buffers of the individuals that died, so that only one population
+                // at a time needs them
+                self.habitat[index].spare = Vec::new();
+            }
+        } else {
+            self.evolve(pool, None, &panicked_mutex);
+        }
+
+        if let Some(optimum) = self.target_optimum {
+            let range = self.simulation_result.original_fitness - optimum;
+            let improvement = self.simulation_result.original_fitness - self.simulation_result.fittest[0].fitness;
+
+            self.simulation_result.normalized_improvement = if range > 0.0 {
+                (improvement / range).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+        }
+
+        if self.simulation_result.fittest[0].fitness < best_fitness {
+            if self.recent_bests_capacity > 0 {
+                self.push_best_snapshot();
+            }
+
+            if self.events.is_some() {
+                let snapshot = self.best_snapshot(self.events_individuals);
+                self.send_event(SimulationEvent::NewBest(snapshot));
+            }
+        }
+
+        self.simulation_result.iteration_counter += 1;
+        if self.minimal_memory {
+            self.simulation_result.fittest.truncate(1);
+        } else {
+            let fittest = self.simulation_result.fittest[0].fitness;
+            self.simulation_result.fitness_history.push(fittest);
+            self.simulation_result.final_fitness = self.habitat.iter()
+                .flat_map(|population| population.population.iter().map(|wrapper| wrapper.fitness))
+                .collect();
+
+            let final_fitness = &self.simulation_result.final_fitness;
+            let best_copies = final_fitness.iter().filter(|fitness| **fitness == fittest).count();
+            self.simulation_result.best_share_history.push(best_copies as f64 / final_fitness.len().max(1) as f64);
+        }
+
+        if self.migration_interval > 0 && !self.sequential_populations &&
+            self.simulation_result.iteration_counter % self.migration_interval == 0 {
+            #[cfg(feature = "profiling")]
+            let start = Instant::now();
+
+            self.migrate();
+
+            #[cfg(feature = "profiling")]
+            self.add_profile(&["iteration", "migration"], start);
+        }
+
+        self.update_stopped();
+
+        if let Some(interval) = self.log_best_every {
+            if self.simulation_result.iteration_counter % interval == 0 {
+                self.log_best();
+            }
+        }
+
+        if let Some(stagnation) = self.prune_stagnation {
+            self.prune_stalled(stagnation);
+        }
+
+        #[cfg(feature = "profiling")]
+        self.add_profile(&["iteration"], iteration_start);
+
+        let panicked = match panicked_mutex.lock() {
+            Ok(panicked) => *panicked,
+            Err(e) => {
+                println!("Mutex (poison) error (panicked): {}", e);
+                None
+            }
+        };
+
+        match panicked {
+            Some(population_id) => Err(RunError::WorkerPanicked { population_id }),
+            None if self.cancel.load(Ordering::SeqCst) => {
+                for population in &mut self.habitat {
+                    population.stopped = true;
+                }
+
+                Err(RunError::Cancelled)
+            }
+            None => Ok(())
+        }
+    }
+
+    /// Returns the migration edges as indices into the habitat: the user defined topology or
+    /// a ring (each population sends to the next one, the last one to the first one).
+    fn migration_edges(&self) -> Vec<(usize, usize)> {
+        let num_of_populations = self.habitat.len();
+
+        match self.migration_topology {
+            Some(ref topology) => {
+                let index_of = |id: usize| self.habitat.iter().position(|population| population.id as usize == id);
+
+                topology.iter()
+                    .filter_map(|&(source, destination)| match (index_of(source), index_of(destination)) {
+                        (Some(source), Some(destination)) => Some((source, destination)),
+                        _ => None
+                    })
+                    .collect()
+            }
+            None if num_of_populations > 1 => {
+                (0..num_of_populations).map(|index| (index, (index + 1) % num_of_populations)).collect()
+            }
+            None => Vec::new()
+        }
+    }
+
+    /// Sends a copy of the fittest individual of each source population along all the
+    /// migration edges, it replaces the least fit individual of the destination population.
+    /// All migrants are taken before any of them is inserted. Stopped populations still send
+    /// migrants but do not receive any.
+    fn migrate(&mut self) {
+        let generation = self.simulation_result.iteration_counter;
+
+        let emigrants = self.emigrants();
+        if self.migration_adaptive {
+            self.record_migration_fitness();
+        }
+
+        let migrants: Vec<(usize, IndividualWrapper<T>)> = self.migration_edges().into_iter()
+            .filter(|&(source, destination)| source != destination && !self.habitat[destination].stopped)
+            .flat_map(|(source, destination)| {
+                self.habitat[source].population.iter().take(emigrants[source])
+                    .map(move |wrapper| (destination, wrapper.clone()))
+            })
+            .collect();
+
+        for (destination, migrant) in migrants {
+            self.habitat[destination].immigrate(migrant, generation);
+        }
+    }
+
+    /// Returns the number of fittest individuals each population sends along each of its
+    /// migration edges. Without adaptive migration this is always one. With adaptive migration
+    /// a population that improved as much as the average since the last migration sends one,
+    /// twice as much sends two and a stagnant one sends none (at most half of the population).
+    /// If no population improved all send one. The improvement is measured since the last
+    /// call of `record_migration_fitness`.
+    fn emigrants(&self) -> Vec<usize> {
+        if !self.migration_adaptive {
+            return vec![1; self.habitat.len()];
+        }
+
+        let improvements: Vec<f64> = self.habitat.iter()
+            .map(|population| if population.migration_fitness == f64::MAX {
+                0.0
+            } else {
+                (population.migration_fitness - population.population[0].fitness).max(0.0)
+            })
+            .collect();
+        let mean_improvement = mean(&improvements);
+
+        if mean_improvement <= 0.0 || mean_improvement.is_nan() {
+            return vec![1; self.habitat.len()];
+        }
+
+        improvements.iter().zip(&self.habitat)
+            .map(|(improvement, population)| {
+                ((improvement / mean_improvement).round() as usize).min((population.population.len() / 2).max(1))
+            })
+            .collect()
+    }
+
+    /// Stores the current fitness of the fittest individual of each population, the adaptive
+    /// migration measures the improvement from there.
+    fn record_migration_fitness(&mut self) {
+        for population in &mut self.habitat {
+            population.migration_fitness = population.population[0].fitness;
+        }
+    }
+
+    /// Removes the populations that have stalled for the given number of iterations, except
+    /// the one with the fittest individual. The global fittest individual is already stored
+    /// in the simulation result at this point, so it is never lost.
+    fn prune_stalled(&mut self, stagnation: u32) {
+        let fittest_index = (0..self.habitat.len())
+            .min_by(|a, b| self.habitat[*a].population[0].cmp(&self.habitat[*b].population[0]));
+        let iteration_counter = self.simulation_result.iteration_counter;
+        let mut index = 0;
+
+        self.habitat.retain(|population| {
+            let prune = Some(index) != fittest_index && population.stalled_iterations >= stagnation;
+            index += 1;
+
+            if prune {
+                log_info!("{}: pruned stalled population, id: {}", iteration_counter, population.id);
+            }
+
+            !prune
+        });
+    }
+
+    /// This is a helper function that the user can call after the simulation stops in order to
+    /// see all the fitness values for all the individuals that participated to the overall
+    /// improvement.
+    pub fn print_fitness(&self) {
+        for wrapper in &self.simulation_result.fittest {
+            println!("fitness: {}, num_of_mutations: {}, population: {}",
+                     wrapper.fitness, wrapper.num_of_mutations, wrapper.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use benchmarks::{Sphere, OneMax};
+    use individual::{Individual, IndividualWrapper, FitnessContext, FitnessGoal, Lineage, Origin};
+    use jobsteal::make_pool;
+    use operators::{Crossover, Mutator, MutationContext, Permutation, RandomOperator};
+    use population::{Aggregator, FitnessEvaluator, InfeasibilityRecovery, Neighborhood, Population, SelectionStrategy,
+        TieBreak, mutate_and_evaluate};
+    #[cfg(feature = "profiling")]
+    use profiling::Profile;
+    use rand::{self, Rng};
+    use population_builder::PopulationBuilder;
+    use simulation_builder::{SimulationBuilder, Error};
+    use std::collections::{HashMap, HashSet, VecDeque};
+    use std::time::Duration;
+    use std::thread;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    #[cfg(feature = "async")]
+    use std::future::Future;
+    #[cfg(feature = "async")]
+    use std::pin::Pin;
+    #[cfg(feature = "async")]
+    use std::task::{Context, Poll};
+
+    use super::{BestSnapshot, ESScheme, Pipeline, RunError, Simulation, SimulationEvent, SimulationType, SimulationResult};
+
+    #[derive(Debug, Clone)]
+    struct Panicking {
+        value: f64,
+    }
+
+    impl Individual for Panicking {
+        fn new<S>(_data_source: S) -> Panicking {
+            Panicking { value: 100.0 }
+        }
+
+        fn mutate(&mut self) {
+            self.value -= 1.0;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value
+        }
+
+        fn calculate_fitness_with_context(&self, context: &FitnessContext) -> f64 {
+            if context.generation == 3 {
+                panic!("deliberate panic in fitness calculation");
+            }
+            self.value
+        }
+    }
+
+    #[test]
+    fn worker_panicked() {
+        let population = PopulationBuilder::<(), Panicking>::new()
+            .set_data_source(())
+            .set_id(7)
+            .individuals(5)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Panicking>::new()
+            .iterations(100)
+            .threads(2)
+            .add_population(population)
+            .finalize().unwrap();
+
+        match simulation.run() {
+            Err(RunError::WorkerPanicked { population_id }) => assert_eq!(population_id, 7),
+            _ => panic!("simulation should report the panic"),
+        }
+
+        assert_eq!(simulation.simulation_result.iteration_counter, 4);
+    }
+
+    #[test]
+    fn converged() {
+        let criterion = SimulationType::EndConvergence { window: 3, epsilon: 0.1 };
+        let mut fitness_history = VecDeque::new();
+
+        assert!(!criterion.converged(&mut fitness_history, 10.0));
+        assert!(!criterion.converged(&mut fitness_history, 5.0));
+        assert!(!criterion.converged(&mut fitness_history, 4.99));
+        assert!(!criterion.converged(&mut fitness_history, 4.98));
+        assert!(criterion.converged(&mut fitness_history, 4.97));
+        assert_eq!(fitness_history.len(), 4);
+    }
+
+    #[test]
+    fn fitness_histogram() {
+        let result = SimulationResult::<Panicking> {
+            improvement_factor: 1.0,
+            normalized_improvement: 0.0,
+            original_fitness: 10.0,
+            fittest: Vec::new(),
+            iteration_counter: 0,
+            fitness_evaluations: 0,
+            total_mutations: 0,
+            successful_mutations: 0,
+            total_crossovers: 0,
+            total_repairs: 0,
+            #[cfg(feature = "profiling")]
+            profile: Profile::new(),
+            operator_stats: Vec::new(),
+            best_snapshots: VecDeque::new(),
+            refinement: None,
+            best_validation: None,
+            selection_pressure_history: Vec::new(),
+            infeasibility_recoveries: Vec::new(),
+            total_selections: 0,
+            final_fitness: vec![1.0, 1.5, 2.0, 4.0, 5.0],
+            fitness_history: vec![10.0, 8.0, 8.0, 4.0],
+            best_share_history: vec![0.2, 0.2, 0.6, 0.2],
+            final_populations: Vec::new(),
+            total_time_in_ms: 0.0
+        };
+
+        assert_eq!(result.fitness_histogram(2), vec![(1.0, 3), (3.0, 2)]);
+        assert_eq!(result.fitness_histogram(0), vec![]);
+        assert_eq!(result.convergence_rate(2), 0.25);
+        assert_eq!(result.convergence_rate(10), (0.2 + 0.0 + 0.5) / 3.0);
+        // Only the fittest fitness 8.0 stays the same, its share grows from 0.2 to 0.6
+        assert!((result.estimated_takeover_time() - 2.0 * 4.0_f64.ln() / 6.0_f64.ln()).abs() < 1.0e-12);
+    }
+
+    #[test]
+    fn pool_populations() {
+        let simulations: Vec<Simulation<(), Panicking>> = (1..3).map(|id| {
+            let population = PopulationBuilder::<(), Panicking>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(5)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Panicking>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize().unwrap()
+        }).collect();
+
+        let pooled = Simulation::pool_populations(simulations, 6).unwrap();
+
+        assert_eq!(pooled.num_of_individuals, 6);
+        assert_eq!(pooled.population.len(), 6);
+        assert!(pooled.population.iter().all(|wrapper| wrapper.id == 1));
+    }
+
+    #[test]
+    fn original_fitness() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let best_initial = population.population.iter()
+            .map(|wrapper| wrapper.individual.calculate_fitness())
+            .fold(::std::f64::MAX, f64::min);
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.step().unwrap();
+
+        assert_eq!(simulation.simulation_result.original_fitness, best_initial);
+        assert_eq!(simulation.habitat[0].original_fitness, best_initial);
+    }
+
+    #[test]
+    fn sample_aggregator() {
+        let samples = [3.0, 1.0, 2.0, 6.0];
+
+        assert_eq!(Aggregator::Mean.aggregate(&samples), 3.0);
+        assert_eq!(Aggregator::Min.aggregate(&samples), 1.0);
+        assert_eq!(Aggregator::Max.aggregate(&samples), 6.0);
+        assert!(Aggregator::MeanPlusStd(1.0).aggregate(&samples) > 3.0);
+
+        // A single sample is the fitness for every aggregation
+        for aggregator in &[Aggregator::Mean, Aggregator::Min, Aggregator::Max] {
+            assert_eq!(aggregator.aggregate(&[4.0]), 4.0);
+        }
+    }
+
+    /// Each mutation improves the fitness by a tiny (floating point noise) amount.
+    #[derive(Debug, Clone)]
+    struct Creeping {
+        value: f64,
+    }
+
+    impl Individual for Creeping {
+        fn new<S>(_data_source: S) -> Creeping {
+            Creeping { value: 1.0 }
+        }
+
+        fn mutate(&mut self) {
+            self.value -= 1.0e-12;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value
+        }
+    }
+
+    #[test]
+    fn improvement_epsilon() {
+        let successful_mutations = |improvement_epsilon: f64| {
+            let population = PopulationBuilder::<(), Creeping>::new()
+                .set_data_source(())
+                .individuals(10)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), Creeping>::new()
+                .iterations(10)
+                .improvement_epsilon(improvement_epsilon)
+                .add_population(population)
+                .finalize().unwrap();
+
+            simulation.run().unwrap();
+            assert_eq!(simulation.simulation_result.total_mutations, 10 * 10);
+            simulation.simulation_result.successful_mutations
+        };
+
+        assert_eq!(successful_mutations(1.0e-9), 0);
+        assert_eq!(successful_mutations(0.0), 10 * 10);
+    }
+
+    #[test]
+    fn diversity_injection() {
+        // The seeds are far worse than any new individual, so only an injected individual
+        // can improve the best fitness
+        let fittest = |diversity_injection: Option<(u32, f64)>| {
+            let mut builder = PopulationBuilder::<(), Creeping>::new()
+                .set_data_source(())
+                .individuals(10)
+                .seed_individuals(vec![Creeping { value: 5.0 }], 1)
+                .reset_limit_end(0);
+
+            if let Some((every, fraction)) = diversity_injection {
+                builder = builder.diversity_injection(every, fraction);
+            }
+
+            let mut simulation = SimulationBuilder::<(), Creeping>::new()
+                .iterations(10)
+                .add_population(builder.finalize().unwrap())
+                .finalize().unwrap();
+
+            for _ in 0..2 {
+                simulation.step().unwrap();
+            }
+            let before = simulation.simulation_result.fittest[0].fitness;
+
+            for _ in 0..3 {
+                simulation.step().unwrap();
+            }
+            (before, simulation.simulation_result.fittest[0].fitness)
+        };
+
+        let (before, after) = fittest(None);
+        assert!(before > 4.0 && after > 4.0);
+
+        let (before, after) = fittest(Some((3, 0.5)));
+        assert!(before > 4.0);
+        assert!(after < 1.0);
+    }
+
+    #[test]
+    fn fitness_scaling() {
+        // Prefers the individuals near the mean, so the fittest one does not survive first
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .fitness_scaling(Box::new(|fitness, stats| (fitness - stats.fitness_mean).abs()))
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        for _ in 0..5 {
+            simulation.step().unwrap();
+
+            let fitness: Vec<f64> = simulation.habitat[0].population.iter()
+                .map(|wrapper| wrapper.fitness)
+                .collect();
+            assert!(fitness.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+
+    #[test]
+    fn set_mutation_rate() {
+        let population = PopulationBuilder::<(), Creeping>::new()
+            .set_data_source(())
+            .set_id(1)
+            .individuals(10)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Creeping>::new()
+            .iterations(100)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.step().unwrap();
+        let fitness = simulation.best_fitness().unwrap();
+
+        match simulation.set_mutation_rate(2, vec![0; 10]) {
+            Err(RunError::UnknownPopulation { population_id: 2 }) => {}
+            result => panic!("unexpected result: {:?}", result)
+        }
+        match simulation.set_mutation_rate(1, vec![0; 3]) {
+            Err(RunError::MutationRateMismatch { expected: 10, given: 3, .. }) => {}
+            result => panic!("unexpected result: {:?}", result)
+        }
+        assert!(simulation.set_threads(0).is_err());
+
+        // Without mutations nothing improves any more
+        simulation.set_mutation_rate(1, vec![0; 10]).unwrap();
+        simulation.step().unwrap();
+        simulation.step().unwrap();
+        assert_eq!(simulation.best_fitness(), Some(fitness));
+        assert!(simulation.habitat[0].population.iter().all(|wrapper| wrapper.num_of_mutations == 0));
+
+        simulation.set_mutation_rate(1, vec![1; 10]).unwrap();
+        simulation.step().unwrap();
+        assert!(simulation.best_fitness().unwrap() < fitness);
+    }
+
+    #[test]
+    fn dedup() {
+        let duplicates_replaced = |dedup: bool| {
+            let population = PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .individuals(20)
+                .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 0)
+                .mutation_rate(vec![0; 20])
+                .reset_limit_end(0)
+                .dedup(dedup)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), OneMax>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize().unwrap();
+
+            simulation.step().unwrap();
+            simulation.habitat[0].stats.duplicates_replaced
+        };
+
+        // All but the first one of the identical seeds are replaced
+        assert_eq!(duplicates_replaced(true), 19);
+        assert_eq!(duplicates_replaced(false), 0);
+    }
+
+    #[test]
+    fn prune_stalled() {
+        let mut builder = SimulationBuilder::<(), OneMax>::new()
+            .iterations(200)
+            .prune_stalled(5);
+
+        for id in 1..4 {
+            builder = builder.add_population(PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(20)
+                .increasing_mutation_rate()
+                .mutation_seed(id as u64)
+                .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 5)
+                .reset_limit_end(0)
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.habitat.len(), 1);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+    }
+
+    #[test]
+    fn spatial_population() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .spatial(5, 4, Neighborhood::VonNeumann)
+            .increasing_mutation_rate()
+            .mutation_seed(3)
+            .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+            .reset_limit_end(0)
+            .finalize().unwrap();
+
+        assert_eq!(population.population.len(), 20);
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(300)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+
+        let mut cells: Vec<usize> = simulation.habitat[0].population.iter().map(|wrapper| wrapper.cell).collect();
+        cells.sort();
+        assert_eq!(cells, (0..20).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn step() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(100)
+            .threads(2)
+            .add_population(population)
+            .finalize().unwrap();
+
+        assert!(simulation.pool.is_none());
+
+        for iteration in 1..4 {
+            simulation.step().unwrap();
+            assert_eq!(simulation.simulation_result.iteration_counter, iteration);
+            assert_eq!(simulation.pool.as_ref().map(|&(threads, _)| threads), Some(2));
+        }
+
+        simulation.set_threads(3).unwrap();
+        simulation.step().unwrap();
+        assert_eq!(simulation.pool.as_ref().map(|&(threads, _)| threads), Some(3));
+    }
+
+    #[test]
+    fn run_for() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(1000000)
+            .add_population(population)
+            .finalize().unwrap();
+
+        let first = simulation.run_for(Duration::from_millis(20)).unwrap();
+        let second = simulation.run_for(Duration::from_millis(20)).unwrap();
+
+        assert!(first >= 1 && second >= 1);
+        assert_eq!(simulation.simulation_result.iteration_counter, first + second);
+        assert!(!simulation.finished());
+    }
+
+    #[test]
+    fn cancelled() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(1000)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.step().unwrap();
+        simulation.cancel_flag().store(true, Ordering::SeqCst);
+
+        match simulation.run() {
+            Err(RunError::Cancelled) => {}
+            result => panic!("Simulation was not cancelled: {:?}", result)
+        }
+
+        assert!(simulation.finished());
+        assert_eq!(simulation.simulation_result.iteration_counter, 2);
+    }
+
+    #[test]
+    fn stop_when_all() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .stop_when_all(vec![SimulationType::EndIteration(20), SimulationType::EndFitness(10.0)])
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.simulation_result.iteration_counter, 20);
+    }
+
+    #[test]
+    fn stop_when_all_nested() {
+        let finalize = |stop_criteria: Vec<SimulationType>| SimulationBuilder::<(), Sphere>::new()
+            .stop_when_all(stop_criteria)
+            .add_population(PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(10)
+                .finalize().unwrap())
+            .finalize();
+
+        let nested = |stop_criterion: SimulationType| finalize(vec![SimulationType::EndIteration(20),
+            SimulationType::EndAll(vec![SimulationType::EndFitness(1.0), stop_criterion])]);
+
+        match nested(SimulationType::EndIteration(5)) {
+            Err(Error::EndIterationTooLow) => {}
+            _ => panic!("Nested iteration limit not checked")
+        }
+        match nested(SimulationType::EndConvergence { window: 0, epsilon: 0.1 }) {
+            Err(Error::ConvergenceWindowTooLow) => {}
+            _ => panic!("Nested convergence window not checked")
+        }
+        match nested(SimulationType::EndRelativeGap(0.1)) {
+            Err(Error::TargetOptimumMissing) => {}
+            _ => panic!("Nested relative gap not checked")
+        }
+        match nested(SimulationType::EndAll(Vec::new())) {
+            Err(Error::StopCriteriaEmpty) => {}
+            _ => panic!("Nested empty stop criteria not checked")
+        }
+
+        assert!(nested(SimulationType::EndFactor(0.5)).is_ok());
+        assert!(finalize(vec![SimulationType::NotConfigured]).is_err());
+    }
+
+    /// Deterministic individual: the mutation uses a random number generator stored in the
+    /// individual itself instead of the thread local one.
+    #[derive(Debug, Clone)]
+    struct Deterministic {
+        state: u64,
+        value: f64,
+    }
+
+    impl Individual for Deterministic {
+        fn new<S>(_data_source: S) -> Deterministic {
+            Deterministic { state: 1, value: 100.0 }
+        }
+
+        fn mutate(&mut self) {
+            self.state = self.state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            self.value += ((self.state >> 33) % 7) as f64 - 4.0;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value.abs()
+        }
+    }
+
+    #[test]
+    fn same_result_for_all_thread_counts() {
+        let run = |threads: usize| {
+            let mut builder = SimulationBuilder::<(), Deterministic>::new()
+                .iterations(50)
+                .threads(threads);
+
+            for id in 1..6 {
+                let mut population = PopulationBuilder::<(), Deterministic>::new()
+                    .set_data_source(())
+                    .set_id(id)
+                    .individuals(10)
+                    .increasing_mutation_rate()
+                    .finalize().unwrap();
+
+                for (index, wrapper) in population.population.iter_mut().enumerate() {
+                    wrapper.individual.state = (id as u64) * 1000 + index as u64;
+                }
+
+                builder = builder.add_population(population);
+            }
+
+            let mut simulation = builder.finalize().unwrap();
+            simulation.run().unwrap();
+            simulation.simulation_result
+        };
+
+        let single = run(1);
+        let multi = run(4);
+
+        assert_eq!(single.fitness_history, multi.fitness_history);
+        assert_eq!(single.final_fitness, multi.final_fitness);
+        assert_eq!(single.fitness_evaluations, multi.fitness_evaluations);
+        assert_eq!(single.fittest.iter().map(|wrapper| wrapper.individual.state).collect::<Vec<u64>>(),
+                   multi.fittest.iter().map(|wrapper| wrapper.individual.state).collect::<Vec<u64>>());
+    }
+
+    /// Mutates with the random number generator given by the framework.
+    #[derive(Debug, Clone)]
+    struct Walker {
+        value: f64,
+    }
+
+    impl Individual for Walker {
+        fn new<S>(_data_source: S) -> Walker {
+            Walker { value: 100.0 }
+        }
+
+        fn mutate(&mut self) {
+            self.mutate_with_rng(&mut rand::thread_rng());
+        }
+
+        fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+            self.value += Rng::gen_range(&mut rng, -1.0, 1.0);
+        }
+
+        fn local_search(&mut self) {
+            self.value -= self.value.signum() * self.value.abs().min(1.0);
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value.abs()
+        }
+
+        fn validation_fitness(&self) -> Option<f64> {
+            Some((self.value - 30.0).abs())
+        }
+    }
+
+    /// The lower the value the fitter, but negative values are infeasible.
+    #[derive(Debug, Clone)]
+    struct NonNegative {
+        value: i32,
+    }
+
+    impl Individual for NonNegative {
+        fn new<S>(_data_source: S) -> NonNegative {
+            NonNegative { value: 10 }
+        }
+
+        fn mutate(&mut self) {
+            self.mutate_with_rng(&mut rand::thread_rng());
+        }
+
+        fn mutate_with_rng(&mut self, mut rng: &mut dyn Rng) {
+            self.value += if Rng::gen(&mut rng) { 1 } else { -1 };
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value as f64
+        }
+
+        fn constraint_violation(&self) -> f64 {
+            (-self.value).max(0) as f64
+        }
+
+        fn repair(&mut self) -> bool {
+            let infeasible = self.value < 0;
+            self.value = self.value.max(0);
+            infeasible
+        }
+    }
+
+    #[test]
+    fn feasibility_retries() {
+        let population = PopulationBuilder::<(), NonNegative>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .reset_limit_end(0)
+            .mutation_seed(1)
+            .feasibility_retries(1000)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), NonNegative>::new()
+            .iterations(50)
+            .keep_final_population(true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.simulation_result.fittest[0].individual.value, 0);
+        assert!(simulation.simulation_result.final_populations[0].1.iter()
+            .all(|individual| individual.constraint_violation() == 0.0));
+    }
+
+    /// An individual that can't be cloned.
+    #[derive(Debug)]
+    struct Unique {
+        value: u32,
+    }
+
+    impl Individual for Unique {
+        fn new<S>(_data_source: S) -> Unique {
+            Unique { value: 0 }
+        }
+
+        fn mutate(&mut self) {
+            self.value += 1;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value as f64
+        }
+    }
+
+    #[test]
+    fn mutate_without_clone() {
+        let mut wrapper = IndividualWrapper {
+            individual: Unique { value: 0 },
+            fitness: ::std::f64::MAX,
+            num_of_mutations: 3,
+            id: 1,
+            objectives: Vec::new(),
+            dirty: true,
+            lineage: None,
+            cell: 0,
+        };
+        let mut evaluator = FitnessEvaluator::new(0, 1, 0, Arc::new(Vec::new()));
+
+        mutate_and_evaluate(&mut wrapper, None, &mut evaluator);
+
+        assert_eq!(wrapper.individual.value, 3);
+        assert_eq!(wrapper.fitness, 3.0);
+    }
+
+    #[test]
+    fn final_population() {
+        let build = |keep_final_population: bool| {
+            let mut builder = SimulationBuilder::<(), Sphere>::new()
+                .iterations(10)
+                .keep_final_population(keep_final_population);
+
+            for &(id, individuals) in &[(1, 10), (2, 15)] {
+                builder = builder.add_population(PopulationBuilder::<(), Sphere>::new()
+                    .set_data_source(())
+                    .set_id(id)
+                    .individuals(individuals)
+                    .finalize().unwrap());
+            }
+
+            let mut simulation = builder.finalize().unwrap();
+            simulation.run().unwrap();
+            simulation
+        };
+
+        let simulation = build(true);
+        let result = &simulation.simulation_result;
+        assert_eq!(result.final_population(1).map(|individuals| individuals.len()), Some(10));
+        assert_eq!(result.final_population(2).map(|individuals| individuals.len()), Some(15));
+        assert!(result.final_population(3).is_none());
+
+        let population = simulation.habitat.iter().find(|population| population.id == 2).unwrap();
+        let fitness: Vec<f64> = result.final_population(2).unwrap().iter()
+            .map(|individual| individual.calculate_fitness())
+            .collect();
+        assert_eq!(fitness, population.population.iter().map(|wrapper| wrapper.fitness).collect::<Vec<f64>>());
+
+        assert!(build(false).simulation_result.final_population(1).is_none());
+    }
+
+    #[test]
+    fn minimal_memory() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(10)
+            .track_lineage(true)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(20)
+            .keep_final_population(true)
+            .minimal_memory(true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let result = &simulation.simulation_result;
+        assert_eq!(result.iteration_counter, 20);
+        assert_eq!(result.fittest.len(), 1);
+        assert!(result.fitness_history.is_empty());
+        assert!(result.final_fitness.is_empty());
+        assert!(result.final_populations.is_empty());
+        assert!(result.fittest[0].lineage.is_none());
+        assert!(simulation.habitat[0].population.iter().all(|wrapper| wrapper.lineage.is_none()));
+        assert!(result.fittest[0].fitness <= simulation.habitat[0].population[0].fitness);
+    }
+
+    #[test]
+    fn enable_repair() {
+        let population = PopulationBuilder::<(), NonNegative>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .reset_limit_end(0)
+            .enable_repair(true)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), NonNegative>::new()
+            .iterations(50)
+            .keep_final_population(true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert!(simulation.simulation_result.total_repairs > 0);
+        assert_eq!(simulation.simulation_result.fittest[0].individual.value, 0);
+        assert!(simulation.simulation_result.final_populations[0].1.iter()
+            .all(|individual| individual.constraint_violation() == 0.0));
+    }
+
+    #[test]
+    fn best_validation() {
+        let population = PopulationBuilder::<(), Walker>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutation_seed(1)
+            .reset_limit_end(0)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Walker>::new()
+            .iterations(200)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let result = &simulation.simulation_result;
+        let &(validation_fitness, ref best) = result.best_validation.as_ref().unwrap();
+        assert_eq!(best.individual.validation_fitness(), Some(validation_fitness));
+        assert!(validation_fitness < result.fittest[0].individual.validation_fitness().unwrap());
+    }
+
+    #[test]
+    fn initial_mutations() {
+        let build = |seeds: Vec<Walker>| PopulationBuilder::<(), Walker>::new()
+            .set_data_source(())
+            .individuals(20)
+            .seed_individuals(seeds, 1)
+            .mutation_seed(7)
+            .initial_mutations(1..10)
+            .finalize().unwrap();
+
+        let values = |population: &Population<(), Walker>| population.population.iter()
+            .map(|wrapper| wrapper.individual.value)
+            .collect::<Vec<f64>>();
+
+        let first = values(&build(Vec::new()));
+        assert_eq!(first, values(&build(Vec::new())));
+        assert!(first.iter().all(|value| *value != 100.0));
+
+        let seeded = values(&build(vec![Walker { value: 50.0 }]));
+        assert_eq!(seeded[0], 50.0);
+        assert!(seeded[1..].iter().all(|value| *value != 50.0));
+    }
+
+    #[test]
+    fn seed_individuals() {
+        let build = || PopulationBuilder::<(), Walker>::new()
+            .set_data_source(())
+            .individuals(20)
+            .mutation_seed(3)
+            .seed_individuals(vec![Walker { value: 50.0 }], 5)
+            .finalize().unwrap();
+
+        let values = |population: &Population<(), Walker>| population.population.iter()
+            .map(|wrapper| wrapper.individual.value)
+            .collect::<Vec<f64>>();
+
+        let first = values(&build());
+        assert_eq!(first, values(&build()));
+        assert_eq!(first[0], 50.0);
+        assert!(first[1..].iter().any(|value| *value != 50.0));
+    }
+
+    #[cfg(feature = "async")]
+    static RUNNING: AtomicUsize = AtomicUsize::new(0);
+    #[cfg(feature = "async")]
+    static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+    /// A fitness that has to wait once for its "remote" result.
+    #[cfg(feature = "async")]
+    struct Response {
+        fitness: f64,
+        waiting: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl Future for Response {
+        type Output = f64;
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<f64> {
+            if self.waiting {
+                let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+                MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+                self.waiting = false;
+                context.waker().wake_by_ref();
+                Poll::Pending
+            } else {
+                RUNNING.fetch_sub(1, Ordering::SeqCst);
+                Poll::Ready(self.fitness)
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[derive(Debug, Clone)]
+    struct Remote {
+        walker: Walker,
+    }
+
+    #[cfg(feature = "async")]
+    impl Individual for Remote {
+        fn new<S>(data_source: S) -> Remote {
+            Remote { walker: Walker::new(data_source) }
+        }
+
+        fn mutate(&mut self) {
+            self.walker.mutate();
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            panic!("The fitness must be calculated asynchronously");
+        }
+
+        fn calculate_fitness_async(&self) -> impl Future<Output = f64> {
+            Response { fitness: self.walker.calculate_fitness(), waiting: true }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn max_concurrent_evals() {
+        let population = PopulationBuilder::<(), Remote>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .reset_limit_end(0)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Remote>::new()
+            .iterations(50)
+            .max_concurrent_evals(4)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert_eq!(MAX_RUNNING.load(Ordering::SeqCst), 4);
+        assert!(simulation.simulation_result.fittest[0].fitness < 100.0);
+        assert!(simulation.simulation_result.fitness_evaluations >= 50 * 20);
+    }
+
+    /// Panics when it is polled, if the individual is broken.
+    #[cfg(feature = "async")]
+    struct Failing {
+        fitness: f64,
+        broken: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl Future for Failing {
+        type Output = f64;
+
+        fn poll(self: Pin<&mut Self>, _context: &mut Context) -> Poll<f64> {
+            if self.broken {
+                panic!("deliberate panic in async fitness calculation");
+            }
+            Poll::Ready(self.fitness)
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[derive(Debug, Clone)]
+    struct Broken {
+        value: f64,
+        broken: bool,
+    }
+
+    #[cfg(feature = "async")]
+    impl Individual for Broken {
+        fn new<S>(_data_source: S) -> Broken {
+            Broken { value: 100.0, broken: false }
+        }
+
+        fn mutate(&mut self) {
+            self.value -= 1.0;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value
+        }
+
+        fn calculate_fitness_async(&self) -> impl Future<Output = f64> {
+            Failing { fitness: self.value, broken: self.broken }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn async_worker_panicked() {
+        let mut builder = SimulationBuilder::<(), Broken>::new()
+            .iterations(10)
+            .max_concurrent_evals(4);
+
+        for id in 1..3 {
+            builder = builder.add_population(PopulationBuilder::<(), Broken>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(5)
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        simulation.step().unwrap();
+
+        // Only the offspring of the second population panic
+        for wrapper in &mut simulation.habitat[1].population {
+            wrapper.individual.broken = true;
+        }
+
+        match simulation.step() {
+            Err(RunError::WorkerPanicked { population_id }) => assert_eq!(population_id, 2),
+            _ => panic!("simulation should report the panic"),
+        }
+    }
+
+    #[test]
+    fn mutation_seed() {
+        let run = |threads: usize, rng_stream: u64, seed_mask: u64| {
+            let mut builder = SimulationBuilder::<(), Walker>::new()
+                .iterations(50)
+                .threads(threads)
+                .rng_stream(rng_stream);
+
+            for id in 1..4 {
+                builder = builder.add_population(PopulationBuilder::<(), Walker>::new()
+                    .set_data_source(())
+                    .set_id(id)
+                    .individuals(20)
+                    .increasing_mutation_rate()
+                    .mutation_seed(id as u64 ^ seed_mask)
+                    .finalize().unwrap());
+            }
+
+            let mut simulation = builder.finalize().unwrap();
+            simulation.run().unwrap();
+            simulation.habitat.iter()
+                .flat_map(|population| population.population.iter().map(|wrapper| wrapper.individual.value))
+                .collect::<Vec<f64>>()
+        };
+
+        let single = run(1, 0, 0);
+        assert_eq!(single, run(8, 0, 0));
+        assert!(single.iter().any(|value| *value != 100.0));
+
+        let other = run(1, 1000, 0);
+        assert_eq!(other, run(8, 1000, 0));
+        assert!(other != single);
+
+        // The stream is mixed into the seeds, so even a huge one is free
+        let huge = run(1, u64::MAX, 0);
+        assert_eq!(huge, run(8, u64::MAX, 0));
+        assert!(huge != single && huge != other);
+
+        // Neighbouring seeds don't give the same generators as another stream
+        assert!(run(1, 1, 0) != run(1, 0, 0x9E3779B97F4A7C15));
+    }
+
+    #[test]
+    fn seeded_selection() {
+        let run = |threads: usize| {
+            let population = PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .individuals(20)
+                .mutation_seed(5)
+                .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+                .reset_limit_end(0)
+                .survival_rate(0.5)
+                .selection_strategy(SelectionStrategy::RankBased { pressure: 1.5 })
+                .crossover(Box::new(OnePoint), 0.5)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), OneMax>::new()
+                .iterations(10)
+                .threads(threads)
+                .add_population(population)
+                .finalize().unwrap();
+
+            for _ in 0..3 {
+                simulation.step().unwrap();
+            }
+
+            simulation.habitat[0].population.iter()
+                .map(|wrapper| wrapper.individual.bits.clone())
+                .collect::<Vec<Vec<bool>>>()
+        };
+
+        assert_eq!(run(1), run(4));
+    }
+
+    #[test]
+    fn seeded_initializer() {
+        let run = |mutation_seed: u64| {
+            let population = PopulationBuilder::<(), Permutation>::new()
+                .set_data_source(())
+                .individuals(10)
+                .increasing_mutation_rate()
+                .mutation_seed(mutation_seed)
+                .initializer(Permutation::initializer(10, 0, |order| order[0] as f64))
+                .reset_limit_start(3)
+                .reset_limit_end(10)
+                .reset_limit_increment(1)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), Permutation>::new()
+                .iterations(20)
+                .threads(2)
+                .add_population(population)
+                .finalize().unwrap();
+
+            // Several resets, each one creates all individuals again with the initializer
+            simulation.run().unwrap();
+            simulation.habitat[0].population.iter()
+                .map(|wrapper| wrapper.individual.order.clone())
+                .collect::<Vec<Vec<usize>>>()
+        };
+
+        assert_eq!(run(1), run(1));
+        assert!(run(1) != run(2));
+    }
+
+    #[test]
+    fn mutation_seeds() {
+        let mut builder = SimulationBuilder::<(), OneMax>::new()
+            .iterations(20);
+
+        for id in 1..3 {
+            builder = builder.add_population(PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(20)
+                .mutation_seed(id as u64)
+                .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+                .reset_limit_end(0)
+                .survival_rate(0.5)
+                .selection_strategy(SelectionStrategy::Tournament { size: 3 })
+                .crossover(Box::new(OnePoint), 0.5)
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        let bits = |simulation: &Simulation<(), OneMax>| simulation.habitat.iter()
+            .flat_map(|population| population.population.iter().map(|wrapper| wrapper.individual.bits.clone()))
+            .collect::<Vec<Vec<bool>>>();
+
+        for _ in 0..2 {
+            simulation.step().unwrap();
+        }
+
+        // Checkpoint
+        let seeds = simulation.mutation_seeds();
+        let habitat = simulation.habitat.clone();
+        let simulation_result = simulation.simulation_result.clone();
+
+        for _ in 0..3 {
+            simulation.step().unwrap();
+        }
+        let first = bits(&simulation);
+
+        // Resume from the checkpoint
+        simulation.habitat = habitat;
+        simulation.simulation_result = simulation_result;
+        for population in &mut simulation.habitat {
+            population.mutation_seed = Some(1000);
+        }
+        simulation.set_mutation_seeds(&seeds).unwrap();
+
+        assert_eq!(seeds, vec![(1, Some(1)), (2, Some(2))]);
+        assert_eq!(simulation.habitat[1].mutation_seed, Some(2));
+        assert_eq!(simulation.simulation_result.iteration_counter, 2);
+
+        for _ in 0..3 {
+            simulation.step().unwrap();
+        }
+        assert_eq!(bits(&simulation), first);
+
+        match simulation.set_mutation_seeds(&[(1, None), (7, None)]) {
+            Err(RunError::UnknownPopulation { population_id: 7 }) => {}
+            _ => panic!("Unknown population not detected")
+        }
+        assert_eq!(simulation.habitat[0].mutation_seed, Some(1));
+    }
+
+    #[test]
+    fn seeded_tie_break() {
+        // The population is full of ties, which are broken randomly
+        let run = || {
+            let population = PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .individuals(20)
+                .mutation_seed(7)
+                .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 2)
+                .reset_limit_end(0)
+                .survival_rate(0.5)
+                .selection_strategy(SelectionStrategy::Tournament { size: 3 })
+                .tournament_tie_break(TieBreak::Random)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), OneMax>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize().unwrap();
+
+            for _ in 0..3 {
+                simulation.step().unwrap();
+            }
+
+            simulation.habitat[0].population.iter()
+                .map(|wrapper| wrapper.individual.bits.clone())
+                .collect::<Vec<Vec<bool>>>()
+        };
+
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn boltzmann_late_generation() {
+        let mut population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(10)
+            .finalize().unwrap();
+
+        for (rank, wrapper) in population.population.iter_mut().enumerate() {
+            wrapper.fitness = rank as f64;
+        }
+
+        let parents = &population.population;
+        let selection_strategy = SelectionStrategy::Boltzmann { initial_temperature: 1.0, cooling_rate: 0.5 };
+        let mut rng = rand::thread_rng();
+
+        // After that many generations the temperature is (nearly) zero, so only the fittest
+        // survivor is chosen
+        for generation in &[1000, 1 << 31, ::std::u32::MAX] {
+            for index in 0..20 {
+                assert_eq!(selection_strategy.select(index, parents, TieBreak::First, *generation, &mut rng), 0);
+            }
+        }
+    }
+
+    #[test]
+    fn final_refinement() {
+        let population = PopulationBuilder::<(), Walker>::new()
+            .set_data_source(())
+            .individuals(10)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Walker>::new()
+            .iterations(10)
+            .final_refinement(5)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let (before, after) = simulation.simulation_result.refinement.unwrap();
+        assert!((after - (before - 5.0).max(0.0)).abs() < 1.0e-9);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, after);
+
+        // Stepping refines only once the simulation has finished
+        let population = PopulationBuilder::<(), Walker>::new()
+            .set_data_source(())
+            .individuals(10)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Walker>::new()
+            .iterations(10)
+            .final_refinement(5)
+            .add_population(population)
+            .finalize().unwrap();
+
+        while !simulation.finished() {
+            assert!(simulation.simulation_result.refinement.is_none());
+            simulation.step().unwrap();
+        }
+
+        let (before, after) = simulation.simulation_result.refinement.unwrap();
+        assert!((after - (before - 5.0).max(0.0)).abs() < 1.0e-9);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, after);
+    }
+
+    /// Two objectives of very different scales.
+    #[derive(Debug, Clone)]
+    struct Scales {
+        cost: f64,
+        quality: f64,
+    }
+
+    impl Individual for Scales {
+        fn new<S>(_data_source: S) -> Scales {
+            Scales { cost: 0.0, quality: 0.0 }
+        }
+
+        fn mutate(&mut self) {
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.cost
+        }
+
+        fn calculate_fitness_multi(&self) -> Vec<f64> {
+            vec![self.cost, self.quality]
+        }
+    }
+
+    #[test]
+    fn objective_bounds() {
+        let run = |objective_bounds: Vec<(f64, f64)>| {
+            let population = PopulationBuilder::<(), Scales>::new()
+                .set_data_source(())
+                .individuals(4)
+                .seed_individuals(vec![Scales { cost: 200.0, quality: 0.1 }, Scales { cost: 300.0, quality: 0.9 }], 1)
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), Scales>::new()
+                .iterations(10)
+                .lexicographic_objectives(2)
+                .objective_goals(vec![FitnessGoal::Minimize, FitnessGoal::Maximize])
+                .objective_bounds(objective_bounds)
+                .add_population(population)
+                .finalize().unwrap();
+
+            simulation.step().unwrap();
+            simulation
+        };
+
+        let simulation = run(Vec::new());
+        assert_eq!(simulation.simulation_result.fittest[0].individual.cost, 200.0);
+        assert_eq!(simulation.best_objectives(), Some(vec![200.0, 0.1]));
+
+        // The bounds don't change the lexicographic order, and the objectives keep their values
+        let simulation = run(vec![(0.0, 100.0), (0.0, 1.0)]);
+        assert_eq!(simulation.simulation_result.fittest[0].individual.cost, 200.0);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, 200.0);
+        assert_eq!(simulation.best_objectives(), Some(vec![200.0, 0.1]));
+        assert_eq!(simulation.normalize_objectives(&[50.0, 0.9]), vec![0.5, 1.0 - 0.9]);
+        // Values beyond the bounds are not clamped
+        assert_eq!(simulation.normalize_objectives(&[300.0, 0.9]), vec![3.0, 1.0 - 0.9]);
+    }
+
+    #[test]
+    fn worst() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(20)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let worst = simulation.worst().unwrap().calculate_fitness();
+
+        assert_eq!(simulation.habitat[0].worst().unwrap().calculate_fitness(), worst);
+        assert!(simulation.habitat[0].population.iter().all(|wrapper| wrapper.fitness <= worst));
+    }
+
+    struct OnePoint;
+
+    impl Crossover<OneMax> for OnePoint {
+        fn crossover(&self, first: &OneMax, second: &OneMax, mut rng: &mut dyn Rng) -> (OneMax, Option<OneMax>) {
+            let point = Rng::gen_range(&mut rng, 0, first.bits.len());
+            let child = first.bits[..point].iter().chain(&second.bits[point..]).cloned().collect();
+            let second_child = second.bits[..point].iter().chain(&first.bits[point..]).cloned().collect();
+
+            (OneMax { bits: child }, Some(OneMax { bits: second_child }))
+        }
+    }
+
+    #[test]
+    fn crossover() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutation_seed(4)
+            .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+            .reset_limit_end(0)
+            .crossover(Box::new(OnePoint), 0.5)
+            .offspring_per_crossover(2)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(200)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert!(simulation.simulation_result.total_crossovers > 0);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+    }
+
+    #[test]
+    fn lineage() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .crossover(Box::new(OnePoint), 0.5)
+            .offspring_per_crossover(2)
+            .track_lineage(true)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(30)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let result = &simulation.simulation_result;
+        let records: HashMap<u64, &Lineage> = simulation.habitat[0].population.iter()
+            .chain(result.fittest.iter())
+            .filter_map(|wrapper| wrapper.lineage.as_ref())
+            .flat_map(|lineage| lineage.history())
+            .map(|record| (record.id, record))
+            .collect();
+
+        // Rebuild the ancestry of the fittest individual from the parent ids alone
+        let mut ancestry = vec![result.fittest[0].lineage_id().unwrap()];
+        while let Some(&parent_id) = records[ancestry.last().unwrap()].parent_ids.first() {
+            ancestry.push(parent_id);
+        }
+
+        let history: Vec<u64> = result.best_lineage().iter().map(|record| record.id).collect();
+        assert_eq!(ancestry, history);
+
+        let oldest = records[ancestry.last().unwrap()];
+        assert!(oldest.origin == Origin::Initial || oldest.origin == Origin::Reset);
+        assert!(records.values().filter(|record| record.origin == Origin::Crossover)
+            .all(|record| record.parent_ids.len() == 2));
+    }
+
+    #[test]
+    fn duplicate_population_id() {
+        let mut builder = SimulationBuilder::<(), Sphere>::new()
+            .iterations(20);
+
+        for _ in 0..2 {
+            builder = builder.add_population(PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .set_id(1)
+                .individuals(10)
+                .finalize().unwrap());
+        }
+
+        match builder.finalize() {
+            Err(Error::DuplicatePopulationId { population_id: 1 }) => {}
+            Err(e) => panic!("Unexpected error: {}", e),
+            Ok(_) => panic!("Duplicate population id not detected")
+        }
+    }
+
+    #[test]
+    fn executor() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .finalize().unwrap();
+
+        let num_of_jobs = Arc::new(AtomicUsize::new(0));
+        let counter = num_of_jobs.clone();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(100)
+            .executor(Box::new(move |jobs| {
+                counter.fetch_add(jobs.len(), Ordering::SeqCst);
+
+                for job in jobs {
+                    job();
+                }
+            }))
+            .add_population(population)
+            .finalize().unwrap();
+
+        // A simulation with an executor can still be moved to another thread
+        let simulation = thread::spawn(move || {
+            simulation.run().unwrap();
+            simulation
+        }).join().unwrap();
+
+        assert_eq!(num_of_jobs.load(Ordering::SeqCst), 100 * 20);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+    }
+
+    #[test]
+    fn executor_pool() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .finalize().unwrap();
+
+        // An existing pool of the application, its scope waits for the borrowing jobs
+        let pool = Mutex::new(make_pool(4).unwrap());
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let job_threads = threads.clone();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(100)
+            .executor(Box::new(move |jobs| {
+                let job_threads = &job_threads;
+
+                pool.lock().unwrap().scope(|scope| {
+                    for job in jobs {
+                        scope.submit(move || {
+                            job_threads.lock().unwrap().insert(thread::current().id());
+                            job();
+                        });
+                    }
+                });
+            }))
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert!(!threads.lock().unwrap().is_empty());
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, OneMax::global_optimum());
+    }
+
+    #[test]
+    fn cpu_affinity() {
+        let run = |cpu_affinity: Option<usize>| {
+            let mut builder = SimulationBuilder::<(), OneMax>::new()
+                .iterations(10)
+                .threads(2);
+
+            for id in 1..3 {
+                let population = PopulationBuilder::<(), OneMax>::new()
+                    .set_data_source(())
+                    .set_id(id)
+                    .individuals(20)
+                    .mutation_seed(id as u64)
+                    .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+                    .reset_limit_end(0)
+                    .cpu_affinity(if id == 1 { cpu_affinity } else { None })
+                    .finalize().unwrap();
+
+                assert_eq!(population.cpu_affinity, if id == 1 { cpu_affinity } else { None });
+                builder = builder.add_population(population);
+            }
+
+            let mut simulation = builder.finalize().unwrap();
+
+            for _ in 0..3 {
+                simulation.step().unwrap();
+            }
+
+            simulation.habitat.iter()
+                .flat_map(|population| population.population.iter().map(|wrapper| wrapper.individual.bits.clone()))
+                .collect::<Vec<Vec<bool>>>()
+        };
+
+        // A pinned population evolves just like one in the thread pool
+        assert_eq!(run(Some(0)), run(None));
+    }
+
+    #[test]
+    fn normalized_improvement() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .target_optimum(OneMax::global_optimum())
+            .fitness(OneMax::global_optimum())
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.simulation_result.normalized_improvement, 1.0);
+    }
+
+    #[test]
+    fn replacement_policy() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .reset_limit_end(0)
+            .replacement_policy(Box::new(|_offspring, _parent| false))
+            .finalize().unwrap();
+
+        let mut original: Vec<f64> = population.population.iter()
+            .map(|wrapper| wrapper.individual.calculate_fitness()).collect();
+        original.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        // No offspring ever replaces its parent
+        let last: Vec<f64> = simulation.habitat[0].population.iter().map(|wrapper| wrapper.fitness).collect();
+        assert_eq!(last, original);
+
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .survival_rate(0.5)
+            .replacement_policy(Box::new(|_offspring, _parent| false))
+            .finalize().unwrap();
+
+        let result = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .pipeline(Pipeline::SelectMutateEvaluate)
+            .add_population(population)
+            .finalize();
+
+        match result {
+            Err(Error::ReplacementPolicyInvalid) => {}
+            _ => panic!("Replacement policy with the select mutate evaluate pipeline not rejected")
+        }
+    }
+
+    #[test]
+    fn sequential_populations() {
+        let mut builder = SimulationBuilder::<(), OneMax>::new()
+            .iterations(50)
+            .migration_interval(5)
+            .sequential_populations(true);
+
+        for id in 1..4 {
+            builder = builder.add_population(PopulationBuilder::<(), OneMax>::new()
+                .set_id(id)
+                .set_data_source(())
+                .individuals(20)
+                .increasing_mutation_rate()
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        simulation.run().unwrap();
+
+        assert_eq!(simulation.simulation_result.iteration_counter, 50);
+
+        for population in &simulation.habitat {
+            assert_eq!(population.population.len(), 20);
+            assert!(population.spare.is_empty());
+        }
+    }
+
+    #[test]
+    fn reevaluate_elites() {
+        let evaluations = |reevaluate_elites| {
+            let population = PopulationBuilder::<(), OneMax>::new()
+                .set_data_source(())
+                .individuals(20)
+                .reset_limit_end(0)
+                .reevaluate_elites(reevaluate_elites)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), OneMax>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize().unwrap();
+
+            simulation.run().unwrap();
+            simulation.simulation_result.fitness_evaluations
+        };
+
+        // All 20 survivors are evaluated again in each of the 10 iterations
+        assert_eq!(evaluations(true), evaluations(false) + 10 * 20);
+    }
+
+    struct FlipBit;
+
+    impl Mutator<OneMax> for FlipBit {
+        fn mutate(&self, individual: &mut OneMax, mut rng: &mut dyn Rng, _context: &MutationContext) {
+            let index = Rng::gen_range(&mut rng, 0, individual.bits.len());
+            individual.bits[index] = !individual.bits[index];
+        }
+    }
+
+    struct DoNothing;
+
+    impl Mutator<OneMax> for DoNothing {
+        fn mutate(&self, _individual: &mut OneMax, _rng: &mut dyn Rng, _context: &MutationContext) {
+        }
+    }
+
+    /// Records the fitness and the mutation scale of each mutated individual.
+    struct RecordScale {
+        scales: Arc<Mutex<Vec<(f64, f64)>>>,
+    }
+
+    impl Mutator<Sphere> for RecordScale {
+        fn mutate(&self, individual: &mut Sphere, mut rng: &mut dyn Rng, context: &MutationContext) {
+            self.scales.lock().unwrap().push((individual.calculate_fitness(), context.scale));
+            individual.mutate_with_rng(&mut rng);
+        }
+    }
+
+    #[test]
+    fn fitness_scaled_mutation() {
+        let scales = |fitness_scaled_mutation: bool| {
+            let scales = Arc::new(Mutex::new(Vec::new()));
+
+            let population = PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(20)
+                .mutator(Box::new(RecordScale { scales: scales.clone() }))
+                .fitness_scaled_mutation(fitness_scaled_mutation)
+                .finalize().unwrap();
+
+            let mut simulation = SimulationBuilder::<(), Sphere>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize().unwrap();
+
+            simulation.step().unwrap();
+
+            let mut scales = scales.lock().unwrap().clone();
+            scales.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            scales
+        };
+
+        // The fittest individual gets 0.0, the least fit one 1.0 and the other ones in between
+        let scaled = scales(true);
+        assert_eq!(scaled.len(), 20);
+        assert_eq!(scaled[0].1, 0.0);
+        assert_eq!(scaled[19].1, 1.0);
+        assert!(scaled.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        assert!(scales(false).iter().all(|&(_, scale)| scale == 1.0));
+    }
+
+    #[test]
+    fn operator_stats() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutator(Box::new(RandomOperator::new()
+                .add("flip", 1.0, Box::new(FlipBit))
+                .add("nothing", 1.0, Box::new(DoNothing))))
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let stats = &simulation.simulation_result.operator_stats;
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].name, "flip");
+        assert_eq!(stats[1].name, "nothing");
+        assert!(stats.iter().all(|stat| stat.applications > 0));
+        assert!(stats[0].improvements > 0);
+        assert!(stats.iter().map(|stat| stat.applications).sum::<u64>() >= simulation.simulation_result.total_mutations);
+    }
+
+    #[test]
+    fn adaptive_operators() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutation_seed(2)
+            .seed_individuals(vec![OneMax { bits: vec![false; 10] }], 3)
+            .reset_limit_end(0)
+            .adaptive_operators(vec![("flip", Box::new(FlipBit)), ("nothing", Box::new(DoNothing))])
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let stats = &simulation.simulation_result.operator_stats;
+        assert_eq!(stats.iter().map(|stat| stat.name.as_str()).collect::<Vec<&str>>(), vec!["flip", "nothing"]);
+        assert!(stats.iter().all(|stat| stat.applications > 0));
+    }
+
+    #[cfg(feature = "profiling")]
+    #[test]
+    fn profile() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_id(1)
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .mutator(Box::new(RandomOperator::new()
+                .add("flip", 1.0, Box::new(FlipBit))
+                .add("nothing", 1.0, Box::new(DoNothing))))
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let profile = &simulation.simulation_result.profile;
+        assert_eq!(profile.get(&["iteration"]).unwrap().count, 10);
+        assert_eq!(profile.get(&["iteration", "prepare"]).unwrap().count, 10);
+        assert_eq!(profile.get(&["population 1", "finish"]).unwrap().count, 10);
+        assert!(profile.get(&["population 1", "evaluation"]).unwrap().count >= 10 * 20);
+        assert!(profile.get(&["population 1", "mutation", "flip"]).is_some());
+        assert!(profile.get(&["population 1", "mutation", "nothing"]).is_some());
+        assert!(profile.to_collapsed().contains("population 1;mutation;flip "));
+    }
+
+    #[test]
+    fn auto_tune_selection() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(100)
+            .increasing_mutation_rate()
+            .survival_rate(0.5)
+            .selection_strategy(SelectionStrategy::Tournament { size: 4 })
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(50)
+            .auto_tune_selection(true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        // OneMax has at most 11 different fitness values, so the diversity of 100 individuals
+        // is always low and the pressure must have been lowered
+        let history = &simulation.simulation_result.selection_pressure_history;
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].1.len(), 50);
+        assert!(history[0].1.iter().all(|pressure| *pressure >= 0.1 && *pressure <= 1.0));
+        assert!(history[0].1.iter().any(|pressure| *pressure < 1.0));
+
+        assert_eq!(SelectionStrategy::Tournament { size: 4 }.scaled(0.5), SelectionStrategy::Tournament { size: 2 });
+        assert_eq!(SelectionStrategy::RankBased { pressure: 2.0 }.scaled(0.5), SelectionStrategy::RankBased { pressure: 1.5 });
+    }
+
+    #[test]
+    fn es_scheme() {
+        let run = |es_scheme: ESScheme| {
+            let population = PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .individuals(20)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Sphere>::new()
+                .iterations(500)
+                .es_scheme(es_scheme)
+                .add_population(population)
+                .finalize()
+        };
+
+        let mut one_plus_one = run(ESScheme::OnePlusOne).unwrap();
+        one_plus_one.run().unwrap();
+        assert_eq!(one_plus_one.habitat[0].population.len(), 2);
+        assert!(one_plus_one.simulation_result.fittest[0].fitness < one_plus_one.simulation_result.original_fitness);
+
+        let mut comma = run(ESScheme::MuCommaLambda { mu: 3, lambda: 12 }).unwrap();
+        comma.run().unwrap();
+        assert_eq!(comma.habitat[0].population.len(), 15);
+        assert!(comma.habitat[0].population.iter().all(|wrapper| wrapper.num_of_mutations == 1));
+        assert!(comma.simulation_result.fittest[0].fitness < comma.simulation_result.original_fitness);
+
+        assert!(run(ESScheme::MuPlusLambda { mu: 5, lambda: 30 }).is_ok());
+        assert!(match run(ESScheme::MuCommaLambda { mu: 5, lambda: 2 }) { Err(Error::ESSchemeInvalid) => true, _ => false });
+        assert!(match run(ESScheme::MuPlusLambda { mu: 0, lambda: 2 }) { Err(Error::ESSchemeInvalid) => true, _ => false });
+    }
+
+    /// Starts infeasible and every mutation makes it worse, only a repair makes it feasible.
+    #[derive(Debug, Clone)]
+    struct Trapped {
+        value: i32,
+    }
+
+    impl Individual for Trapped {
+        fn new<S>(_data_source: S) -> Trapped {
+            Trapped { value: -10 }
+        }
+
+        fn mutate(&mut self) {
+            self.value -= 1;
+        }
+
+        fn calculate_fitness(&self) -> f64 {
+            self.value.abs() as f64
+        }
+
+        fn constraint_violation(&self) -> f64 {
+            (-self.value).max(0) as f64
+        }
+
+        fn repair(&mut self) -> bool {
+            let infeasible = self.value < 0;
+            self.value = self.value.max(0);
+            infeasible
+        }
+    }
+
+    #[test]
+    fn infeasibility_recovery() {
+        let run = |policy: InfeasibilityRecovery, generations: u32| {
+            let population = PopulationBuilder::<(), Trapped>::new()
+                .set_data_source(())
+                .individuals(10)
+                .increasing_mutation_rate()
+                .reset_limit_end(0)
+                .finalize().unwrap();
+
+            SimulationBuilder::<(), Trapped>::new()
+                .iterations(20)
+                .infeasibility_recovery(policy, generations)
+                .add_population(population)
+                .finalize()
+        };
+
+        // After the repair the parents stay feasible, so it is needed only once
+        let mut repair = run(InfeasibilityRecovery::Repair, 3).unwrap();
+        repair.run().unwrap();
+        assert_eq!(repair.simulation_result.infeasibility_recoveries, vec![(3, 1, InfeasibilityRecovery::Repair)]);
+        assert_eq!(repair.simulation_result.fittest[0].fitness, 0.0);
+
+        // New individuals are infeasible again
+        let mut reseed = run(InfeasibilityRecovery::Reseed, 3).unwrap();
+        reseed.run().unwrap();
+        assert!(reseed.simulation_result.infeasibility_recoveries.len() >= 2);
+
+        let mut relax = run(InfeasibilityRecovery::RelaxPenalty, 3).unwrap();
+        relax.run().unwrap();
+        assert!(relax.habitat[0].penalty_scale < 1.0);
+
+        assert!(match run(InfeasibilityRecovery::Repair, 0) { Err(Error::InfeasibilityGenerationsTooLow) => true, _ => false });
+    }
+
+    #[test]
+    fn threads_per_individuals() {
+        let population = PopulationBuilder::<(), OneMax>::new()
+            .set_data_source(())
+            .individuals(50)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), OneMax>::new()
+            .iterations(10)
+            .threads_per_individuals(20)
+            .add_population(population)
+            .finalize().unwrap();
+
+        let cores = thread::available_parallelism().unwrap().get();
+        assert_eq!(simulation.thread_count(), cores.min(3));
+        simulation.run().unwrap();
+
+        simulation.set_threads(4).unwrap();
+        assert_eq!(simulation.thread_count(), 4);
+    }
+
+    #[test]
+    fn fitness_trajectories() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .track_individual_history(true)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(50)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let result = &simulation.simulation_result;
+        let trajectories = result.fitness_trajectories(3);
+        assert_eq!(trajectories.len(), 3.min(result.fittest.len()));
+        assert_eq!(trajectories[0].0, result.fittest[0].lineage.as_ref().unwrap().id);
+        assert_eq!(trajectories[0].1.last().unwrap().1, result.fittest[0].fitness);
+        assert!(trajectories.iter().zip(&result.fittest)
+            .all(|(&(id, _), wrapper)| wrapper.lineage.as_ref().map(|lineage| lineage.id) == Some(id)));
+        assert!(trajectories.iter().all(|&(_, ref trajectory)| trajectory.windows(2).all(|pair| pair[0].0 < pair[1].0)));
+    }
+
+    #[test]
+    fn recent_bests() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(100)
+            .recent_bests(3, true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+
+        let recent = simulation.simulation_result.recent_bests();
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].fitness, simulation.simulation_result.fittest[0].fitness);
+        assert!(recent[0].fitness < recent[1].fitness && recent[1].fitness < recent[2].fitness);
+        assert!(recent[0].iteration > recent[1].iteration);
+        assert!(recent.iter().all(|snapshot| snapshot.individual.is_some()));
+    }
+
+    #[test]
+    fn events() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .increasing_mutation_rate()
+            .finalize().unwrap();
+
+        let (sender, receiver) = channel();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(100)
+            .events(sender, true)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.run().unwrap();
+        drop(simulation.events.take());
+
+        let bests: Vec<BestSnapshot<Sphere>> = receiver.iter().filter_map(|event| match event {
+            SimulationEvent::NewBest(snapshot) => Some(snapshot),
+            SimulationEvent::InfeasibilityRecovery { .. } => None,
+        }).collect();
+
+        assert!(!bests.is_empty());
+        assert!(bests.windows(2).all(|pair| pair[1].fitness < pair[0].fitness));
+        assert_eq!(bests.last().unwrap().fitness, simulation.simulation_result.fittest[0].fitness);
+        assert_eq!(bests.last().unwrap().individual.as_ref().unwrap().calculate_fitness(),
+                   simulation.simulation_result.fittest[0].fitness);
+    }
+
+    #[test]
+    fn migration_adaptive() {
+        let mut builder = SimulationBuilder::<(), Sphere>::new()
+            .iterations(10)
+            .migration_interval(1)
+            .migration_adaptive(true);
+
+        for id in 1..4 {
+            builder = builder.add_population(PopulationBuilder::<(), Sphere>::new()
+                .set_data_source(())
+                .set_id(id)
+                .individuals(10)
+                .finalize().unwrap());
+        }
+
+        let mut simulation = builder.finalize().unwrap();
+        simulation.initialize();
+
+        // No improvement measured yet
+        assert_eq!(simulation.emigrants(), vec![1, 1, 1]);
+
+        for (population, improvement) in simulation.habitat.iter_mut().zip(&[2.0, 1.0, 0.0]) {
+            population.migration_fitness = population.population[0].fitness + improvement;
+        }
+
+        assert_eq!(simulation.emigrants(), vec![2, 1, 0]);
+        // Asking does not reset the measured improvement
+        assert_eq!(simulation.emigrants(), vec![2, 1, 0]);
+
+        simulation.record_migration_fitness();
+        assert_eq!(simulation.emigrants(), vec![1, 1, 1]);
+        simulation.run().unwrap();
+    }
+
+    #[test]
+    fn total_time_accumulates() {
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(20)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.step().unwrap();
+        simulation.step().unwrap();
+        let time_before = simulation.simulation_result.total_time_in_ms;
+
+        simulation.run().unwrap();
+
+        assert!(time_before > 0.0);
+        assert!(simulation.simulation_result.total_time_in_ms > time_before);
+
+        // A simulation that continues with a saved result adds its time to the saved one
+        let saved = simulation.simulation_result.clone();
+        let population = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(20)
+            .finalize().unwrap();
+
+        let mut simulation = SimulationBuilder::<(), Sphere>::new()
+            .iterations(20)
+            .add_population(population)
+            .finalize().unwrap();
+
+        simulation.simulation_result = saved.clone();
+        simulation.step().unwrap();
+
+        assert!(simulation.simulation_result.total_time_in_ms > saved.total_time_in_ms);
     }
 }
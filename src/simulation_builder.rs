@@ -12,10 +12,23 @@
 //!
 
 use std;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+#[cfg(feature = "serde")]
+use std::path::Path;
 
-use simulation::{Simulation, SimulationType, SimulationResult};
-use individual::{Individual};
-use population::Population;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+#[cfg(feature = "profiling")]
+use profiling::Profile;
+use simulation::{Simulation, SimulationType, SimulationResult, SimulationEvent, Pipeline, ESScheme, Executor};
+use individual::{Individual, FitnessGoal};
+use population::{Population, InfeasibilityRecovery};
+#[cfg(feature = "serde")]
+use autosave::Autosave;
 
 /// This is a helper struct in order to build (configure) a valid simulation.
 /// See builder pattern: https://en.wikipedia.org/wiki/Builder_pattern
@@ -27,8 +40,49 @@ pub struct SimulationBuilder<S: Send + Sync, T: Individual + Send + Sync> {
 quick_error! {
     #[derive(Debug)]
     pub enum Error {
+        /// No stop criterion was set and not all populations have their own stop criterion
+        NoStopCriterion {}
         /// The number of iteration is too low, should be >= 10
         EndIterationTooLow {}
+        /// The window of the convergence stop criterion must be >= 1
+        ConvergenceWindowTooLow {}
+        /// The list of stop criteria that must all be reached must not be empty
+        StopCriteriaEmpty {}
+        /// The relative gap stop criterion needs a target optimum
+        TargetOptimumMissing {}
+        /// The tolerance must not be negative
+        ToleranceNegative {}
+        /// The improvement epsilon must not be negative
+        ImprovementEpsilonNegative {}
+        /// The number of objective goals must match the number of objectives
+        ObjectiveGoalsMismatch {}
+        /// The number of objective bounds must match the number of objectives and each
+        /// bound must be min < max
+        ObjectiveBoundsInvalid {}
+        /// Two populations have the same id (see `PopulationBuilder::set_id`)
+        DuplicatePopulationId { population_id: u32 } {
+            display("duplicate population id: {}", population_id)
+        }
+        /// The migration topology contains a population id that was not added
+        UnknownMigrationPopulation {}
+        /// The stagnation limit for pruning stalled populations must be >= 1
+        PruneStagnationTooLow {}
+        /// The interval for printing the fittest individual must be >= 1
+        LogIntervalTooLow {}
+        /// The number of individuals per thread must be >= 1
+        IndividualsPerThreadTooLow {}
+        /// The ES scheme needs mu >= 1 and lambda >= 1 (lambda >= mu for the comma selection)
+        /// and does not work with spatial populations
+        ESSchemeInvalid {}
+        /// A replacement policy does not work with `Pipeline::SelectMutateEvaluate` (or an
+        /// ES scheme), see `PopulationBuilder::replacement_policy`
+        ReplacementPolicyInvalid {}
+        /// The number of iterations without a feasible individual before the infeasibility
+        /// recovery is applied must be >= 1
+        InfeasibilityGenerationsTooLow {}
+        /// The maximum number of concurrent fitness calculations must be >= 1
+        #[cfg(feature = "async")]
+        MaxConcurrentEvalsTooLow {}
     }
 }
 
@@ -38,18 +92,76 @@ pub type Result<S, T> = std::result::Result<Simulation<S, T>, Error>;
 impl<S: Send + Sync, T: Individual + Send + Sync> SimulationBuilder<S, T> {
     /// Start with this method, it must always be called as the first one.
     /// It creates a default simulation with some dummy (but invalid) values.
+    /// A stop criterion must be set (`iterations`, `factor`, `fitness`, `convergence`),
+    /// otherwise `finalize()` returns `Error::NoStopCriterion`.
     pub fn new() -> SimulationBuilder<S, T> {
         SimulationBuilder {
             simulation: Simulation {
-                type_of_simulation: SimulationType::EndIteration(10),
+                type_of_simulation: SimulationType::NotConfigured,
+                fitness_history: VecDeque::new(),
+                num_of_objectives: 0,
+                objective_goals: Vec::new(),
+                objective_bounds: Vec::new(),
+                plateau_threshold: None,
+                improvement_epsilon: None,
+                target_optimum: None,
+                tolerance: 1.0e-9,
+                pipeline: Pipeline::MutateEvaluateSelect,
+                es_scheme: None,
+                infeasibility_recovery: None,
                 num_of_threads: 2,
+                individuals_per_thread: None,
+                #[cfg(feature = "indicatif")]
+                progress_bar: false,
+                #[cfg(feature = "serde")]
+                autosave: None,
+                migration_interval: 0,
+                migration_topology: None,
+                migration_adaptive: false,
+                on_reset: None,
+                executor: None,
+                #[cfg(feature = "async")]
+                max_concurrent_evals: None,
+                prune_stagnation: None,
+                log_best_every: None,
+                keep_final_population: false,
+                minimal_memory: false,
+                sequential_populations: false,
+                recent_bests_capacity: 0,
+                recent_bests_individuals: false,
+                events: None,
+                events_individuals: false,
+                final_refinement: 0,
+                rng_stream: 0,
+                auto_tune_selection: false,
+                cancel: Arc::new(AtomicBool::new(false)),
+                pool: None,
                 habitat: Vec::new(),
-                total_time_in_ms: 0.0,
                 simulation_result: SimulationResult {
                     improvement_factor: std::f64::MAX,
+                    normalized_improvement: 0.0,
                     original_fitness: std::f64::MAX,
                     fittest: Vec::new(),
-                    iteration_counter: 0
+                    iteration_counter: 0,
+                    fitness_evaluations: 0,
+                    total_mutations: 0,
+                    successful_mutations: 0,
+                    total_crossovers: 0,
+                    total_repairs: 0,
+                    #[cfg(feature = "profiling")]
+                    profile: Profile::new(),
+                    operator_stats: Vec::new(),
+                    best_snapshots: VecDeque::new(),
+                    refinement: None,
+                    best_validation: None,
+                    selection_pressure_history: Vec::new(),
+                    infeasibility_recoveries: Vec::new(),
+                    total_selections: 0,
+                    final_fitness: Vec::new(),
+                    fitness_history: Vec::new(),
+                    best_share_history: Vec::new(),
+                    final_populations: Vec::new(),
+                    total_time_in_ms: 0.0
                 }
             },
         }
@@ -76,12 +188,377 @@ impl<S: Send + Sync, T: Individual + Send + Sync> SimulationBuilder<S, T> {
         self
     }
 
+    /// Stops the simulation when the fitness of the fittest individual has changed by less
+    /// than `epsilon` over the last `window` iterations and thus sets the simulation type to
+    /// `EndConvergence`. The window must be >= 1.
+    pub fn convergence(mut self, window: u32, epsilon: f64) -> SimulationBuilder<S, T> {
+        self.simulation.type_of_simulation = SimulationType::EndConvergence { window, epsilon };
+        self
+    }
+
+    /// Stops the simulation only when all the given stop criteria are reached and thus sets
+    /// the simulation type to `EndAll`. For example
+    /// `.stop_when_all(vec![SimulationType::EndIteration(100), SimulationType::EndFitness(0.0)])`
+    /// runs at least 100 iterations even if the target fitness is reached earlier, but does
+    /// not stop after 100 iterations until the target fitness is reached. The list must not
+    /// be empty.
+    pub fn stop_when_all(mut self, stop_criteria: Vec<SimulationType>) -> SimulationBuilder<S, T> {
+        self.simulation.type_of_simulation = SimulationType::EndAll(stop_criteria);
+        self
+    }
+
+    /// Sets the known optimum (best possible fitness) of the problem, needed for the
+    /// `relative_gap` stop criterion.
+    pub fn target_optimum(mut self, target_optimum: f64) -> SimulationBuilder<S, T> {
+        self.simulation.target_optimum = Some(target_optimum);
+        self
+    }
+
+    /// Stops the simulation when the remaining gap to the target optimum relative to the
+    /// original gap is less or equal to the given value and thus sets the simulation type to
+    /// `EndRelativeGap`. For example with a known optimal tour length of 387.0,
+    /// `.target_optimum(387.0).relative_gap(0.05)` stops when 95% of the possible improvement
+    /// has been made. `target_optimum` must be set.
+    pub fn relative_gap(mut self, gap: f64) -> SimulationBuilder<S, T> {
+        self.simulation.type_of_simulation = SimulationType::EndRelativeGap(gap);
+        self
+    }
+
+    /// Sets the tolerance for the `EndFitness` and `EndFactor` stop criteria: the simulation
+    /// stops if `fitness <= end_fitness + tolerance` (the fitness is always minimized).
+    /// Default value is 1.0e-9.
+    pub fn tolerance(mut self, tolerance: f64) -> SimulationBuilder<S, T> {
+        self.simulation.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the minimal fitness improvement for a mutation to count as successful (see
+    /// `SimulationResult::successful_mutations`): a mutated individual must be fitter than
+    /// `parent_fitness - improvement_epsilon`, so that floating point noise does not inflate
+    /// the success ratio. If not set the tolerance is used (see `tolerance`).
+    pub fn improvement_epsilon(mut self, improvement_epsilon: f64) -> SimulationBuilder<S, T> {
+        self.simulation.improvement_epsilon = Some(improvement_epsilon);
+        self
+    }
+
+    /// Uses several objectives (fitness values) instead of one scalar fitness, they are
+    /// compared lexicographically: minimize the first objective, then the second one and so
+    /// on. The objectives are calculated with `Individual::calculate_fitness_multi` which must
+    /// return `count` values. The fitness reported and used for the stop criteria is the first
+    /// objective.
+    pub fn lexicographic_objectives(mut self, count: usize) -> SimulationBuilder<S, T> {
+        self.simulation.num_of_objectives = count;
+        self
+    }
+
+    /// Enables plateau detection: if the variance of the fitness of all the individuals in a
+    /// population drops below the given threshold, selection can't make any progress. In that
+    /// case all individuals except the fittest one get a hyper-mutation kick (they are mutated
+    /// `population::HYPER_MUTATION_FACTOR` times as often as usual). This is a local, softer
+    /// alternative to the reset limit. See `PopulationStats::plateau_kick`.
+    pub fn plateau_threshold(mut self, plateau_threshold: f64) -> SimulationBuilder<S, T> {
+        self.simulation.plateau_threshold = Some(plateau_threshold);
+        self
+    }
+
+    /// Sets for each objective (see `lexicographic_objectives`) if it is minimized or
+    /// maximized, so that maximized objectives (like quality) don't have to be negated by the
+    /// user. The number of goals must match the number of objectives. Note that the fitness
+    /// (first objective) and the stop criteria still use the internal "lower is better" value,
+    /// use `Simulation::best_objectives` for the original values. By default all objectives are minimized.
+    pub fn objective_goals(mut self, objective_goals: Vec<FitnessGoal>) -> SimulationBuilder<S, T> {
+        self.simulation.objective_goals = objective_goals;
+        self
+    }
+
+    /// Sets the known (plausible) range (min, max) of each objective (see
+    /// `lexicographic_objectives`), with their original sign. `Simulation::normalize_objectives`
+    /// uses them to map each objective to [0.0, 1.0], for code where the scale of the
+    /// objectives matters (for example a distance between objective vectors). The
+    /// lexicographic comparison doesn't depend on the scale, so the individuals are still
+    /// compared with (and keep) their raw objectives. The number of bounds must match the
+    /// number of objectives, and each min must be below its max. By default there are no bounds.
+    pub fn objective_bounds(mut self, objective_bounds: Vec<(f64, f64)>) -> SimulationBuilder<S, T> {
+        self.simulation.objective_bounds = objective_bounds;
+        self
+    }
+
+    /// Sets the order of the steps in each iteration for all populations:
+    /// `Pipeline::MutateEvaluateSelect` (default) mutates copies of all individuals, evaluates
+    /// them and keeps the fittest of parents and copies. `Pipeline::SelectMutateEvaluate`
+    /// selects the fittest `survival_rate` fraction of each population as parents first and
+    /// only mutates and evaluates their offspring, which needs fewer fitness evaluations per
+    /// iteration. Use a survival rate below 1.0 with it, for example 0.5. The generation gap
+    /// is only used by the default pipeline.
+    pub fn pipeline(mut self, pipeline: Pipeline) -> SimulationBuilder<S, T> {
+        self.simulation.pipeline = pipeline;
+        self
+    }
+
+    /// Configures all populations as the given textbook evolution strategy in one step: each
+    /// population gets `mu + lambda` individuals, the `mu` fittest are the parents, each of the
+    /// `lambda` offspring is a copy of a uniformly chosen parent that is mutated once. With
+    /// `ESScheme::MuPlusLambda` (and `OnePlusOne`) the parents compete with their offspring,
+    /// with `ESScheme::MuCommaLambda` only the offspring survive.
+    /// This uses `Pipeline::SelectMutateEvaluate` and overrides `pipeline` and the size,
+    /// mutation rates, survival rate, selection strategy and generation gap of the
+    /// populations. Spatial populations (see `PopulationBuilder::spatial`) are not supported.
+    /// By default no scheme is used.
+    pub fn es_scheme(mut self, es_scheme: ESScheme) -> SimulationBuilder<S, T> {
+        self.simulation.es_scheme = Some(es_scheme);
+        self
+    }
+
+    /// What a population does if none of its individuals has been feasible (see
+    /// `Individual::constraint_violation`) for the given number of iterations in a row, see
+    /// `InfeasibilityRecovery`. Each application is recorded in
+    /// `SimulationResult::infeasibility_recoveries` and sent as
+    /// `SimulationEvent::InfeasibilityRecovery` (see `events`).
+    /// By default nothing is done.
+    pub fn infeasibility_recovery(mut self, policy: InfeasibilityRecovery, generations: u32)
+            -> SimulationBuilder<S, T> {
+        self.simulation.infeasibility_recovery = Some((policy, generations));
+        self
+    }
+
     /// Sets the number of threads in order to speed up the simulation.
     pub fn threads(mut self, threads: usize) -> SimulationBuilder<S, T> {
         self.simulation.num_of_threads = threads;
         self
     }
 
+    /// Uses one thread per `individuals` individuals (of all populations together) instead
+    /// of a fixed number of threads, at least one and at most the number of available cores.
+    /// The number of threads is calculated again at the beginning of each `run()` or `step()`,
+    /// so it follows populations that grow or shrink (see `Simulation::thread_count`).
+    /// This overrides `threads`.
+    pub fn threads_per_individuals(mut self, individuals: usize) -> SimulationBuilder<S, T> {
+        self.simulation.individuals_per_thread = Some(individuals);
+        self
+    }
+
+    /// Shows a progress bar with the current iteration and fittest fitness while the
+    /// simulation runs. For stop criteria other than `EndIteration` the total number of
+    /// iterations is unknown, so a spinner is shown instead.
+    /// Only available with the `indicatif` feature.
+    #[cfg(feature = "indicatif")]
+    pub fn progress_bar(mut self, progress_bar: bool) -> SimulationBuilder<S, T> {
+        self.simulation.progress_bar = progress_bar;
+        self
+    }
+
+    /// Writes the fittest individual as JSON to the given file. If `on_improvement` is true
+    /// the file is written every time a new fittest individual is found, otherwise only once
+    /// at the end of the simulation. The file is written to a temporary file first and then
+    /// renamed, so a crash never leaves a half written file.
+    /// Only available with the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub fn autosave_best<P: AsRef<Path>>(mut self, path: P, on_improvement: bool) -> SimulationBuilder<S, T>
+        where T: Serialize {
+        self.simulation.autosave = Some(Autosave::new(path, on_improvement));
+        self
+    }
+
+    /// Enables migration between the populations (island model): every `migration_interval`
+    /// iterations the fittest individual of each population replaces the least fit individual
+    /// of the destination population(s), see `migration_topology`. Default value is 0
+    /// (no migration). Ignored with `sequential_populations`.
+    pub fn migration_interval(mut self, migration_interval: u32) -> SimulationBuilder<S, T> {
+        self.simulation.migration_interval = migration_interval;
+        self
+    }
+
+    /// Sets which populations send migrants to which ones, as a list of directed edges
+    /// (source population id, destination population id). This allows star, fully connected
+    /// or custom topologies. If not set the populations form a ring in the order they were
+    /// added.
+    pub fn migration_topology(mut self, migration_topology: Vec<(usize, usize)>) -> SimulationBuilder<S, T> {
+        self.simulation.migration_topology = Some(migration_topology);
+        self
+    }
+
+    /// Enables adaptive migration: instead of always sending its fittest individual, each
+    /// population sends a number of its fittest individuals that depends on how much it
+    /// improved since the last migration. A population that improved as much as the average
+    /// of all populations sends one individual, twice as much sends two and a stagnant
+    /// population sends none. So the good genes of fast improving islands spread faster.
+    /// Default value is false.
+    pub fn migration_adaptive(mut self, migration_adaptive: bool) -> SimulationBuilder<S, T> {
+        self.simulation.migration_adaptive = migration_adaptive;
+        self
+    }
+
+    /// Sets a callback that is called with the population id and the current iteration every
+    /// time a population is reset. A population resets when its reset counter (increased
+    /// once per iteration) gets greater than its reset limit, at the beginning of the
+    /// iteration before any mutation. The callback is called right after the new individuals
+    /// were created, at that point the reset counter is already zero again and the reset limit
+    /// has been increased.
+    pub fn on_reset(mut self, on_reset: Box<dyn FnMut(usize, u32) + Send>) -> SimulationBuilder<S, T> {
+        self.simulation.on_reset = Some(on_reset);
+        self
+    }
+
+    /// Hands the mutation and fitness evaluation jobs (the expensive part of each iteration)
+    /// to the given executor instead of the built-in thread pool, for example to an existing
+    /// thread pool of the application to avoid too many threads. The executor is called once
+    /// per iteration with all the jobs of that iteration (one per mutated individual) and may
+    /// run them in any order and on any thread, but it must not return before all of them
+    /// are finished, since they borrow the individuals. So the jobs are not `'static` and
+    /// can't be sent to a pool that only takes `'static` jobs (like `std::thread::spawn` or
+    /// most `execute` methods of thread pools), only to a scope that waits for them, for
+    /// example `rayon::scope`, `std::thread::scope` or `jobsteal::Pool::scope` of an existing
+    /// pool (see `Executor`). A panic in a job is caught and
+    /// reported as `RunError::WorkerPanicked`. The other phases of an iteration still use the
+    /// built-in threads (see `threads`). By default the built-in thread pool is used.
+    /// The built-in pool distributes the individuals of all populations over all threads
+    /// (work stealing), so a population is not bound to a thread, unless it is pinned to a
+    /// core (see `PopulationBuilder::cpu_affinity`). For any other placement (for example
+    /// threads pinned to the cores of one NUMA node), run the jobs on such threads in the
+    /// executor.
+    pub fn executor(mut self, executor: Executor) -> SimulationBuilder<S, T> {
+        self.simulation.executor = Some(executor);
+        self
+    }
+
+    /// Calculates the fitness with `Individual::calculate_fitness_async` instead of the
+    /// synchronous methods, with at most `max_concurrent_evals` calculations awaited at the
+    /// same time. This overlaps the waiting times (I/O latency) of the individuals, unlike the
+    /// threads (see `threads`) that run CPU bound work in parallel. The mutation still runs on
+    /// the threads, the futures all run on the thread that calls `run()` or `step()`.
+    /// This is used for the initial population and the mutated individuals of each iteration,
+    /// the few other individuals (for example new ones after a reset or the offspring of
+    /// `PopulationBuilder::survival_rate`) still use the synchronous fitness calculation.
+    /// It replaces the executor (see `executor`) for the fitness calculation and is not used
+    /// for lexicographic objectives (see `lexicographic_objectives`).
+    /// Only available with the `async` feature. By default the fitness is calculated
+    /// synchronously.
+    #[cfg(feature = "async")]
+    pub fn max_concurrent_evals(mut self, max_concurrent_evals: usize) -> SimulationBuilder<S, T> {
+        self.simulation.max_concurrent_evals = Some(max_concurrent_evals);
+        self
+    }
+
+    /// Removes every population whose fittest individual has not improved (by more than the
+    /// improvement epsilon) for `stagnation` iterations, so that the threads work on the
+    /// productive populations only. The population with the fittest individual is never
+    /// removed, and the global fittest individual is always kept in the simulation result.
+    pub fn prune_stalled(mut self, stagnation: u32) -> SimulationBuilder<S, T> {
+        self.simulation.prune_stagnation = Some(stagnation);
+        self
+    }
+
+    /// Logs the fitness of the fittest individual every `interval` iterations, together
+    /// with its description if the individual provides one (see `Individual::describe`).
+    /// With the `log` feature this is an `info!` message of the `log` crate, otherwise it is
+    /// printed to stdout. This is a cheap way to monitor a long running simulation.
+    /// By default nothing is logged.
+    pub fn log_best_every(mut self, interval: u32) -> SimulationBuilder<S, T> {
+        self.simulation.log_best_every = Some(interval);
+        self
+    }
+
+    /// Keeps all the individuals of all populations in the simulation result after the
+    /// simulation (and after each `Simulation::step`), see `SimulationResult::final_population`
+    /// and `SimulationResult::export_population`. This needs memory for a copy of all
+    /// populations. Default value is false.
+    pub fn keep_final_population(mut self, keep_final_population: bool) -> SimulationBuilder<S, T> {
+        self.simulation.keep_final_population = keep_final_population;
+        self
+    }
+
+    /// Enables the minimal memory mode for very long runs: nothing is recorded that grows
+    /// with the number of iterations or is not needed for the simulation itself.
+    /// In this mode:
+    /// - `SimulationResult::fittest` only contains the current fittest individual.
+    /// - `SimulationResult::fitness_history`, `best_share_history` and `final_fitness` stay
+    ///   empty, so `convergence_rate`, `estimated_takeover_time` and `fitness_histogram`
+    ///   return nothing useful.
+    /// - The final populations are not kept (see `keep_final_population`).
+    /// - Lineage tracking is disabled for all populations (see
+    ///   `PopulationBuilder::track_lineage`).
+    ///
+    /// Still available are the counters of the simulation result (iterations, fitness
+    /// evaluations, mutations, selections), the improvement factor, the original fitness,
+    /// the run time and the current populations (`Simulation::habitat`).
+    /// Default value is false.
+    pub fn minimal_memory(mut self, minimal_memory: bool) -> SimulationBuilder<S, T> {
+        self.simulation.minimal_memory = minimal_memory;
+        self
+    }
+
+    /// Keeps the last `capacity` improvements of the fittest individual (iteration, fitness
+    /// and population id) in a ring buffer, see `SimulationResult::recent_bests`. If
+    /// `with_individuals` is true each snapshot contains a copy of the fittest individual.
+    /// Unlike the list of all the fittest individuals it never grows beyond the capacity, so
+    /// it can be used for live monitoring, also together with `minimal_memory`.
+    /// Default value is 0 (no snapshots).
+    pub fn recent_bests(mut self, capacity: usize, with_individuals: bool) -> SimulationBuilder<S, T> {
+        self.simulation.recent_bests_capacity = capacity;
+        self.simulation.recent_bests_individuals = with_individuals;
+        self
+    }
+
+    /// Sends the events of the simulation (see `SimulationEvent`) to the given channel while
+    /// the simulation runs, for example to stream each new fittest individual to a client
+    /// immediately. If `with_individuals` is true each `NewBest` event contains a copy of the
+    /// fittest individual, otherwise only its fitness (cloning may be expensive).
+    /// Default value is no channel.
+    pub fn events(mut self, events: Sender<SimulationEvent<T>>, with_individuals: bool) -> SimulationBuilder<S, T> {
+        self.simulation.events = Some(events);
+        self.simulation.events_individuals = with_individuals;
+        self
+    }
+
+    /// Refines the fittest individual once the simulation has finished (at the end of `run()`
+    /// or of the `step()` or `run_for()` call that finishes it) with the given number of local
+    /// search steps (see `Individual::local_search`, for example 2-opt for a TSP tour). Each
+    /// step is applied to the best refined individual so far and kept if it is fitter.
+    /// The fitness before and after the refinement is reported in
+    /// `SimulationResult::refinement`. Default value is 0 (no refinement).
+    pub fn final_refinement(mut self, steps: u32) -> SimulationBuilder<S, T> {
+        self.simulation.final_refinement = steps;
+        self
+    }
+
+    /// Selects another stream of all the seeded random number generators (mutation, selection,
+    /// crossover, see `PopulationBuilder::mutation_seed`). With the same seeds, different
+    /// streams give different but reproducible runs, for example one per machine of a
+    /// distributed experiment. The generators are `rand::XorShiftRng`s, which can't jump ahead:
+    /// instead the stream is mixed with the mutation seed (through SplitMix64) into the seed of
+    /// each generator, so it costs nothing no matter how large it is. Populations without a
+    /// mutation seed ignore this. Default value is 0.
+    pub fn rng_stream(mut self, rng_stream: u64) -> SimulationBuilder<S, T> {
+        self.simulation.rng_stream = rng_stream;
+        self
+    }
+
+    /// Tunes the selection pressure of each population automatically: if the diversity of the
+    /// survivors collapses (see `PopulationStats::diversity`) the pressure is lowered step by
+    /// step, once the diversity has recovered it is raised again up to the configured value.
+    /// A lower pressure means a smaller tournament, a lower rank based pressure or a higher
+    /// Boltzmann temperature (see `PopulationBuilder::selection_strategy`), and a higher
+    /// survival rate (see `PopulationBuilder::survival_rate`). Populations with a survival rate
+    /// of 1.0 and the round robin selection have no pressure to tune.
+    /// The pressure of each iteration is recorded in `SimulationResult::selection_pressure_history`.
+    /// Default value is false.
+    pub fn auto_tune_selection(mut self, auto_tune_selection: bool) -> SimulationBuilder<S, T> {
+        self.simulation.auto_tune_selection = auto_tune_selection;
+        self
+    }
+
+    /// Evolves the populations one after another in each iteration instead of all at once.
+    /// Each population needs a second buffer of its size for the offspring, in this mode the
+    /// buffer is freed after the population is done, so only one buffer at a time is needed.
+    /// This lowers the peak memory for very large populations, but only the individuals of
+    /// one population are mutated and evaluated in parallel and the buffers have to be
+    /// allocated again in every iteration. Migration is disabled in this mode.
+    /// Default value is false.
+    pub fn sequential_populations(mut self, sequential_populations: bool) -> SimulationBuilder<S, T> {
+        self.simulation.sequential_populations = sequential_populations;
+        self
+    }
+
     /// Add a population to the simulation.
     pub fn add_population(mut self, population: Population<S, T>) -> SimulationBuilder<S, T> {
         self.simulation.habitat.push(population);
@@ -90,12 +567,137 @@ impl<S: Send + Sync, T: Individual + Send + Sync> SimulationBuilder<S, T> {
 
     /// This checks the configuration of the simulation and returns an error or Ok if no errors
     /// where found.
-    pub fn finalize(self) -> Result<S, T> {
+    pub fn finalize(mut self) -> Result<S, T> {
+        if let Some(es_scheme) = self.simulation.es_scheme {
+            if !es_scheme.is_valid() || self.simulation.habitat.iter().any(|population| population.grid.is_some()) {
+                return Err(Error::ESSchemeInvalid);
+            }
+
+            let (mu, lambda, comma) = es_scheme.parameters();
+            self.simulation.pipeline = Pipeline::SelectMutateEvaluate;
+
+            for population in &mut self.simulation.habitat {
+                population.apply_es_scheme(mu, lambda, comma);
+            }
+        }
+
+        for population in &mut self.simulation.habitat {
+            population.num_of_objectives = self.simulation.num_of_objectives;
+            population.plateau_threshold = self.simulation.plateau_threshold;
+            population.improvement_epsilon = self.simulation.improvement_epsilon
+                .unwrap_or(self.simulation.tolerance);
+            population.pipeline = self.simulation.pipeline;
+            population.infeasibility_recovery = self.simulation.infeasibility_recovery;
+            population.objective_goals = Arc::new(self.simulation.objective_goals.clone());
+            population.cancel = self.simulation.cancel.clone();
+            population.rng_stream = self.simulation.rng_stream;
+            population.auto_tune_selection = self.simulation.auto_tune_selection;
+
+            if self.simulation.minimal_memory {
+                population.track_lineage = false;
+                population.track_individual_history = false;
+
+                // Drop the records of the initial individuals as well
+                for wrapper in &mut population.population {
+                    wrapper.lineage = None;
+                }
+            }
+        }
+
+        if self.simulation.minimal_memory {
+            self.simulation.keep_final_population = false;
+        }
+
+        // Ids are used to find populations (migration, inject, mutation rates), so they must
+        // be unique
+        let mut ids: Vec<u32> = self.simulation.habitat.iter().map(|population| population.id).collect();
+        ids.sort();
+        if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(Error::DuplicatePopulationId { population_id: pair[0] });
+        }
+
+        let unknown_migration_population = match self.simulation.migration_topology {
+            Some(ref topology) => {
+                let habitat = &self.simulation.habitat;
+                let known = |id: usize| habitat.iter().any(|population| population.id as usize == id);
+
+                topology.iter().any(|&(source, destination)| !known(source) || !known(destination))
+            }
+            None => false
+        };
+
+        // Without a stop criterion of the simulation all populations need their own (see below)
+        let simulation_stop_criterion = match self.simulation.type_of_simulation {
+            SimulationType::NotConfigured => None,
+            ref stop_criterion => Some(stop_criterion)
+        };
+        let population_stop_criteria = self.simulation.habitat.iter()
+            .filter_map(|population| population.stop_criterion.as_ref());
+
+        for stop_criterion in simulation_stop_criterion.into_iter().chain(population_stop_criteria) {
+            check_stop_criterion(stop_criterion, self.simulation.target_optimum)?;
+        }
+
         match self.simulation {
-            Simulation { type_of_simulation: SimulationType::EndIteration(0...9), .. } => {
-                Err(Error::EndIterationTooLow)
+            Simulation { type_of_simulation: SimulationType::NotConfigured, ref habitat, .. }
+                if habitat.is_empty() || !habitat.iter().all(|population| population.stop_criterion.is_some()) => {
+                Err(Error::NoStopCriterion)
+            }
+            Simulation { tolerance, .. } if tolerance < 0.0 || tolerance.is_nan() => {
+                Err(Error::ToleranceNegative)
+            }
+            Simulation { improvement_epsilon: Some(improvement_epsilon), .. }
+                if improvement_epsilon < 0.0 || improvement_epsilon.is_nan() => {
+                Err(Error::ImprovementEpsilonNegative)
+            }
+            Simulation { num_of_objectives, ref objective_goals, .. }
+                if !objective_goals.is_empty() && objective_goals.len() != num_of_objectives => {
+                Err(Error::ObjectiveGoalsMismatch)
+            }
+            Simulation { num_of_objectives, ref objective_bounds, .. } if !objective_bounds.is_empty() &&
+                (objective_bounds.len() != num_of_objectives ||
+                 objective_bounds.iter().any(|&(min, max)| min >= max || min.is_nan() || max.is_nan())) => {
+                Err(Error::ObjectiveBoundsInvalid)
+            }
+            _ if unknown_migration_population => {
+                Err(Error::UnknownMigrationPopulation)
+            }
+            Simulation { pipeline: Pipeline::SelectMutateEvaluate, ref habitat, .. }
+                if habitat.iter().any(|population| population.replacement_policy.is_some()) => {
+                Err(Error::ReplacementPolicyInvalid)
+            }
+            Simulation { prune_stagnation: Some(0), .. } => {
+                Err(Error::PruneStagnationTooLow)
+            }
+            Simulation { log_best_every: Some(0), .. } => {
+                Err(Error::LogIntervalTooLow)
+            }
+            Simulation { individuals_per_thread: Some(0), .. } => {
+                Err(Error::IndividualsPerThreadTooLow)
+            }
+            Simulation { infeasibility_recovery: Some((_, 0)), .. } => {
+                Err(Error::InfeasibilityGenerationsTooLow)
+            }
+            #[cfg(feature = "async")]
+            Simulation { max_concurrent_evals: Some(0), .. } => {
+                Err(Error::MaxConcurrentEvalsTooLow)
             }
             _ => Ok(self.simulation),
         }
     }
 }
+
+/// Checks the given stop criterion (of the simulation or of a population), the criteria of
+/// `SimulationType::EndAll` recursively.
+fn check_stop_criterion(stop_criterion: &SimulationType, target_optimum: Option<f64>) -> std::result::Result<(), Error> {
+    match *stop_criterion {
+        SimulationType::NotConfigured => Err(Error::NoStopCriterion),
+        SimulationType::EndIteration(0...9) => Err(Error::EndIterationTooLow),
+        SimulationType::EndConvergence { window: 0, .. } => Err(Error::ConvergenceWindowTooLow),
+        SimulationType::EndRelativeGap(_) if target_optimum.is_none() => Err(Error::TargetOptimumMissing),
+        SimulationType::EndAll(ref stop_criteria) if stop_criteria.is_empty() => Err(Error::StopCriteriaEmpty),
+        SimulationType::EndAll(ref stop_criteria) => stop_criteria.iter()
+            .try_for_each(|stop_criterion| check_stop_criterion(stop_criterion, target_optimum)),
+        _ => Ok(())
+    }
+}
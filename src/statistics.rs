@@ -0,0 +1,252 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//!
+
+use std::cmp::Ordering;
+
+use individual::Individual;
+use simulation::SimulationResult;
+
+/// Which one of two compared simulation results did better.
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Better {
+    /// The result the compare method was called on did better.
+    This,
+    /// The result given as argument did better.
+    Other,
+    /// Both results are equal.
+    Equal,
+}
+
+/// The `Comparison` type. Holds the outcome of comparing two single simulation results,
+/// see `SimulationResult::compare`.
+#[derive(Debug,Clone)]
+pub struct Comparison {
+    /// Which result reached the better (lower) fitness.
+    pub fitness: Better,
+    /// Which result needed fewer fitness evaluations.
+    pub fitness_evaluations: Better,
+    /// Which result needed less time.
+    pub time: Better,
+}
+
+/// The `StatSummary` type. Holds the outcome of a Mann-Whitney U test on the best fitness of
+/// two sets of simulation runs, see `compare_runs`.
+#[derive(Debug,Clone)]
+pub struct StatSummary {
+    /// The U statistic of the first set of runs.
+    pub u_statistic: f64,
+    /// The z score (normal approximation) of the U statistic. A negative value means that
+    /// the first set of runs tends to reach a better (lower) fitness.
+    pub z_score: f64,
+    /// Two sided p value. A low value (for example < 0.05) means that the difference between
+    /// the two sets of runs is statistically significant.
+    pub p_value: f64,
+    /// The median of the best fitness of the first set of runs.
+    pub median_a: f64,
+    /// The median of the best fitness of the second set of runs.
+    pub median_b: f64,
+}
+
+/// Maps an ordering of "lower is better" values to the `Better` type.
+pub fn better_from_ordering(ordering: Ordering) -> Better {
+    match ordering {
+        Ordering::Less => Better::This,
+        Ordering::Greater => Better::Other,
+        Ordering::Equal => Better::Equal,
+    }
+}
+
+/// Compares two sets of simulation runs (for example with two different configurations)
+/// using the best fitness reached by each run. Since evolutionary algorithms are stochastic
+/// a single run says nothing, so this runs a Mann-Whitney U test (normal approximation with
+/// tie correction) on both sets.
+pub fn compare_runs<T: Individual + Send + Sync>(runs_a: &[SimulationResult<T>],
+                                                 runs_b: &[SimulationResult<T>]) -> StatSummary {
+    let fitness_a: Vec<f64> = runs_a.iter().map(|result| result.fittest[0].fitness).collect();
+    let fitness_b: Vec<f64> = runs_b.iter().map(|result| result.fittest[0].fitness).collect();
+
+    mann_whitney(&fitness_a, &fitness_b)
+}
+
+/// Runs the Mann-Whitney U test on two samples.
+pub fn mann_whitney(sample_a: &[f64], sample_b: &[f64]) -> StatSummary {
+    let n1 = sample_a.len() as f64;
+    let n2 = sample_b.len() as f64;
+    let n = n1 + n2;
+
+    // Sort all values together, remember where they came from (true = sample_a)
+    let mut values: Vec<(f64, bool)> = sample_a.iter().map(|value| (*value, true))
+        .chain(sample_b.iter().map(|value| (*value, false)))
+        .collect();
+    values.sort_by(|a, b| a.0.partial_cmp(&b.0).expect("Fitness is NaN"));
+
+    // Assign ranks, tied values get the average rank
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut start = 0;
+
+    while start < values.len() {
+        let mut end = start + 1;
+        while end < values.len() && values[end].0 == values[start].0 {
+            end += 1;
+        }
+
+        let ties = (end - start) as f64;
+        let rank = (start + end + 1) as f64 / 2.0;
+
+        for value in &values[start..end] {
+            if value.1 {
+                rank_sum_a += rank;
+            }
+        }
+
+        tie_correction += ties * ties * ties - ties;
+        start = end;
+    }
+
+    let u_statistic = rank_sum_a - (n1 * (n1 + 1.0) / 2.0);
+    let mean = n1 * n2 / 2.0;
+    let variance = if n > 1.0 {
+        (n1 * n2 / 12.0) * ((n + 1.0) - tie_correction / (n * (n - 1.0)))
+    } else {
+        0.0
+    };
+
+    let (z_score, p_value) = if variance > 0.0 {
+        let z_score = (u_statistic - mean) / variance.sqrt();
+        (z_score, (2.0 * (1.0 - normal_cdf(z_score.abs()))).min(1.0))
+    } else {
+        (0.0, 1.0)
+    };
+
+    StatSummary {
+        u_statistic,
+        z_score,
+        p_value,
+        median_a: median(sample_a),
+        median_b: median(sample_b),
+    }
+}
+
+/// Calculates the mean of the given values. Returns NaN for an empty slice.
+pub fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / (values.len() as f64)
+}
+
+/// Calculates the (sample) standard deviation of the given values. Returns 0.0 if there are
+/// less than two values.
+pub fn standard_deviation(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = mean(values);
+    let sum = values.iter().map(|value| (value - mean) * (value - mean)).sum::<f64>();
+
+    (sum / ((values.len() - 1) as f64)).sqrt()
+}
+
+/// Calculates the median of the given values. Returns NaN for an empty slice.
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("Fitness is NaN"));
+
+    let middle = sorted.len() / 2;
+
+    if sorted.len() % 2 == 0 {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    }
+}
+
+/// Calculates the given percentile (0.0 - 100.0) of the values with linear interpolation
+/// between the closest ranks. Returns NaN for an empty slice.
+pub fn percentile(values: &[f64], percentile: f64) -> f64 {
+    if values.is_empty() {
+        return f64::NAN;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("Fitness is NaN"));
+
+    let rank = (percentile / 100.0).clamp(0.0, 1.0) * ((sorted.len() - 1) as f64);
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+/// Cumulative distribution function of the standard normal distribution.
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / 2.0_f64.sqrt()))
+}
+
+/// Error function, approximation from Abramowitz and Stegun (7.1.26), max error: 1.5e-7
+fn erf(x: f64) -> f64 {
+    let sign = if x < 0.0 { -1.0 } else { 1.0 };
+    let x = x.abs();
+
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let y = 1.0 - (((((1.061405429 * t - 1.453152027) * t) + 1.421413741) * t - 0.284496736) * t +
+        0.254829592) * t * (-x * x).exp();
+
+    sign * y
+}
+
+#[cfg(test)]
+mod test {
+    use super::{mann_whitney, median, mean, percentile, standard_deviation};
+
+    #[test]
+    fn median1() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 2.0, 3.0]), 2.5);
+    }
+
+    #[test]
+    fn percentile1() {
+        assert_eq!(percentile(&[4.0, 1.0, 3.0, 2.0, 5.0], 50.0), 3.0);
+        assert_eq!(percentile(&[4.0, 1.0, 3.0, 2.0, 5.0], 100.0), 5.0);
+        assert_eq!(percentile(&[1.0, 2.0], 25.0), 1.25);
+    }
+
+    #[test]
+    fn mean_and_standard_deviation() {
+        assert_eq!(mean(&[1.0, 2.0, 3.0, 6.0]), 3.0);
+        assert_eq!(standard_deviation(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]), (32.0_f64 / 7.0).sqrt());
+        assert_eq!(standard_deviation(&[5.0]), 0.0);
+    }
+
+    #[test]
+    fn mann_whitney_same() {
+        let summary = mann_whitney(&[1.0, 2.0, 3.0, 4.0], &[1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(summary.u_statistic, 8.0);
+        assert!(summary.p_value > 0.99);
+    }
+
+    #[test]
+    fn mann_whitney_different() {
+        let summary = mann_whitney(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0],
+                                   &[11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0]);
+
+        assert_eq!(summary.u_statistic, 0.0);
+        assert!(summary.z_score < 0.0);
+        assert!(summary.p_value < 0.01);
+    }
+}
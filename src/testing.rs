@@ -0,0 +1,98 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.2 (2016.07.xx)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem
+//!
+//! Only available with the `testing` feature.
+
+use individual::Individual;
+use simulation::Simulation;
+
+/// Runs the simulation returned by `builder_fn` twice and panics if the two runs differ
+/// (fittest fitness, number of iterations, number of fitness evaluations or the fitness of
+/// the final population). Use it in a test to verify that an `Individual` implementation has
+/// no hidden non-determinism, for example `mutate` using `rand::thread_rng` or iterating over a
+/// `HashMap`. `builder_fn` must create the individuals from a fixed seed every time.
+///
+/// With `PopulationBuilder::mutation_seed` the framework seeds the random number generator of
+/// all the features that need randomness: a `Mutator`, `seed_individuals`, an `initializer`
+/// (also for the new individuals after a reset, see `PopulationBuilder::reset_limit_end`), a
+/// `crossover`, random tie breaks and the random selection strategies (like
+/// `SelectionStrategy::RankBased`). Without a mutation seed these use unseeded generators and
+/// must not be used in a reproducible simulation. Without an initializer the new individuals
+/// come from `Individual::new`, which must not use random numbers then.
+/// Time based stop criteria or callbacks must not be used either.
+/// The seeded generators are derived from the mutation seeds, which can be saved and restored
+/// with `Simulation::mutation_seeds` and `Simulation::set_mutation_seeds`. The state of the
+/// thread local random number generator can't, so a simulation that uses it can't be resumed
+/// bit-exact either.
+pub fn assert_reproducible<S, T, F>(mut builder_fn: F)
+    where S: Send + Sync, T: Individual + Send + Sync + Clone, F: FnMut() -> Simulation<S, T> {
+    let mut first = builder_fn();
+    let mut second = builder_fn();
+
+    first.run().expect("first run failed");
+    second.run().expect("second run failed");
+
+    let first = &first.simulation_result;
+    let second = &second.simulation_result;
+
+    assert_eq!(first.fittest[0].fitness, second.fittest[0].fitness,
+        "the fittest fitness differs between two runs");
+    assert_eq!(first.iteration_counter, second.iteration_counter,
+        "the number of iterations differs between two runs");
+    assert_eq!(first.fitness_evaluations, second.fitness_evaluations,
+        "the number of fitness evaluations differs between two runs");
+    assert_eq!(first.final_fitness, second.final_fitness,
+        "the final population differs between two runs");
+}
+
+#[cfg(test)]
+mod test {
+    use benchmarks::{Sphere, DIMENSION};
+    use population_builder::PopulationBuilder;
+    use simulation::Simulation;
+    use simulation_builder::SimulationBuilder;
+
+    use super::assert_reproducible;
+
+    /// A small simulation, all randomness comes from the mutation seed if `seeded` is true.
+    fn build(seeded: bool) -> Simulation<(), Sphere> {
+        let mut builder = PopulationBuilder::<(), Sphere>::new()
+            .set_data_source(())
+            .individuals(10)
+            .increasing_mutation_rate()
+            .reset_limit_end(0);
+
+        if seeded {
+            builder = builder.mutation_seed(1);
+        }
+
+        let population = builder
+            .seed_individuals(vec![Sphere { x: vec![2.0; DIMENSION] }], 5)
+            .finalize().unwrap();
+
+        SimulationBuilder::<(), Sphere>::new()
+            .iterations(20)
+            .threads(2)
+            .add_population(population)
+            .finalize().unwrap()
+    }
+
+    #[test]
+    fn seeded_is_reproducible() {
+        assert_reproducible(|| build(true));
+    }
+
+    #[test]
+    #[should_panic(expected = "differs between two runs")]
+    fn unseeded_is_not_reproducible() {
+        assert_reproducible(|| build(false));
+    }
+}
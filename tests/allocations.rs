@@ -0,0 +1,100 @@
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Benchmark of the allocations saved by `Individual::reuse_from` on the TSP benchmark.
+//! It replaces the global allocator to count the allocations, so it is a test binary of its
+//! own: `cargo test --release --test allocations -- --ignored --nocapture`
+
+extern crate rand;
+
+// Internal crates
+extern crate darwin_rs;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::Rng;
+
+// Internal modules
+use darwin_rs::benchmarks::Tsp;
+use darwin_rs::individual::Individual;
+use darwin_rs::population_builder::PopulationBuilder;
+use darwin_rs::simulation_builder::SimulationBuilder;
+
+/// Counts all the allocations of this test binary, see `tsp_allocations`.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// The same as `Tsp`, but with the default `reuse_from` that clones the tour.
+#[derive(Debug, Clone)]
+struct ClonedTsp(Tsp);
+
+impl Individual for ClonedTsp {
+    fn new<S>(data_source: S) -> ClonedTsp {
+        ClonedTsp(Tsp::new(data_source))
+    }
+
+    fn mutate(&mut self) {
+        self.0.mutate();
+    }
+
+    fn mutate_with_rng(&mut self, rng: &mut dyn Rng) {
+        self.0.mutate_with_rng(rng);
+    }
+
+    fn calculate_fitness(&self) -> f64 {
+        self.0.calculate_fitness()
+    }
+}
+
+/// Returns the average number of allocations per iteration of a TSP simulation. The first
+/// iteration (the initial population and the thread pool) is not measured.
+fn allocations_per_iteration<T: Individual + Send + Sync + Clone>(iterations: u32) -> f64 {
+    let population = PopulationBuilder::<(), T>::new()
+        .set_data_source(())
+        .individuals(100)
+        .increasing_mutation_rate()
+        .reset_limit_end(0)
+        .finalize().unwrap();
+
+    let mut simulation = SimulationBuilder::<(), T>::new()
+        .iterations(iterations + 1)
+        .threads(1)
+        .add_population(population)
+        .finalize().unwrap();
+
+    simulation.step().unwrap();
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+
+    for _ in 0..iterations {
+        simulation.step().unwrap();
+    }
+
+    (ALLOCATIONS.load(Ordering::Relaxed) - start) as f64 / (iterations as f64)
+}
+
+/// Benchmark: the allocations per iteration with and without recycling the tours of the
+/// individuals that died (`Individual::reuse_from`).
+#[test]
+#[ignore]
+fn tsp_allocations() {
+    let cloned = allocations_per_iteration::<ClonedTsp>(200);
+    let reused = allocations_per_iteration::<Tsp>(200);
+
+    println!("TSP allocations per iteration, clone: {}, reuse_from: {}", cloned, reused);
+    assert!(reused < cloned);
+}